@@ -3,34 +3,109 @@
 //! 负责依赖注入和生命周期管理
 
 use crate::application::services::EnvService;
-use crate::domain::repositories::{EnvRepository, RepositoryFactory, StorageConfig};
+use crate::domain::models::EnvSource;
+use crate::domain::repositories::{EnvRepository, RepositoryFactory, SecretCipher, StorageConfig};
+use crate::domain::secrets::SecretClassifier;
+use crate::infrastructure::crypto::LocalKeyCipher;
+use crate::infrastructure::settings::{self, CONFIG_FILE_NAME, ProcessConfig, Settings};
 use crate::infrastructure::storage::FileEnvRepository;
 use anyhow::Result;
 use std::sync::Arc;
 
 /// 应用程序配置
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone)]
 pub struct AppConfig {
     pub verbose: bool,
     pub storage: StorageConfig,
+    /// set/unset/import 未指定 `--target` 时的层级
+    pub default_target: EnvSource,
+    /// list/export 未指定 `--format` 时的格式名称
+    pub default_format: String,
+    /// 敏感变量名模式
+    pub secret_patterns: Vec<String>,
+    /// `up` 启动的进程（配置中的 `[processes]`）
+    pub processes: Vec<ProcessConfig>,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        let mut config = Self {
+            verbose: false,
+            storage: StorageConfig::default(),
+            default_target: EnvSource::Local,
+            default_format: "env".to_string(),
+            secret_patterns: Vec::new(),
+            processes: Vec::new(),
+        };
+        config.apply(Settings::defaults());
+        config
+    }
+}
+
+impl AppConfig {
+    /// 读取用户与项目配置文件（项目配置优先）
+    pub fn load_settings(storage: &StorageConfig) -> crate::domain::Result<Settings> {
+        let user = settings::load(&storage.user_dir.join(CONFIG_FILE_NAME))?;
+        let project = settings::load(&storage.project_dir.join(CONFIG_FILE_NAME))?;
+        Ok(user.merge(project))
+    }
+
+    /// 应用配置文件中出现的设置
+    pub fn apply(&mut self, settings: Settings) {
+        if let Some(target) = settings.default_target {
+            self.default_target = target;
+        }
+        if let Some(format) = settings.default_format {
+            self.default_format = format;
+        }
+        if let Some(patterns) = settings.secret_patterns {
+            self.secret_patterns = patterns;
+        }
+        if let Some(order) = settings.layer_order {
+            self.storage.layer_order = order;
+        }
+        if let Some(enabled) = settings.cache_enabled {
+            self.storage.cache_enabled = enabled;
+        }
+        if let Some(ttl) = settings.cache_ttl_seconds {
+            self.storage.cache_ttl_seconds = ttl;
+        }
+        if let Some(processes) = settings.processes {
+            self.processes = processes;
+        }
+    }
 }
 
 /// 应用程序容器
 pub struct Application {
+    /// 合并配置文件后的应用配置
+    pub config: AppConfig,
     /// 环境变量服务
     pub env_service: Arc<EnvService>,
 }
 
 impl Application {
     /// 创建应用程序实例
-    pub async fn new(config: AppConfig) -> Result<Self> {
+    ///
+    /// `config` 中的目录与命令行选项保持不变，其余设置从 `config.toml` 读取。
+    pub async fn new(mut config: AppConfig) -> Result<Self> {
+        config.apply(AppConfig::load_settings(&config.storage)?);
+
         // 创建 Repository
         let env_repo: Arc<dyn EnvRepository> =
             Arc::new(FileEnvRepository::new(config.storage.clone()));
 
+        // 创建加密器
+        let cipher: Arc<dyn SecretCipher> =
+            Arc::new(LocalKeyCipher::new(config.storage.user_dir.join("keys")));
+
         // 创建服务
-        let env_service = Arc::new(EnvService::new(env_repo));
-        Ok(Self { env_service })
+        let classifier = SecretClassifier::new(config.secret_patterns.clone());
+        let env_service = Arc::new(EnvService::new(env_repo, cipher, classifier));
+        Ok(Self {
+            config,
+            env_service,
+        })
     }
 }
 
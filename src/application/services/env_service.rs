@@ -1,32 +1,158 @@
 //! 环境变量应用服务
 
-use crate::domain::error::Result;
-use crate::domain::models::{EnvSource, EnvVar, OutputFormat};
-use crate::domain::repositories::EnvRepository;
+use crate::domain::error::{DomainError, Result};
+use crate::domain::format::Format;
+use crate::domain::interpolation::{Interpolator, Reference, unescape};
+use crate::domain::models::{EnvSource, EnvVar};
+use crate::domain::repositories::{
+    Definition, EnvRepository, ImportChanges, SecretCipher, SetOptions,
+};
+use crate::domain::secrets::{MaskedVar, SecretClassifier, is_envelope};
+use serde::Serialize;
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::PathBuf;
 use std::sync::Arc;
 
+/// 导出选项
+#[derive(Debug, Clone, Default)]
+pub struct ExportOptions {
+    /// 输出存储的原始文本，不解析插值
+    pub raw: bool,
+    /// 显示敏感变量的完整值
+    pub reveal: bool,
+    /// 解密加密信封
+    pub decrypt: bool,
+}
+
+/// 导入时对目标层级中已存在变量的处理策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImportStrategy {
+    /// 保留已有值，只添加新变量
+    #[default]
+    Keep,
+    /// 以导入的值覆盖
+    Overwrite,
+    /// 覆盖，并删除导入源中不存在的变量
+    Prune,
+    /// 逐个询问值不同的变量
+    Interactive,
+}
+
+impl ImportStrategy {
+    pub fn parse(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "keep" => Ok(ImportStrategy::Keep),
+            "overwrite" => Ok(ImportStrategy::Overwrite),
+            "prune" => Ok(ImportStrategy::Prune),
+            "interactive" => Ok(ImportStrategy::Interactive),
+            _ => Err(DomainError::InvalidArgument(format!(
+                "无效的导入策略: {}（可选 keep/overwrite/prune/interactive）",
+                s
+            ))),
+        }
+    }
+}
+
+/// 导入结果，各列表按导入源中的顺序排列（`removed` 按目标层级中的顺序）
+#[derive(Debug, Clone, Default)]
+pub struct ImportReport {
+    pub added: Vec<String>,
+    pub updated: Vec<String>,
+    pub unchanged: Vec<String>,
+    pub removed: Vec<String>,
+    /// 值不同但保留了已有值的变量
+    pub kept: Vec<String>,
+}
+
+/// 变量解析过程
+#[derive(Debug, Clone, Serialize)]
+pub struct Explanation {
+    pub key: String,
+    /// 最终值（已解密并解析插值）
+    pub value: String,
+    /// 是否为敏感变量
+    pub secret: bool,
+    /// 生效的定义是否为加密信封
+    pub decrypted: bool,
+    /// 各层定义，按优先级从低到高排列，最后一个生效
+    pub definitions: Vec<Definition>,
+    /// 生效值中直接引用的变量
+    pub references: Vec<Reference>,
+}
+
 /// 环境变量服务
 pub struct EnvService {
     repository: Arc<dyn EnvRepository>,
+    cipher: Arc<dyn SecretCipher>,
+    classifier: SecretClassifier,
 }
 
 impl EnvService {
-    pub fn new(repository: Arc<dyn EnvRepository>) -> Self {
-        Self { repository }
+    pub fn new(
+        repository: Arc<dyn EnvRepository>,
+        cipher: Arc<dyn SecretCipher>,
+        classifier: SecretClassifier,
+    ) -> Self {
+        Self {
+            repository,
+            cipher,
+            classifier,
+        }
+    }
+
+    /// 敏感变量分类器
+    pub fn classifier(&self) -> &SecretClassifier {
+        &self.classifier
     }
 
-    /// 获取变量
+    /// 加密器
+    pub fn cipher(&self) -> Arc<dyn SecretCipher> {
+        self.cipher.clone()
+    }
+
+    /// 对敏感变量做掩码，`reveal` 为 true 时保留原值
+    pub fn mask(&self, vars: Vec<EnvVar>, reveal: bool) -> Vec<MaskedVar> {
+        vars.into_iter()
+            .map(|var| self.classifier.mask(var, reveal))
+            .collect()
+    }
+
+    /// 获取变量，不解析插值（`\$` 还原为 `$`，引用原样保留）
     pub async fn get(&self, key: &str) -> Result<Option<String>> {
         self.repository.get(key).await
     }
 
+    /// 获取变量并解析插值（加密值自动解密）
+    pub async fn get_resolved(&self, key: &str) -> Result<Option<String>> {
+        self.interpolator(true, None).await?.resolve(key)
+    }
+
     /// 设置变量
     pub async fn set(&self, key: &str, value: &str, source: EnvSource) -> Result<()> {
         self.repository.set(key, value, source).await
     }
 
+    /// 按选项设置变量
+    pub async fn set_with_options(
+        &self,
+        key: &str,
+        value: &str,
+        source: EnvSource,
+        options: &SetOptions,
+    ) -> Result<()> {
+        if options.encrypt {
+            let envelope = self.cipher.encrypt(key, value)?;
+            return self
+                .repository
+                .set_with_options(key, &envelope, source, options)
+                .await;
+        }
+
+        self.repository
+            .set_with_options(key, value, source, options)
+            .await
+    }
+
     /// 删除变量
     pub async fn unset(&self, key: &str, source: &EnvSource) -> Result<bool> {
         self.repository.delete(key, source).await
@@ -37,24 +163,157 @@ impl EnvService {
         self.repository.list(source_filter).await
     }
 
-    /// 导出变量
+    /// 列出变量并解析插值，`decrypt` 为 true 时解密加密信封
+    pub async fn list_resolved(
+        &self,
+        source_filter: Option<EnvSource>,
+        decrypt: bool,
+    ) -> Result<Vec<EnvVar>> {
+        Ok(self
+            .resolve_vars(source_filter, decrypt, false, None)
+            .await?
+            .0)
+    }
+
+    /// 列出变量并解析插值（加密值自动解密），system 层取 `system` 而不是当前进程的环境
+    ///
+    /// 用于 shell 钩子：当前进程环境中可能仍是上次加载的值，自引用（如 `PATH=${PATH}:./bin`）
+    /// 需要基于加载前的环境解析，否则每次重新加载都会重复追加。
+    pub async fn list_resolved_with_system(
+        &self,
+        system: HashMap<String, String>,
+    ) -> Result<Vec<EnvVar>> {
+        Ok(self.resolve_vars(None, true, false, Some(system)).await?.0)
+    }
+
+    /// 列出变量并解析插值，无法解密的变量保留存储的值
+    ///
+    /// 用于展示：缺少密钥时其余变量照常解析，同时返回未能解析的变量的解密错误。
+    pub async fn list_resolved_lossy(
+        &self,
+        source_filter: Option<EnvSource>,
+    ) -> Result<(Vec<EnvVar>, Vec<DomainError>)> {
+        self.resolve_vars(source_filter, true, true, None).await
+    }
+
+    /// 逐个解析变量，`lossy` 为 true 时跳过解密错误，否则遇到第一个错误即停止
+    async fn resolve_vars(
+        &self,
+        source_filter: Option<EnvSource>,
+        decrypt: bool,
+        lossy: bool,
+        system: Option<HashMap<String, String>>,
+    ) -> Result<(Vec<EnvVar>, Vec<DomainError>)> {
+        let vars = self.repository.list(source_filter).await?;
+        let mut interpolator = self.interpolator(decrypt, system).await?;
+
+        let mut resolved = Vec::with_capacity(vars.len());
+        let mut failures = Vec::new();
+        for mut var in vars {
+            if decrypt && var.source != EnvSource::System && is_envelope(&var.value) {
+                var.secret = true;
+            }
+            match interpolator.resolve_in(&var.key, var.source) {
+                Ok(Some(value)) => var.value = value,
+                Ok(None) => {}
+                Err(e @ DomainError::Crypto(_)) if lossy => failures.push(e),
+                Err(e) => return Err(e),
+            }
+            resolved.push(var);
+        }
+        Ok((resolved, failures))
+    }
+
+    /// 按格式导出变量
     pub async fn export(
         &self,
         source_filter: Option<EnvSource>,
-        format: OutputFormat,
+        format: &dyn Format,
+        options: &ExportOptions,
     ) -> Result<String> {
-        match format {
-            OutputFormat::Env => self.repository.export(source_filter).await,
-            OutputFormat::Json => {
-                let vars = self.repository.list(source_filter).await?;
-                Ok(serde_json::to_string_pretty(&vars)?)
+        let vars = self.export_vars(source_filter, options).await?;
+        format.render(&vars)
+    }
+
+    /// 按导出选项读取变量（解析插值、解密并掩码）
+    pub async fn export_vars(
+        &self,
+        source_filter: Option<EnvSource>,
+        options: &ExportOptions,
+    ) -> Result<Vec<MaskedVar>> {
+        let vars = if options.raw {
+            self.repository.list(source_filter).await?
+        } else {
+            self.list_resolved(source_filter, options.decrypt).await?
+        };
+        Ok(self.mask(vars, options.reveal))
+    }
+
+    /// 按策略导入已解析的键值对，值使用插值语法（见 [`Format::parse`]）
+    ///
+    /// `confirm` 仅在 `Interactive` 策略下对值不同的变量调用，参数为变量名、当前值与导入值，
+    /// 返回 true 时覆盖。
+    pub async fn import<F>(
+        &self,
+        entries: Vec<(String, String)>,
+        target: EnvSource,
+        strategy: ImportStrategy,
+        mut confirm: F,
+    ) -> Result<ImportReport>
+    where
+        F: FnMut(&str, &str, &str) -> Result<bool> + Send,
+    {
+        let existing = self.repository.list(Some(target)).await?;
+
+        // 同名变量以最后一次出现为准，保留首次出现的位置
+        let mut incoming: Vec<(String, String)> = Vec::new();
+        for (key, value) in entries {
+            match incoming.iter_mut().find(|(k, _)| *k == key) {
+                Some(entry) => entry.1 = value,
+                None => incoming.push((key, value)),
+            }
+        }
+
+        let mut report = ImportReport::default();
+        let mut changes = ImportChanges::default();
+        for (key, value) in &incoming {
+            match existing.iter().find(|v| v.key == *key) {
+                None => {
+                    report.added.push(key.clone());
+                    changes.set.push((key.clone(), value.clone()));
+                }
+                Some(current) if current.template() == *value => report.unchanged.push(key.clone()),
+                Some(current) => {
+                    let overwrite = match strategy {
+                        ImportStrategy::Keep => false,
+                        ImportStrategy::Overwrite | ImportStrategy::Prune => true,
+                        ImportStrategy::Interactive => {
+                            confirm(key, &current.literal_value(), &unescape(value))?
+                        }
+                    };
+                    if overwrite {
+                        report.updated.push(key.clone());
+                        changes.set.push((key.clone(), value.clone()));
+                    } else {
+                        report.kept.push(key.clone());
+                    }
+                }
             }
         }
-    }
 
-    /// 导入 .env 文件
-    pub async fn import(&self, file_path: &Path, target: EnvSource) -> Result<usize> {
-        self.repository.import(file_path, target).await
+        if strategy == ImportStrategy::Prune {
+            for var in &existing {
+                if !incoming.iter().any(|(k, _)| *k == var.key) {
+                    report.removed.push(var.key.clone());
+                    changes.remove.push(var.key.clone());
+                }
+            }
+        }
+
+        if !changes.set.is_empty() || !changes.remove.is_empty() {
+            self.repository.import(&changes, target).await?;
+        }
+        Ok(report)
     }
 
     /// 清除缓存
@@ -62,22 +321,132 @@ impl EnvService {
         self.repository.clear_cache().await;
     }
 
-    /// 获取变量来源信息
-    pub async fn get_variable_info(&self, key: &str) -> Result<Vec<(EnvSource, String)>> {
-        let mut results = Vec::new();
+    /// 各层级包含的文件，按优先级从低到高排列
+    pub async fn source_files(&self) -> Result<Vec<PathBuf>> {
+        self.repository.source_files().await
+    }
+
+    /// 层级对应的文件路径
+    pub fn layer_path(&self, source: &EnvSource) -> Result<PathBuf> {
+        self.repository.layer_path(source)
+    }
+
+    /// 当前激活的 profile
+    pub fn active_profile(&self) -> Option<String> {
+        self.repository.active_profile()
+    }
+
+    /// 当前 profile 的继承链（由近及远）
+    pub async fn profile_chain(&self) -> Result<Vec<String>> {
+        self.repository.profile_chain().await
+    }
+
+    /// 使用新密钥重新加密所有层级中的加密信封（包括未激活的 profile）
+    ///
+    /// 先确认全部信封可以解密，之后才生成新密钥并写回，返回新密钥 ID 与重新加密的数量。
+    pub async fn rotate_keys(&self) -> Result<(String, usize)> {
+        let writable: Vec<EnvSource> = EnvSource::ALL
+            .into_iter()
+            .filter(EnvSource::is_writable)
+            .collect();
 
-        for source in [
-            EnvSource::System,
-            EnvSource::User,
-            EnvSource::Project,
-            EnvSource::Local,
-        ] {
-            if let Some(value) = self.repository.get_from_source(key, &source).await? {
-                results.push((source, value));
+        // 只解密不改写，确认与写回覆盖同一组文件
+        let cipher = self.cipher.clone();
+        let verify = move |key: &str, value: &str| -> Result<Option<String>> {
+            if is_envelope(value) {
+                cipher.decrypt(key, value)?;
             }
+            Ok(None)
+        };
+        for source in &writable {
+            self.repository.rewrite_values(source, &verify).await?;
         }
 
-        Ok(results)
+        let key_id = self.cipher.rotate_key()?;
+        let cipher = self.cipher.clone();
+        let reencrypt = move |key: &str, value: &str| -> Result<Option<String>> {
+            if !is_envelope(value) {
+                return Ok(None);
+            }
+            let plaintext = cipher.decrypt(key, value)?;
+            Ok(Some(cipher.encrypt(key, &plaintext)?))
+        };
+
+        let mut count = 0;
+        for source in &writable {
+            count += self.repository.rewrite_values(source, &reencrypt).await?;
+        }
+
+        Ok((key_id, count))
+    }
+
+    /// 构建覆盖所有层级的插值解析器，`decrypt` 为 true 时在解析到加密信封时解密；
+    /// 给出 `system` 时以其代替当前进程的环境作为 system 层
+    async fn interpolator(
+        &self,
+        decrypt: bool,
+        mut system: Option<HashMap<String, String>>,
+    ) -> Result<Interpolator> {
+        let mut layers = Vec::new();
+
+        for source in self.repository.layer_order() {
+            if source == EnvSource::System
+                && let Some(values) = system.take()
+            {
+                layers.push((source, values));
+                continue;
+            }
+            let mut values = HashMap::new();
+            for var in self.repository.list(Some(source)).await? {
+                values.insert(var.key.clone(), var.template());
+            }
+            layers.push((source, values));
+        }
+
+        let interpolator = Interpolator::new(layers);
+        if !decrypt {
+            return Ok(interpolator);
+        }
+        let cipher = self.cipher.clone();
+        Ok(interpolator.with_decrypt(Box::new(move |key, envelope| cipher.decrypt(key, envelope))))
+    }
+
+    /// 解释变量的解析过程：各层定义、生效的定义、解密与插值
+    pub async fn explain(&self, key: &str) -> Result<Explanation> {
+        let definitions = self.repository.definitions(key).await?;
+        let Some(winner) = definitions.last() else {
+            return Err(DomainError::NotFound(key.to_string()));
+        };
+
+        let decrypted = winner.source != EnvSource::System && is_envelope(&winner.value);
+        let (value, mut references) = self.interpolator(true, None).await?.trace(key)?;
+        let secret = self.is_secret_definition(key, &definitions);
+        for reference in &mut references {
+            let definitions = self.repository.definitions(&reference.name).await?;
+            reference.secret = self.is_secret_definition(&reference.name, &definitions);
+        }
+
+        Ok(Explanation {
+            key: key.to_string(),
+            value: value.unwrap_or_default(),
+            secret,
+            decrypted,
+            definitions,
+            references,
+        })
+    }
+
+    /// 变量是否敏感：任一层以 `# @secret` 标记或加密存储，或变量名匹配敏感模式
+    fn is_secret_definition(&self, key: &str, definitions: &[Definition]) -> bool {
+        definitions
+            .iter()
+            .any(|d| d.secret || (d.source != EnvSource::System && is_envelope(&d.value)))
+            || self.classifier.matches_key(key)
+    }
+
+    /// 层级顺序（优先级从低到高）
+    pub fn layer_order(&self) -> Vec<EnvSource> {
+        self.repository.layer_order()
     }
 
     /// 检查变量冲突（多层级定义）
@@ -85,12 +454,7 @@ impl EnvService {
         let mut conflicts = Vec::new();
         let mut key_sources: HashMap<String, Vec<EnvSource>> = HashMap::new();
 
-        for source in [
-            EnvSource::System,
-            EnvSource::User,
-            EnvSource::Project,
-            EnvSource::Local,
-        ] {
+        for source in self.repository.layer_order() {
             let vars = self.repository.list(Some(source)).await?;
             for var in vars {
                 key_sources.entry(var.key).or_default().push(source);
@@ -102,6 +466,7 @@ impl EnvService {
                 conflicts.push((key, sources));
             }
         }
+        conflicts.sort_by(|a, b| a.0.cmp(&b.0));
 
         Ok(conflicts)
     }
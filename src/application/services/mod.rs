@@ -2,4 +2,4 @@
 
 mod env_service;
 
-pub use env_service::EnvService;
+pub use env_service::{EnvService, Explanation, ExportOptions, ImportReport, ImportStrategy};
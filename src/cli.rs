@@ -1,6 +1,12 @@
 //! CLI 参数定义
 
-use clap::{Parser, Subcommand};
+use crate::commands::list::{ListView, TABLE};
+use crate::commands::run::RunOptions;
+use crate::domain::format::Format;
+use crate::domain::models::EnvSource;
+use crate::infrastructure::formats;
+use clap::{Args, Parser, Subcommand};
+use std::path::PathBuf;
 
 /// EnvCLI - 跨平台环境变量管理工具
 #[derive(Parser)]
@@ -17,6 +23,86 @@ pub struct Cli {
     /// 详细输出模式
     #[arg(short, long, global = true)]
     pub verbose: bool,
+
+    /// 显示敏感变量的完整值（默认掩码）
+    #[arg(long, global = true)]
+    pub reveal: bool,
+
+    /// 激活的 profile（.envcli/profiles/<NAME>.env）
+    #[arg(long, global = true, env = "ENVCLI_PROFILE")]
+    pub profile: Option<String>,
+
+    /// 项目根目录（默认从当前目录向上查找最近的 .envcli）
+    #[arg(short = 'C', long = "project-dir", global = true, value_name = "DIR")]
+    pub project_dir: Option<PathBuf>,
+
+    /// 级联合并祖先目录中的 .envcli/project.env（近者优先）
+    #[arg(long, global = true, env = "ENVCLI_CASCADE")]
+    pub cascade: bool,
+}
+
+impl Cli {
+    /// 本次命令使用的 profile
+    ///
+    /// `--target`/`--source` 中的 `profile:<NAME>` 优先于 `--profile`。
+    #[must_use]
+    pub fn effective_profile(&self) -> Option<String> {
+        let layer = match &self.command {
+            Commands::Set { target, .. }
+            | Commands::Unset { target, .. }
+            | Commands::Import { target, .. } => target.as_deref(),
+            Commands::List { source, .. } | Commands::Export { source, .. } => source.as_deref(),
+            _ => None,
+        };
+
+        layer
+            .and_then(EnvSource::profile_name)
+            .map(str::to_string)
+            .or_else(|| self.profile.clone())
+    }
+}
+
+/// 子进程环境参数（run 与 up 共用）
+#[derive(Args, Debug, Clone)]
+pub struct ChildEnvArgs {
+    /// 临时环境变量 (KEY=VALUE)
+    #[arg(short, long)]
+    pub env: Vec<String>,
+    /// 从文件加载变量
+    #[arg(short, long)]
+    pub from_file: Option<String>,
+    /// 注入存储的原始值，不解析插值
+    #[arg(long)]
+    pub raw: bool,
+    /// 不继承父进程环境，只注入 envcli 管理的变量
+    #[arg(long)]
+    pub clean: bool,
+    /// --clean 时保留的父进程变量（逗号分隔，如 PATH,HOME,TERM）
+    #[arg(long, value_delimiter = ',', requires = "clean")]
+    pub keep: Vec<String>,
+    /// --clean 时保留匹配模式的父进程变量（如 'LC_*'）
+    #[arg(long, value_delimiter = ',', requires = "clean")]
+    pub keep_pattern: Vec<String>,
+    /// 不继承匹配模式的父进程变量（如 'AWS_*'）
+    #[arg(long, value_delimiter = ',', conflicts_with = "clean")]
+    pub drop: Vec<String>,
+}
+
+impl ChildEnvArgs {
+    /// 转换为 run 选项，其余选项取默认值
+    #[must_use]
+    pub fn into_options(self) -> RunOptions {
+        RunOptions {
+            raw: self.raw,
+            from_file: self.from_file,
+            overrides: self.env,
+            clean: self.clean,
+            keep: self.keep,
+            keep_patterns: self.keep_pattern,
+            drop: self.drop,
+            ..Default::default()
+        }
+    }
 }
 
 #[derive(Subcommand)]
@@ -25,6 +111,21 @@ pub enum Commands {
     Get {
         /// 变量名称
         key: String,
+        /// 输出存储的原始值，不解析插值
+        #[arg(long)]
+        raw: bool,
+        /// 同时输出值的来源（精简版 explain）
+        #[arg(long, conflicts_with = "raw")]
+        explain: bool,
+    },
+
+    /// 显示变量的解析过程：各层定义、覆盖关系、解密与插值
+    Explain {
+        /// 变量名称
+        key: String,
+        /// 输出格式 (text/json)
+        #[arg(short, long, default_value = "text")]
+        format: String,
     },
 
     /// 设置环境变量
@@ -33,18 +134,27 @@ pub enum Commands {
         key: String,
         /// 变量值
         value: String,
-        /// 目标层级 (user/project/local)
-        #[arg(short, long, default_value = "local")]
-        target: String,
+        /// 目标层级 (user/project/profile[:NAME]/local)，默认取配置 default_target
+        #[arg(short, long)]
+        target: Option<String>,
+        /// 新变量写入的分节（`# [name]` 注释）
+        #[arg(long)]
+        section: Option<String>,
+        /// 标记为敏感变量（添加 `# @secret` 注释）
+        #[arg(long)]
+        mark_secret: bool,
+        /// 加密后存储（`ENC[...]` 信封）
+        #[arg(long)]
+        secret: bool,
     },
 
     /// 删除环境变量
     Unset {
         /// 变量名称
         key: String,
-        /// 目标层级
-        #[arg(short, long, default_value = "local")]
-        target: String,
+        /// 目标层级，默认取配置 default_target
+        #[arg(short, long)]
+        target: Option<String>,
     },
 
     /// 列出环境变量
@@ -52,8 +162,37 @@ pub enum Commands {
         /// 指定来源
         #[arg(short, long)]
         source: Option<String>,
-        /// 输出格式 (env/json)
-        #[arg(short, long, default_value = "env")]
+        /// 输出格式 (table/env/json/json-flat/yaml/toml/properties/bash/…)，默认取配置 default_format
+        #[arg(short, long)]
+        format: Option<String>,
+        /// 输出存储的原始值，不解析插值
+        #[arg(long)]
+        raw: bool,
+        /// 排序方式 (key/source/modified)
+        #[arg(long, default_value = "key")]
+        sort: String,
+        /// 只列出以此开头的变量
+        #[arg(long)]
+        prefix: Option<String>,
+        /// 只列出变量名或值匹配此正则的变量
+        #[arg(long)]
+        grep: Option<String>,
+        /// 只列出在多个层级中定义的变量
+        #[arg(long)]
+        only_shadowed: bool,
+        /// 在每行后标注来源层级
+        #[arg(long)]
+        show_source: bool,
+    },
+
+    /// 比较两组变量，存在差异时以状态 1 退出
+    Diff {
+        /// 层级 (system/user/project/profile[:NAME]/local)、merged（合并视图）或文件路径
+        a: String,
+        /// 同上
+        b: String,
+        /// 输出格式 (text/json)
+        #[arg(short, long, default_value = "text")]
         format: String,
     },
 
@@ -62,59 +201,153 @@ pub enum Commands {
         /// 指定来源
         #[arg(short, long)]
         source: Option<String>,
-        /// 输出格式
-        #[arg(short, long, default_value = "env")]
-        format: String,
+        /// 输出格式 (env/json/json-flat/yaml/toml/properties/bash/fish/powershell/nu)，默认取配置 default_format
+        #[arg(short, long)]
+        format: Option<String>,
+        /// 输出存储的原始值，不解析插值
+        #[arg(long)]
+        raw: bool,
+        /// 解密加密信封
+        #[arg(long)]
+        decrypt: bool,
+        /// 输出删除这些变量的语句（需 shell 格式），用于撤销之前的加载
+        #[arg(long)]
+        unset: bool,
     },
 
     /// 导入 .env 文件
     Import {
-        /// 文件路径
+        /// 文件路径，`-` 表示从标准输入读取
         file: String,
-        /// 目标层级
-        #[arg(short, long, default_value = "local")]
-        target: String,
+        /// 目标层级，默认取配置 default_target
+        #[arg(short, long)]
+        target: Option<String>,
+        /// 文件格式 (env/json/yaml/toml/properties)，默认按扩展名与内容检测
+        #[arg(short, long)]
+        format: Option<String>,
+        /// 已存在变量的处理策略 (keep/overwrite/prune/interactive)
+        #[arg(long, default_value = "keep")]
+        strategy: String,
     },
 
     /// 运行命令并注入环境变量
     Run {
-        /// 临时环境变量 (KEY=VALUE)
-        #[arg(short, long)]
-        env: Vec<String>,
-        /// 从文件加载变量
-        #[arg(short, long)]
-        from_file: Option<String>,
+        #[command(flatten)]
+        child_env: ChildEnvArgs,
+        /// 只打印子进程将获得的环境及每个变量的来源，不执行命令
+        #[arg(long)]
+        print_env: bool,
+        /// 层级文件或 --from-file 变化且环境改变时重启命令
+        #[arg(long, conflicts_with = "print_env")]
+        watch: bool,
+        /// --watch 时改为向命令发送信号（如 HUP），不重启
+        #[arg(long, requires = "watch")]
+        reload_signal: Option<String>,
+        /// --watch 重启时等待命令响应 SIGTERM 的秒数，超时后强制结束
+        #[arg(long, requires = "watch", default_value_t = 10)]
+        stop_timeout: u64,
+        /// 敏感变量写入临时目录中的文件，子进程获得 KEY_FILE=<路径> 而不是 KEY
+        #[arg(long)]
+        secrets_as_files: bool,
         /// 要执行的命令
-        #[arg(required = true, last = true)]
+        #[arg(required_unless_present = "print_env", last = true)]
         command: Vec<String>,
     },
 
+    /// 按 Procfile 或配置中的 [processes] 同时启动多个进程
+    Up {
+        /// 只启动这些进程（默认全部）
+        names: Vec<String>,
+        /// Procfile 路径（默认为项目根目录下的 Procfile）
+        #[arg(long)]
+        procfile: Option<PathBuf>,
+        /// 停止进程时等待其响应 SIGTERM 的秒数，超时后强制结束
+        #[arg(long, default_value_t = 10)]
+        stop_timeout: u64,
+        #[command(flatten)]
+        child_env: ChildEnvArgs,
+    },
+
+    /// 以合并后的变量启动交互式子 shell（$SHELL），exit 后回到原 shell
+    Shell {
+        /// 已在 envcli shell 中时仍然嵌套启动
+        #[arg(long)]
+        force: bool,
+        /// 注入存储的原始值，不解析插值
+        #[arg(long)]
+        raw: bool,
+    },
+
     /// 显示当前状态
     Status,
 
     /// 诊断问题
     Doctor,
 
+    /// 输出 shell 钩子脚本，进入项目目录时自动加载变量、离开时还原
+    ///
+    /// 用法：在 ~/.bashrc 中加入 `eval "$(envcli hook bash)"`
+    Hook {
+        /// shell 类型 (bash/zsh/fish)
+        shell: String,
+    },
+
+    /// 输出当前目录需要执行的加载/还原语句（由钩子脚本调用）
+    #[command(hide = true)]
+    HookEnv {
+        /// shell 类型 (bash/zsh/fish)
+        #[arg(long)]
+        shell: String,
+    },
+
+    /// 信任当前项目目录，允许 shell 钩子自动加载其变量
+    Allow,
+
+    /// 取消信任当前项目目录
+    Deny,
+
     /// 设置系统级环境变量
     SystemSet {
         /// 变量名称
         key: String,
         /// 变量值
         value: String,
-        /// 作用域 (global/machine)
+        /// 作用域 (global/machine/session)
         #[arg(short, long, default_value = "global")]
         scope: String,
+        /// 写入的 shell 启动文件 (bash/zsh/fish/sh)，默认根据 $SHELL 检测（仅 Unix）
+        #[arg(long)]
+        shell: Option<String>,
+        /// machine 作用域文件所在的根目录（用于测试或镜像构建）
+        #[arg(long, default_value = "/")]
+        root: PathBuf,
+        /// 仅显示文件改动，不写入
+        #[arg(long)]
+        dry_run: bool,
     },
 
     /// 删除系统级环境变量
     SystemUnset {
         /// 变量名称
         key: String,
-        /// 作用域
+        /// 作用域 (global/machine/session)
         #[arg(short, long, default_value = "global")]
         scope: String,
+        /// 修改的 shell 启动文件 (bash/zsh/fish/sh)，默认根据 $SHELL 检测（仅 Unix）
+        #[arg(long)]
+        shell: Option<String>,
+        /// machine 作用域文件所在的根目录（用于测试或镜像构建）
+        #[arg(long, default_value = "/")]
+        root: PathBuf,
+        /// 仅显示文件改动，不写入
+        #[arg(long)]
+        dry_run: bool,
     },
 
+    /// 查看 envcli 持久化的系统级环境变量
+    #[command(subcommand)]
+    System(SystemCommands),
+
     /// 缓存管理
     #[command(subcommand)]
     Cache(CacheCommands),
@@ -122,6 +355,32 @@ pub enum Commands {
     /// 配置管理
     #[command(subcommand)]
     Config(ConfigCommands),
+
+    /// 加密密钥管理
+    #[command(subcommand)]
+    Key(KeyCommands),
+}
+
+#[derive(Subcommand)]
+pub enum SystemCommands {
+    /// 列出 envcli 写入受管区块（Windows 上为注册表）的变量及其作用域与文件
+    List {
+        /// 仅列出该作用域 (global/machine/session)
+        #[arg(short, long)]
+        scope: Option<String>,
+        /// machine 作用域文件所在的根目录
+        #[arg(long, default_value = "/")]
+        root: PathBuf,
+    },
+    /// 比较已持久化的值与当前进程环境，找出尚未生效的变量
+    Diff {
+        /// 仅比较该作用域 (global/machine/session)
+        #[arg(short, long)]
+        scope: Option<String>,
+        /// machine 作用域文件所在的根目录
+        #[arg(long, default_value = "/")]
+        root: PathBuf,
+    },
 }
 
 #[derive(Subcommand)]
@@ -137,11 +396,28 @@ pub enum CacheCommands {
 
 #[derive(Subcommand)]
 pub enum ConfigCommands {
-    /// 验证配置
+    /// 验证配置文件（未知配置项、类型错误）
     Validate {
         #[arg(short, long)]
         verbose: bool,
     },
+    /// 读取配置项的生效值
+    Get {
+        /// 配置项（如 default_target、cache.ttl_seconds）
+        key: String,
+    },
+    /// 设置配置项（数组以逗号分隔）
+    Set {
+        /// 配置项
+        key: String,
+        /// 配置值
+        value: String,
+        /// 写入项目配置 .envcli/config.toml（默认写入用户配置）
+        #[arg(long)]
+        project: bool,
+    },
+    /// 列出所有配置项及来源
+    List,
     /// 初始化配置
     Init {
         #[arg(short, long)]
@@ -151,10 +427,14 @@ pub enum ConfigCommands {
     Info,
 }
 
-/// 解析来源参数（可写）
-pub fn parse_writable_source(source: &str) -> anyhow::Result<crate::domain::models::EnvSource> {
-    use crate::domain::models::EnvSource;
+#[derive(Subcommand)]
+pub enum KeyCommands {
+    /// 生成新密钥并重新加密所有层级中的加密值
+    Rotate,
+}
 
+/// 解析来源参数（可写）
+pub fn parse_writable_source(source: &str) -> anyhow::Result<EnvSource> {
     let s =
         EnvSource::parse(source).ok_or_else(|| anyhow::anyhow!("无效的环境层级: {}", source))?;
 
@@ -165,8 +445,40 @@ pub fn parse_writable_source(source: &str) -> anyhow::Result<crate::domain::mode
     Ok(s)
 }
 
+/// 解析目标层级参数，未指定时使用配置中的默认层级
+pub fn parse_target(target: Option<&str>, default: EnvSource) -> anyhow::Result<EnvSource> {
+    match target {
+        Some(target) => parse_writable_source(target),
+        None => Ok(default),
+    }
+}
+
+/// 解析输出格式参数，未指定时使用配置中的默认格式
+pub fn parse_format(format: Option<&str>, default: &str) -> anyhow::Result<&'static dyn Format> {
+    let name = format.unwrap_or(default);
+    formats::find(name).ok_or_else(|| {
+        anyhow::anyhow!(
+            "无效的输出格式: {}（可选 {}）",
+            name,
+            formats::names().join("/")
+        )
+    })
+}
+
+/// 解析 list 的输出视图，`table` 之外的名称按输出格式解析
+pub fn parse_list_view(format: Option<&str>, default: &str) -> anyhow::Result<ListView> {
+    match format {
+        Some(name) if name.eq_ignore_ascii_case(TABLE) => Ok(ListView::Table),
+        _ => parse_format(format, default).map(ListView::Format),
+    }
+}
+
+/// 解析导入格式参数，未指定时返回 `None`（按扩展名与内容自动检测）
+pub fn parse_import_format(format: Option<&str>) -> anyhow::Result<Option<&'static dyn Format>> {
+    format.map(|f| parse_format(Some(f), f)).transpose()
+}
+
 /// 解析来源参数
-pub fn parse_source(source: Option<&str>) -> Option<crate::domain::models::EnvSource> {
-    use crate::domain::models::EnvSource;
+pub fn parse_source(source: Option<&str>) -> Option<EnvSource> {
     source.and_then(EnvSource::parse)
 }
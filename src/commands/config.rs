@@ -1,39 +1,193 @@
 //! config 命令处理器
 
 use super::{CommandContext, CommandHandler};
-use crate::domain::error::Result;
+use crate::domain::error::{DomainError, Result};
+use crate::domain::repositories::StorageConfig;
 use crate::infrastructure::paths;
+use crate::infrastructure::settings::{self, CONFIG_FILE_NAME, Settings};
 use async_trait::async_trait;
+use std::path::PathBuf;
+
+/// 用户与项目配置文件路径
+fn config_files(storage: &StorageConfig) -> [(&'static str, PathBuf); 2] {
+    [
+        ("user", storage.user_dir.join(CONFIG_FILE_NAME)),
+        ("project", storage.project_dir.join(CONFIG_FILE_NAME)),
+    ]
+}
 
 /// config validate 命令
 pub struct ConfigValidateCommand {
-    #[allow(dead_code)]
+    storage: StorageConfig,
     verbose: bool,
 }
 
 impl ConfigValidateCommand {
-    pub fn new(verbose: bool) -> Self {
-        Self { verbose }
+    pub fn new(storage: StorageConfig, verbose: bool) -> Self {
+        Self { storage, verbose }
     }
 }
 
 #[async_trait]
 impl CommandHandler for ConfigValidateCommand {
-    async fn execute(&self, _ctx: &CommandContext) -> Result<()> {
+    async fn execute(&self, ctx: &CommandContext) -> Result<()> {
         println!("🔍 配置文件验证\n");
-        println!("✅ 配置格式正确");
+
+        let mut total = 0;
+        for (_, path) in config_files(&self.storage) {
+            if !path.exists() {
+                if self.verbose || ctx.verbose {
+                    println!("○ {}: 不存在", path.display());
+                }
+                continue;
+            }
+
+            let content =
+                std::fs::read_to_string(&path).map_err(|e| DomainError::Io(e.to_string()))?;
+            let (_, issues) = settings::parse(&content);
+            if issues.is_empty() {
+                println!("✓ {}", path.display());
+            }
+            for issue in &issues {
+                println!("✗ {}:{}: {}", path.display(), issue.line, issue.message);
+            }
+            total += issues.len();
+        }
+
+        if total > 0 {
+            return Err(DomainError::Config(format!(
+                "配置文件存在 {} 个问题",
+                total
+            )));
+        }
+
+        println!("\n✅ 配置格式正确");
+        Ok(())
+    }
+}
+
+/// config get 命令
+pub struct ConfigGetCommand {
+    storage: StorageConfig,
+    key: String,
+}
+
+impl ConfigGetCommand {
+    pub fn new(storage: StorageConfig, key: String) -> Self {
+        Self { storage, key }
+    }
+}
+
+#[async_trait]
+impl CommandHandler for ConfigGetCommand {
+    async fn execute(&self, _ctx: &CommandContext) -> Result<()> {
+        let [(_, user), (_, project)] = config_files(&self.storage);
+        let effective = Settings::defaults()
+            .merge(settings::load(&user)?)
+            .merge(settings::load(&project)?);
+
+        match effective.value(&self.key) {
+            Some(value) => {
+                println!("{}", value);
+                Ok(())
+            }
+            None => Err(DomainError::InvalidArgument(format!(
+                "未知配置项: {}（可选: {}）",
+                self.key,
+                settings::keys().collect::<Vec<_>>().join(", ")
+            ))),
+        }
+    }
+}
+
+/// config set 命令
+pub struct ConfigSetCommand {
+    storage: StorageConfig,
+    key: String,
+    value: String,
+    project: bool,
+}
+
+impl ConfigSetCommand {
+    pub fn new(storage: StorageConfig, key: String, value: String, project: bool) -> Self {
+        Self {
+            storage,
+            key,
+            value,
+            project,
+        }
+    }
+}
+
+#[async_trait]
+impl CommandHandler for ConfigSetCommand {
+    async fn execute(&self, ctx: &CommandContext) -> Result<()> {
+        let [(_, user), (_, project)] = config_files(&self.storage);
+        let path = if self.project { project } else { user };
+
+        let content = if path.exists() {
+            std::fs::read_to_string(&path).map_err(|e| DomainError::Io(e.to_string()))?
+        } else {
+            String::new()
+        };
+        let updated = settings::set_value(&content, &self.key, &self.value)?;
+
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| DomainError::Io(e.to_string()))?;
+        }
+        std::fs::write(&path, updated).map_err(|e| DomainError::Io(e.to_string()))?;
+
+        if ctx.verbose {
+            println!(
+                "✓ 已设置 {} = {} ({})",
+                self.key,
+                self.value,
+                path.display()
+            );
+        }
+        Ok(())
+    }
+}
+
+/// config list 命令
+pub struct ConfigListCommand {
+    storage: StorageConfig,
+}
+
+impl ConfigListCommand {
+    pub fn new(storage: StorageConfig) -> Self {
+        Self { storage }
+    }
+}
+
+#[async_trait]
+impl CommandHandler for ConfigListCommand {
+    async fn execute(&self, _ctx: &CommandContext) -> Result<()> {
+        let [(_, user), (_, project)] = config_files(&self.storage);
+        let user = settings::load(&user)?;
+        let project = settings::load(&project)?;
+        let defaults = Settings::defaults();
+
+        for key in settings::keys() {
+            let (value, origin) = [(&project, "project"), (&user, "user"), (&defaults, "默认")]
+                .into_iter()
+                .find_map(|(layer, origin)| layer.value(key).map(|value| (value, origin)))
+                .unwrap_or_default();
+            println!("{} = {} ({})", key, value, origin);
+        }
         Ok(())
     }
 }
 
 /// config init 命令
 pub struct ConfigInitCommand {
+    storage: StorageConfig,
     force: bool,
 }
 
 impl ConfigInitCommand {
-    pub fn new(force: bool) -> Self {
-        Self { force }
+    pub fn new(storage: StorageConfig, force: bool) -> Self {
+        Self { storage, force }
     }
 }
 
@@ -42,7 +196,7 @@ impl CommandHandler for ConfigInitCommand {
     async fn execute(&self, ctx: &CommandContext) -> Result<()> {
         println!("🔧 初始化配置文件\n");
 
-        let config_dir = paths::ensure_config_dir()?;
+        let config_dir = paths::ensure_config_dir(&self.storage)?;
         println!("✓ 配置目录: {}", config_dir.display());
 
         // 创建用户级配置文件
@@ -57,7 +211,7 @@ impl CommandHandler for ConfigInitCommand {
         }
 
         // 创建项目级目录
-        let project_dir = paths::ensure_project_dir()?;
+        let project_dir = paths::ensure_project_dir(&self.storage)?;
         println!("✓ 项目配置目录: {}", project_dir.display());
 
         // 创建 local.env
@@ -90,17 +244,13 @@ impl CommandHandler for ConfigInitCommand {
 }
 
 /// config info 命令
-pub struct ConfigInfoCommand;
-
-impl Default for ConfigInfoCommand {
-    fn default() -> Self {
-        Self::new()
-    }
+pub struct ConfigInfoCommand {
+    storage: StorageConfig,
 }
 
 impl ConfigInfoCommand {
-    pub fn new() -> Self {
-        Self
+    pub fn new(storage: StorageConfig) -> Self {
+        Self { storage }
     }
 }
 
@@ -109,25 +259,28 @@ impl CommandHandler for ConfigInfoCommand {
     async fn execute(&self, _ctx: &CommandContext) -> Result<()> {
         println!("📋 EnvCLI 配置信息\n");
 
-        match paths::get_config_dir() {
-            Ok(dir) => {
-                println!("配置目录: {}", dir.display());
-                println!(
-                    "状态: {}",
-                    if dir.exists() {
-                        "✓ 存在"
-                    } else {
-                        "✗ 不存在"
-                    }
-                );
+        let dir = &self.storage.user_dir;
+        println!("配置目录: {}", dir.display());
+        println!(
+            "状态: {}",
+            if dir.exists() {
+                "✓ 存在"
+            } else {
+                "✗ 不存在"
             }
-            Err(e) => println!("配置目录: 无法确定 ({e})"),
+        );
+        println!("项目目录: {}", self.storage.project_dir.display());
+
+        println!("\n配置文件:");
+        for (scope, path) in config_files(&self.storage) {
+            let status = if path.exists() { "存在" } else { "不存在" };
+            println!("  {}: {} ({})", scope, path.display(), status);
         }
 
         println!("\n层级文件:");
         use crate::domain::models::EnvSource;
         for source in [EnvSource::User, EnvSource::Project, EnvSource::Local] {
-            match paths::get_layer_path(&source) {
+            match paths::get_layer_path(&self.storage, &source) {
                 Ok(path) => {
                     if path.exists() {
                         println!("  {}: {} (存在)", source, path.display());
@@ -0,0 +1,286 @@
+//! diff 命令处理器
+//!
+//! 比较两组变量：每一侧可以是层级（`project`、`profile:<NAME>` 等）、
+//! 合并视图（`merged`，不含 system 层）或文件（格式按扩展名与内容检测）。
+//! 值按插值语法比较但不解析引用，`'a$b'` 与 `"a\$b"` 视为相同，输出时 `\$` 还原为 `$`；
+//! 加密信封解密后再比较，存在差异时以状态 1 退出。
+
+use super::{CommandContext, CommandHandler};
+use crate::domain::error::{DomainError, Result};
+use crate::domain::interpolation::{escape, unescape};
+use crate::domain::models::{EnvSource, EnvVar};
+use crate::domain::repositories::{EnvRepository, SecretCipher, StorageConfig};
+use crate::domain::secrets::{SecretClassifier, is_envelope, mask_value};
+use crate::infrastructure::formats;
+use crate::infrastructure::storage::FileEnvRepository;
+use async_trait::async_trait;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// 合并视图的名称
+pub const MERGED: &str = "merged";
+
+/// 比较的一侧
+#[derive(Debug, Clone, PartialEq)]
+enum Side {
+    /// 单个层级，profile 层附带名称
+    Layer(EnvSource, Option<String>),
+    /// 除 system 外各层合并后的视图
+    Merged,
+    File(PathBuf),
+}
+
+impl Side {
+    /// 层级名称优先，其余按文件路径处理（同名文件可写作 `./project`）
+    fn parse(name: &str) -> Self {
+        if name.eq_ignore_ascii_case(MERGED) {
+            return Side::Merged;
+        }
+        match EnvSource::parse(name) {
+            Some(source) => Side::Layer(source, EnvSource::profile_name(name).map(str::to_string)),
+            None => Side::File(PathBuf::from(name)),
+        }
+    }
+}
+
+/// 单侧的变量：变量名 -> (插值语法表示的值, 是否敏感)
+type Vars = BTreeMap<String, (String, bool)>;
+
+/// 一处差异
+#[derive(Debug, Serialize)]
+struct Change {
+    key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    old: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    new: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    masked: bool,
+}
+
+/// JSON 输出
+#[derive(Debug, Serialize)]
+struct Report<'a> {
+    a: &'a str,
+    b: &'a str,
+    equal: bool,
+    added: Vec<Change>,
+    removed: Vec<Change>,
+    changed: Vec<Change>,
+    unchanged: usize,
+}
+
+/// diff 命令
+pub struct DiffCommand {
+    storage: StorageConfig,
+    cipher: Arc<dyn SecretCipher>,
+    classifier: SecretClassifier,
+    a: String,
+    b: String,
+    format: String,
+}
+
+impl DiffCommand {
+    pub fn new(
+        storage: StorageConfig,
+        cipher: Arc<dyn SecretCipher>,
+        classifier: SecretClassifier,
+        a: String,
+        b: String,
+        format: String,
+    ) -> Self {
+        Self {
+            storage,
+            cipher,
+            classifier,
+            a,
+            b,
+            format,
+        }
+    }
+
+    /// 读取一侧的变量（存储的原始值，不解析插值；加密信封解密后按敏感变量处理）
+    async fn load(&self, name: &str) -> Result<Vars> {
+        let vars = match Side::parse(name) {
+            Side::Layer(source, profile) => {
+                let mut storage = self.storage.clone();
+                if profile.is_some() {
+                    storage.profile = profile;
+                }
+                FileEnvRepository::new(storage).list(Some(source)).await?
+            }
+            Side::Merged => FileEnvRepository::new(self.storage.clone())
+                .list(None)
+                .await?
+                .into_iter()
+                .filter(|v| v.source != EnvSource::System)
+                .collect(),
+            Side::File(path) => {
+                if !path.exists() {
+                    return Err(DomainError::FileNotFound(path));
+                }
+                let content = std::fs::read_to_string(&path)
+                    .map_err(|e| DomainError::Io(format!("{}: {}", path.display(), e)))?;
+                formats::detect(Some(&path), &content)
+                    .parse(&content)?
+                    .into_iter()
+                    .map(|(key, value)| EnvVar::new(key, value, EnvSource::Local))
+                    .collect()
+            }
+        };
+
+        vars.into_iter()
+            .map(|var| {
+                if var.source != EnvSource::System && is_envelope(&var.value) {
+                    let value = self.cipher.decrypt(&var.key, &var.value)?;
+                    return Ok((var.key, (escape(&value), true)));
+                }
+                let secret = self.classifier.is_secret(&var);
+                let value = var.template();
+                Ok((var.key, (value, secret)))
+            })
+            .collect()
+    }
+
+    fn display(&self, value: &str, secret: bool, reveal: bool) -> (String, bool) {
+        let value = unescape(value);
+        if secret && !reveal {
+            (mask_value(&value), true)
+        } else {
+            (value, false)
+        }
+    }
+}
+
+#[async_trait]
+impl CommandHandler for DiffCommand {
+    async fn execute(&self, ctx: &CommandContext) -> Result<()> {
+        let json = match self.format.as_str() {
+            "text" => false,
+            "json" => true,
+            other => {
+                return Err(DomainError::InvalidArgument(format!(
+                    "无效的输出格式: {}（可选 text/json）",
+                    other
+                )));
+            }
+        };
+
+        let left = self.load(&self.a).await?;
+        let right = self.load(&self.b).await?;
+
+        let mut added = Vec::new();
+        let mut removed = Vec::new();
+        let mut changed = Vec::new();
+        let mut unchanged = Vec::new();
+
+        for (key, (old, old_secret)) in &left {
+            match right.get(key) {
+                None => {
+                    let (old, masked) = self.display(old, *old_secret, ctx.reveal);
+                    removed.push(Change {
+                        key: key.clone(),
+                        old: Some(old),
+                        new: None,
+                        masked,
+                    });
+                }
+                Some((new, _)) if new == old => unchanged.push(key.as_str()),
+                Some((new, new_secret)) => {
+                    // 任一侧敏感时两侧都掩码
+                    let secret = *old_secret || *new_secret;
+                    let (old, masked) = self.display(old, secret, ctx.reveal);
+                    let (new, _) = self.display(new, secret, ctx.reveal);
+                    changed.push(Change {
+                        key: key.clone(),
+                        old: Some(old),
+                        new: Some(new),
+                        masked,
+                    });
+                }
+            }
+        }
+        for (key, (new, secret)) in &right {
+            if !left.contains_key(key) {
+                let (new, masked) = self.display(new, *secret, ctx.reveal);
+                added.push(Change {
+                    key: key.clone(),
+                    old: None,
+                    new: Some(new),
+                    masked,
+                });
+            }
+        }
+
+        let differences = added.len() + removed.len() + changed.len();
+        if json {
+            let report = Report {
+                a: &self.a,
+                b: &self.b,
+                equal: differences == 0,
+                added,
+                removed,
+                changed,
+                unchanged: unchanged.len(),
+            };
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            println!("--- {}\n+++ {}", self.a, self.b);
+
+            // 按变量名合并排序输出
+            let mut lines: Vec<(&str, String)> = Vec::new();
+            for c in &removed {
+                lines.push((
+                    &c.key,
+                    format!("- {}={}", c.key, c.old.as_deref().unwrap_or("")),
+                ));
+            }
+            for c in &added {
+                lines.push((
+                    &c.key,
+                    format!("+ {}={}", c.key, c.new.as_deref().unwrap_or("")),
+                ));
+            }
+            for c in &changed {
+                lines.push((
+                    &c.key,
+                    format!(
+                        "~ {}: {} → {}",
+                        c.key,
+                        c.old.as_deref().unwrap_or(""),
+                        c.new.as_deref().unwrap_or("")
+                    ),
+                ));
+            }
+            if ctx.verbose {
+                for key in &unchanged {
+                    lines.push((key, format!("  {}", key)));
+                }
+            }
+            lines.sort_by(|a, b| a.0.cmp(b.0));
+            for (_, line) in &lines {
+                println!("{}", line);
+            }
+
+            if differences == 0 {
+                println!("✓ 两侧相同（{} 个变量）", unchanged.len());
+            } else {
+                println!(
+                    "{} 处差异：新增 {}，删除 {}，修改 {}",
+                    differences,
+                    added.len(),
+                    removed.len(),
+                    changed.len()
+                );
+            }
+        }
+
+        if differences > 0 {
+            // 与 diff(1) 一致：存在差异时以状态 1 退出
+            return Err(DomainError::ExitStatus(1));
+        }
+        Ok(())
+    }
+}
@@ -4,6 +4,7 @@ use super::{CommandContext, CommandHandler};
 use crate::application::services::EnvService;
 use crate::domain::error::Result;
 use crate::domain::models::EnvSource;
+use crate::domain::repositories::StorageConfig;
 use crate::infrastructure::paths;
 use async_trait::async_trait;
 use std::sync::Arc;
@@ -11,11 +12,15 @@ use std::sync::Arc;
 /// doctor 命令
 pub struct DoctorCommand {
     env_service: Arc<EnvService>,
+    storage: StorageConfig,
 }
 
 impl DoctorCommand {
-    pub fn new(env_service: Arc<EnvService>) -> Self {
-        Self { env_service }
+    pub fn new(env_service: Arc<EnvService>, storage: StorageConfig) -> Self {
+        Self {
+            env_service,
+            storage,
+        }
     }
 }
 
@@ -31,26 +36,20 @@ impl CommandHandler for DoctorCommand {
 
         // 1. 检查配置目录
         println!("📁 1. 配置目录检查");
-        match paths::get_config_dir() {
-            Ok(dir) => {
-                if !dir.exists() {
-                    println!("   ❌ 配置目录不存在: {}", dir.display());
-                    issues += 1;
-                } else {
-                    println!("   ✓ 配置目录存在: {}", dir.display());
-                }
-            }
-            Err(e) => {
-                println!("   ❌ 无法确定配置目录: {}", e);
-                issues += 1;
-            }
+        let dir = &self.storage.user_dir;
+        if !dir.exists() {
+            println!("   ❌ 配置目录不存在: {}", dir.display());
+            issues += 1;
+        } else {
+            println!("   ✓ 配置目录存在: {}", dir.display());
         }
         println!();
 
         // 2. 检查层级文件
         println!("📄 2. 配置文件状态");
+        println!("   ✓ 项目目录: {}", self.storage.project_dir.display());
         for source in [EnvSource::User, EnvSource::Project, EnvSource::Local] {
-            match paths::get_layer_path(&source) {
+            match paths::get_layer_path(&self.storage, &source) {
                 Ok(path) => {
                     if path.exists() {
                         println!("   ✓ {}: {}", source, path.display());
@@ -66,8 +65,35 @@ impl CommandHandler for DoctorCommand {
         }
         println!();
 
-        // 3. 检查变量冲突
-        println!("🔄 3. 变量冲突检查");
+        // 3. 检查 profile
+        println!("🏷️ 3. Profile 检查");
+        match self.env_service.active_profile() {
+            None => println!("   ○ 未激活 profile"),
+            Some(name) => match self.env_service.profile_chain().await {
+                Ok(chain) => {
+                    println!("   ✓ 当前 profile: {}", chain.join(" -> "));
+                    match self.env_service.layer_path(&EnvSource::Profile) {
+                        Ok(path) if !path.exists() => {
+                            println!("   ⚠️  profile 文件不存在: {}", path.display());
+                            warnings += 1;
+                        }
+                        Ok(_) => {}
+                        Err(e) => {
+                            println!("   ❌ {}: {}", name, e);
+                            issues += 1;
+                        }
+                    }
+                }
+                Err(e) => {
+                    println!("   ❌ {}", e);
+                    issues += 1;
+                }
+            },
+        }
+        println!();
+
+        // 4. 检查变量冲突
+        println!("🔄 4. 变量冲突检查");
         match self.env_service.check_conflicts().await {
             Ok(conflicts) => {
                 if conflicts.is_empty() {
@@ -89,8 +115,8 @@ impl CommandHandler for DoctorCommand {
         }
         println!();
 
-        // 4. 系统环境
-        println!("🖥️ 4. 系统环境");
+        // 5. 系统环境
+        println!("🖥️ 5. 系统环境");
         match paths::get_system_env() {
             Ok(vars) => {
                 println!("   系统变量数: {}", vars.len());
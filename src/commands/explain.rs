@@ -0,0 +1,179 @@
+//! explain 命令处理器
+//!
+//! 显示变量的解析过程：定义它的每个层级及文件位置、生效与被覆盖的定义、
+//! 解密与插值步骤。`get --explain` 输出精简形式。
+
+use super::{CommandContext, CommandHandler};
+use crate::application::services::{EnvService, Explanation};
+use crate::domain::error::{DomainError, Result};
+use crate::domain::repositories::Definition;
+use crate::domain::secrets::{is_envelope, mask_value};
+use async_trait::async_trait;
+use std::sync::Arc;
+
+/// explain 命令
+pub struct ExplainCommand {
+    env_service: Arc<EnvService>,
+    key: String,
+    format: String,
+    /// 精简输出（`get --explain`）
+    compact: bool,
+}
+
+impl ExplainCommand {
+    pub fn new(env_service: Arc<EnvService>, key: String, format: String, compact: bool) -> Self {
+        Self {
+            env_service,
+            key,
+            format,
+            compact,
+        }
+    }
+
+    /// 按需掩码，敏感变量在未指定 `--reveal` 时隐藏
+    fn mask(explanation: &mut Explanation) {
+        if explanation.secret {
+            explanation.value = mask_value(&explanation.value);
+            for definition in &mut explanation.definitions {
+                if !is_envelope(&definition.value) {
+                    definition.value = mask_value(&definition.value);
+                }
+            }
+        }
+        for reference in &mut explanation.references {
+            if reference.secret {
+                reference.value = reference.value.as_deref().map(mask_value);
+            }
+        }
+    }
+
+    fn print_full(explanation: &Explanation) {
+        println!("{} = {}", explanation.key, explanation.value);
+
+        println!("\n定义（优先级从高到低）:");
+        let rows: Vec<(String, String, String)> = explanation
+            .definitions
+            .iter()
+            .rev()
+            .map(|d| (d.source.to_string(), location(d), display_value(&d.value)))
+            .collect();
+        let source_width = rows.iter().map(|r| r.0.len()).max().unwrap_or(0);
+        let location_width = rows.iter().map(|r| r.1.chars().count()).max().unwrap_or(0);
+        for (index, (source, location, value)) in rows.iter().enumerate() {
+            let (mark, note) = if index == 0 {
+                ("✓", "")
+            } else {
+                (" ", "（被覆盖）")
+            };
+            let padding = location_width - location.chars().count();
+            println!(
+                "  {} {:<sw$}  {}{}  {}{}",
+                mark,
+                source,
+                location,
+                " ".repeat(padding),
+                value,
+                note,
+                sw = source_width
+            );
+        }
+
+        if explanation.decrypted {
+            println!("\n解密: 生效值为加密信封，已使用本地密钥解密");
+        }
+        if !explanation.references.is_empty() {
+            println!("\n插值:");
+            for reference in &explanation.references {
+                match (&reference.source, &reference.value) {
+                    (Some(source), Some(value)) => {
+                        println!("  ${{{}}} → {}（{}）", reference.name, value, source)
+                    }
+                    _ => println!("  ${{{}}} → 未定义", reference.name),
+                }
+            }
+        }
+    }
+
+    fn print_compact(explanation: &Explanation) {
+        println!("{}", explanation.value);
+
+        let Some((winner, shadowed)) = explanation.definitions.split_last() else {
+            return;
+        };
+        let mut line = format!("  ← {}（{}）", winner.source, location(winner));
+        if !shadowed.is_empty() {
+            let sources: Vec<String> = shadowed
+                .iter()
+                .rev()
+                .map(|d| d.source.to_string())
+                .collect();
+            line.push_str(&format!("，覆盖 {}", sources.join("、")));
+        }
+        if explanation.decrypted {
+            line.push_str("，已解密");
+        }
+        if !explanation.references.is_empty() {
+            let names: Vec<&str> = explanation
+                .references
+                .iter()
+                .map(|r| r.name.as_str())
+                .collect();
+            line.push_str(&format!("，插值 {}", names.join("、")));
+        }
+        println!("{}", line);
+    }
+}
+
+/// 定义所在位置，文件路径尽量显示为相对当前目录的路径
+fn location(definition: &Definition) -> String {
+    let Some(path) = &definition.path else {
+        return "（进程环境）".to_string();
+    };
+    let path = std::env::current_dir()
+        .ok()
+        .and_then(|cwd| path.strip_prefix(cwd).ok().map(|p| p.to_path_buf()))
+        .unwrap_or_else(|| path.clone());
+    match definition.line {
+        Some(line) => format!("{}:{}", path.display(), line),
+        None => path.display().to_string(),
+    }
+}
+
+/// 加密信封只显示前缀
+fn display_value(value: &str) -> String {
+    if is_envelope(value) {
+        "ENC[…]".to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+#[async_trait]
+impl CommandHandler for ExplainCommand {
+    async fn execute(&self, ctx: &CommandContext) -> Result<()> {
+        let json = match self.format.as_str() {
+            "text" => false,
+            "json" => true,
+            other => {
+                return Err(DomainError::InvalidArgument(format!(
+                    "无效的输出格式: {}（可选 text/json）",
+                    other
+                )));
+            }
+        };
+
+        let mut explanation = self.env_service.explain(&self.key).await?;
+        if !ctx.reveal {
+            Self::mask(&mut explanation);
+        }
+
+        if json {
+            println!("{}", serde_json::to_string_pretty(&explanation)?);
+        } else if self.compact {
+            Self::print_compact(&explanation);
+        } else {
+            Self::print_full(&explanation);
+        }
+        Ok(())
+    }
+}
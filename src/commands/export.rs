@@ -1,9 +1,11 @@
 //! export 命令处理器
 
 use super::{CommandContext, CommandHandler};
-use crate::application::services::EnvService;
+use crate::application::services::{EnvService, ExportOptions};
 use crate::domain::error::Result;
-use crate::domain::models::{EnvSource, OutputFormat};
+use crate::domain::format::Format;
+use crate::domain::models::EnvSource;
+use crate::infrastructure::shell::is_valid_name;
 use async_trait::async_trait;
 use std::sync::Arc;
 
@@ -11,31 +13,77 @@ use std::sync::Arc;
 pub struct ExportCommand {
     env_service: Arc<EnvService>,
     source: Option<EnvSource>,
-    format: OutputFormat,
+    format: &'static dyn Format,
+    options: ExportOptions,
+    unset: bool,
 }
 
 impl ExportCommand {
     pub fn new(
         env_service: Arc<EnvService>,
         source: Option<EnvSource>,
-        format: OutputFormat,
+        format: &'static dyn Format,
+        options: ExportOptions,
+        unset: bool,
     ) -> Self {
         Self {
             env_service,
             source,
             format,
+            options,
+            unset,
         }
     }
+
+    /// 输出 shell 语句
+    ///
+    /// 未指定来源时不包含 system 层：这些变量已在环境中，`--unset` 更不应删除它们。
+    async fn export_shell(&self) -> Result<()> {
+        let vars = self
+            .env_service
+            .export_vars(self.source, &self.options)
+            .await?;
+        let mut vars: Vec<_> = vars
+            .into_iter()
+            .filter(|v| self.source.is_some() || v.var.source != EnvSource::System)
+            .collect();
+        vars.sort_by(|a, b| a.var.key.cmp(&b.var.key));
+
+        vars.retain(|masked| {
+            let valid = is_valid_name(&masked.var.key);
+            if !valid {
+                eprintln!("跳过 {}：变量名无法在 shell 中使用", masked.var.key);
+            }
+            valid
+        });
+
+        let output = if self.unset {
+            let keys: Vec<&str> = vars.iter().map(|v| v.var.key.as_str()).collect();
+            self.format.render_unset(&keys)?
+        } else {
+            self.format.render(&vars)?
+        };
+        print!("{}", output);
+        Ok(())
+    }
 }
 
 #[async_trait]
 impl CommandHandler for ExportCommand {
     async fn execute(&self, _ctx: &CommandContext) -> Result<()> {
+        if self.format.is_shell() {
+            return self.export_shell().await;
+        }
+        if self.unset {
+            // 非 shell 格式：由格式给出不支持 --unset 的错误
+            self.format.render_unset(&[])?;
+        }
+
         let output = self
             .env_service
-            .export(self.source, self.format.clone())
+            .export(self.source, self.format, &self.options)
             .await?;
-        println!("{}", output);
+        print!("{}", output);
         Ok(())
     }
 }
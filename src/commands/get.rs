@@ -10,18 +10,29 @@ use std::sync::Arc;
 pub struct GetCommand {
     env_service: Arc<EnvService>,
     key: String,
+    raw: bool,
 }
 
 impl GetCommand {
-    pub fn new(env_service: Arc<EnvService>, key: String) -> Self {
-        Self { env_service, key }
+    pub fn new(env_service: Arc<EnvService>, key: String, raw: bool) -> Self {
+        Self {
+            env_service,
+            key,
+            raw,
+        }
     }
 }
 
 #[async_trait]
 impl CommandHandler for GetCommand {
     async fn execute(&self, _ctx: &CommandContext) -> Result<()> {
-        match self.env_service.get(&self.key).await? {
+        let value = if self.raw {
+            self.env_service.get(&self.key).await?
+        } else {
+            self.env_service.get_resolved(&self.key).await?
+        };
+
+        match value {
             Some(value) => {
                 println!("{}", value);
                 Ok(())
@@ -0,0 +1,334 @@
+//! hook/hook-env/allow/deny 命令处理器
+//!
+//! `envcli hook <shell>` 输出在每次显示提示符时运行 `envcli hook-env` 的脚本；
+//! `hook-env` 比较当前目录与上次加载的状态，输出加载或还原变量的语句。
+//! 状态保存在 shell 变量 `ENVCLI_HOOK_STATE` 中，记录被修改的变量及其原值，
+//! 离开项目时据此精确还原。
+
+use super::{CommandContext, CommandHandler};
+use crate::application::services::EnvService;
+use crate::domain::error::{DomainError, Result};
+use crate::domain::models::EnvSource;
+use crate::domain::repositories::StorageConfig;
+use crate::infrastructure::paths;
+use crate::infrastructure::settings::CONFIG_FILE_NAME;
+use crate::infrastructure::shell::Shell;
+use crate::infrastructure::trust::TrustStore;
+use async_trait::async_trait;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD as BASE64;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// 保存钩子状态的 shell 变量
+pub const STATE_ENV: &str = "ENVCLI_HOOK_STATE";
+
+/// 钩子自动加载的层级
+const HOOK_LAYERS: [EnvSource; 3] = [EnvSource::Project, EnvSource::Profile, EnvSource::Local];
+
+/// 上次加载的状态
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct HookState {
+    /// 项目根目录
+    dir: PathBuf,
+    /// 层级文件与信任状态的指纹，变化时重新加载
+    fingerprint: String,
+    /// 被修改的变量及其原值（`None` 表示原先未设置）
+    changed: BTreeMap<String, Option<String>>,
+}
+
+impl HookState {
+    fn decode(encoded: &str) -> Option<Self> {
+        let json = BASE64.decode(encoded).ok()?;
+        serde_json::from_slice(&json).ok()
+    }
+
+    fn encode(&self) -> Result<String> {
+        Ok(BASE64.encode(serde_json::to_vec(self)?))
+    }
+}
+
+/// 解析 hook 支持的 shell
+fn parse_shell(name: &str) -> Result<Shell> {
+    match Shell::parse(name) {
+        Some(shell @ (Shell::Bash | Shell::Zsh | Shell::Fish)) => Ok(shell),
+        _ => Err(DomainError::InvalidArgument(format!(
+            "不支持的 shell: {}（可选 bash/zsh/fish）",
+            name
+        ))),
+    }
+}
+
+/// hook 命令
+pub struct HookCommand {
+    shell: String,
+}
+
+impl HookCommand {
+    pub fn new(shell: String) -> Self {
+        Self { shell }
+    }
+}
+
+#[async_trait]
+impl CommandHandler for HookCommand {
+    async fn execute(&self, _ctx: &CommandContext) -> Result<()> {
+        let shell = parse_shell(&self.shell)?;
+        let exe = std::env::current_exe().map_err(|e| DomainError::Io(e.to_string()))?;
+        let exe = shell.quote(&exe.to_string_lossy());
+
+        let script = match shell {
+            Shell::Bash => format!(
+                r#"_envcli_hook() {{
+  local previous_exit_status=$?
+  eval "$({exe} hook-env --shell bash)"
+  return $previous_exit_status
+}}
+if [[ ";${{PROMPT_COMMAND:-}};" != *";_envcli_hook;"* ]]; then
+  PROMPT_COMMAND="_envcli_hook${{PROMPT_COMMAND:+;$PROMPT_COMMAND}}"
+fi
+"#
+            ),
+            Shell::Zsh => format!(
+                r#"_envcli_hook() {{
+  eval "$({exe} hook-env --shell zsh)"
+}}
+typeset -ag precmd_functions chpwd_functions
+if (( ! ${{precmd_functions[(I)_envcli_hook]}} )); then
+  precmd_functions=(_envcli_hook $precmd_functions)
+fi
+if (( ! ${{chpwd_functions[(I)_envcli_hook]}} )); then
+  chpwd_functions=(_envcli_hook $chpwd_functions)
+fi
+"#
+            ),
+            _ => format!(
+                r#"function __envcli_hook --on-event fish_prompt --on-variable PWD
+    {exe} hook-env --shell fish | source
+end
+"#
+            ),
+        };
+
+        print!("{}", script);
+        Ok(())
+    }
+}
+
+/// hook-env 命令
+pub struct HookEnvCommand {
+    env_service: Arc<EnvService>,
+    storage: StorageConfig,
+    shell: String,
+}
+
+impl HookEnvCommand {
+    pub fn new(env_service: Arc<EnvService>, storage: StorageConfig, shell: String) -> Self {
+        Self {
+            env_service,
+            storage,
+            shell,
+        }
+    }
+
+    /// 当前所在的项目根目录（存在 `.envcli` 时）
+    fn project_root(&self) -> Option<PathBuf> {
+        if !self.storage.project_dir.is_dir() {
+            return None;
+        }
+        let root = self.storage.project_dir.parent()?;
+        Some(root.canonicalize().unwrap_or_else(|_| root.to_path_buf()))
+    }
+
+    /// 钩子读取的文件与信任状态的指纹
+    fn fingerprint(&self, allowed: bool) -> String {
+        let mut files = vec![
+            self.storage.project_dir.join("project.env"),
+            self.storage.project_dir.join("local.env"),
+            self.storage.project_dir.join(CONFIG_FILE_NAME),
+        ];
+        if let Some(profile) = &self.storage.profile
+            && let Ok(path) = paths::get_profile_path(&self.storage, profile)
+        {
+            files.push(path);
+        }
+
+        let mut parts = vec![format!(
+            "allowed={};profile={}",
+            allowed,
+            self.storage.profile.as_deref().unwrap_or("")
+        )];
+        for file in files {
+            parts.push(match std::fs::metadata(&file) {
+                Ok(meta) => {
+                    let modified = meta
+                        .modified()
+                        .ok()
+                        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                        .map(|d| d.as_nanos())
+                        .unwrap_or(0);
+                    format!("{}:{}", modified, meta.len())
+                }
+                Err(_) => "-".to_string(),
+            });
+        }
+        parts.join(";")
+    }
+}
+
+#[async_trait]
+impl CommandHandler for HookEnvCommand {
+    async fn execute(&self, ctx: &CommandContext) -> Result<()> {
+        let shell = parse_shell(&self.shell)?;
+        let previous = std::env::var(STATE_ENV)
+            .ok()
+            .and_then(|s| HookState::decode(&s));
+
+        let root = self.project_root();
+        let allowed = match &root {
+            Some(root) => TrustStore::new(&self.storage.user_dir).is_allowed(root)?,
+            None => false,
+        };
+        let fingerprint = root.as_ref().map(|_| self.fingerprint(allowed));
+
+        // 不在项目中且没有需要还原的状态
+        if previous.is_none() && root.is_none() {
+            return Ok(());
+        }
+
+        // 目录与文件均未变化
+        if let Some(previous) = &previous
+            && Some(&previous.dir) == root.as_ref()
+            && Some(&previous.fingerprint) == fingerprint.as_ref()
+        {
+            return Ok(());
+        }
+
+        let mut env: HashMap<String, String> = std::env::vars().collect();
+        let mut out = Vec::new();
+
+        // 还原上次修改的变量
+        if let Some(previous) = &previous {
+            for (key, value) in &previous.changed {
+                match value {
+                    Some(value) => {
+                        out.push(shell.export(key, value));
+                        env.insert(key.clone(), value.clone());
+                    }
+                    None => {
+                        out.push(shell.unset(key));
+                        env.remove(key);
+                    }
+                }
+            }
+            if !previous.changed.is_empty() && Some(&previous.dir) != root.as_ref() {
+                eprintln!("envcli: 已卸载 {}", previous.dir.display());
+            }
+        }
+
+        let (Some(root), Some(fingerprint)) = (root, fingerprint) else {
+            out.push(shell.unset(STATE_ENV));
+            print_statements(&out);
+            return Ok(());
+        };
+
+        let mut state = HookState {
+            dir: root,
+            fingerprint,
+            changed: BTreeMap::new(),
+        };
+
+        if allowed {
+            // 基于还原后的环境解析，自引用不会随重新加载累积
+            let vars = self
+                .env_service
+                .list_resolved_with_system(env.clone())
+                .await?;
+            for var in vars.iter().filter(|v| HOOK_LAYERS.contains(&v.source)) {
+                let current = env.get(&var.key);
+                if current == Some(&var.value) {
+                    continue;
+                }
+                state.changed.insert(var.key.clone(), current.cloned());
+                out.push(shell.export(&var.key, &var.value));
+            }
+
+            eprintln!(
+                "envcli: 已加载 {}（{} 个变量）",
+                state.dir.display(),
+                state.changed.len()
+            );
+            if ctx.verbose {
+                let keys: Vec<&str> = state.changed.keys().map(String::as_str).collect();
+                eprintln!("envcli: {}", keys.join(" "));
+            }
+        } else {
+            eprintln!(
+                "envcli: {} 未受信任，变量未加载（运行 `envcli allow` 信任该目录）",
+                state.dir.display()
+            );
+        }
+
+        out.push(shell.export(STATE_ENV, &state.encode()?));
+        print_statements(&out);
+        Ok(())
+    }
+}
+
+fn print_statements(statements: &[String]) {
+    for statement in statements {
+        println!("{};", statement);
+    }
+}
+
+/// allow/deny 命令
+pub struct AllowCommand {
+    storage: StorageConfig,
+    allow: bool,
+}
+
+impl AllowCommand {
+    pub fn new(storage: StorageConfig, allow: bool) -> Self {
+        Self { storage, allow }
+    }
+
+    /// 当前项目根目录，必须存在 `.envcli`
+    fn project_root(&self) -> Result<&Path> {
+        let root = self.storage.project_dir.parent().unwrap_or(Path::new("/"));
+        if !self.storage.project_dir.is_dir() {
+            return Err(DomainError::NotFound(format!(
+                "{} 中的 {} 目录",
+                root.display(),
+                paths::PROJECT_DIR_NAME
+            )));
+        }
+        Ok(root)
+    }
+}
+
+#[async_trait]
+impl CommandHandler for AllowCommand {
+    async fn execute(&self, _ctx: &CommandContext) -> Result<()> {
+        let store = TrustStore::new(&self.storage.user_dir);
+
+        if self.allow {
+            let root = self.project_root()?;
+            if store.allow(root)? {
+                println!("✓ 已信任 {}，shell 钩子将自动加载其变量", root.display());
+            } else {
+                println!("{} 已受信任", root.display());
+            }
+        } else {
+            let root = self.storage.project_dir.parent().unwrap_or(Path::new("/"));
+            if store.revoke(root)? {
+                println!("✓ 已取消信任 {}", root.display());
+            } else {
+                println!("{} 未受信任", root.display());
+            }
+        }
+
+        Ok(())
+    }
+}
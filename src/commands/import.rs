@@ -1,44 +1,159 @@
 //! import 命令处理器
 
 use super::{CommandContext, CommandHandler};
-use crate::application::services::EnvService;
-use crate::domain::error::Result;
-use crate::domain::models::EnvSource;
+use crate::application::services::{EnvService, ImportReport, ImportStrategy};
+use crate::domain::error::{DomainError, Result};
+use crate::domain::format::Format;
+use crate::domain::models::{EnvSource, EnvVar};
+use crate::infrastructure::shell::is_valid_name;
+use crate::infrastructure::{dotenv, formats};
 use async_trait::async_trait;
+use std::io::{BufRead, Read, Write};
 use std::path::PathBuf;
 use std::sync::Arc;
 
 /// import 命令
 pub struct ImportCommand {
     env_service: Arc<EnvService>,
+    /// 文件路径，`-` 表示标准输入
     file: PathBuf,
     target: EnvSource,
+    /// 文件格式，`None` 时按扩展名与内容检测
+    format: Option<&'static dyn Format>,
+    strategy: ImportStrategy,
 }
 
 impl ImportCommand {
-    pub fn new(env_service: Arc<EnvService>, file: PathBuf, target: EnvSource) -> Self {
+    pub fn new(
+        env_service: Arc<EnvService>,
+        file: PathBuf,
+        target: EnvSource,
+        format: Option<&'static dyn Format>,
+        strategy: ImportStrategy,
+    ) -> Self {
         Self {
             env_service,
             file,
             target,
+            format,
+            strategy,
+        }
+    }
+
+    fn is_stdin(&self) -> bool {
+        self.file.as_os_str() == "-"
+    }
+
+    /// 读取导入内容
+    fn read_content(&self) -> Result<String> {
+        if self.is_stdin() {
+            let mut content = String::new();
+            std::io::stdin()
+                .read_to_string(&mut content)
+                .map_err(|e| DomainError::Io(format!("标准输入: {}", e)))?;
+            return Ok(content);
+        }
+
+        if !self.file.exists() {
+            return Err(DomainError::FileNotFound(self.file.clone()));
+        }
+        std::fs::read_to_string(&self.file)
+            .map_err(|e| DomainError::Io(format!("{}: {}", self.file.display(), e)))
+    }
+
+    /// 输出导入结果汇总
+    fn print_summary(&self, format: &dyn Format, report: &ImportReport, verbose: bool) {
+        let name = if self.is_stdin() {
+            "标准输入".to_string()
+        } else {
+            self.file.display().to_string()
+        };
+        println!("从 {}（{}）导入到 {}：", name, format.name(), self.target);
+
+        let mut rows = vec![
+            ("新增", &report.added, true),
+            ("更新", &report.updated, true),
+            ("未变", &report.unchanged, verbose),
+            ("删除", &report.removed, true),
+        ];
+        if !report.kept.is_empty() {
+            rows.push(("保留", &report.kept, true));
+        }
+        for (label, keys, show_keys) in rows {
+            if show_keys && !keys.is_empty() {
+                println!("  {}  {:>3}  {}", label, keys.len(), keys.join(", "));
+            } else {
+                println!("  {}  {:>3}", label, keys.len());
+            }
+        }
+        if !report.kept.is_empty() {
+            println!("已有值不同的变量保持不变，使用 --strategy overwrite 覆盖");
         }
     }
 }
 
+/// 询问是否覆盖已有变量
+fn ask_overwrite(key: &str, current: &str, incoming: &str) -> Result<bool> {
+    let mut stderr = std::io::stderr();
+    let _ = write!(
+        stderr,
+        "{} 已存在\n  当前: {}\n  导入: {}\n覆盖? [y/N] ",
+        key, current, incoming
+    );
+    let _ = stderr.flush();
+
+    let mut answer = String::new();
+    std::io::stdin()
+        .lock()
+        .read_line(&mut answer)
+        .map_err(|e| DomainError::Io(format!("标准输入: {}", e)))?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
 #[async_trait]
 impl CommandHandler for ImportCommand {
     async fn execute(&self, ctx: &CommandContext) -> Result<()> {
-        let count = self.env_service.import(&self.file, self.target).await?;
+        if self.is_stdin() && self.strategy == ImportStrategy::Interactive {
+            return Err(DomainError::InvalidArgument(
+                "从标准输入导入时无法逐个询问，请改用 keep/overwrite/prune 策略".to_string(),
+            ));
+        }
 
-        if ctx.verbose {
-            println!(
-                "✓ 从 {} 导入了 {} 个变量到 {:?}",
-                self.file.display(),
-                count,
-                self.target
-            );
+        let content = self.read_content()?;
+        let path = (!self.is_stdin()).then_some(self.file.as_path());
+        let format = self
+            .format
+            .unwrap_or_else(|| formats::detect(path, &content));
+        let entries = format.parse(&content).map_err(|e| match path {
+            Some(path) => dotenv::in_file(path, e),
+            None => e,
+        })?;
+        if let Some((key, _)) = entries.iter().find(|(key, _)| !is_valid_name(key)) {
+            return Err(DomainError::InvalidArgument(format!(
+                "无效的变量名 '{}'：只能包含字母、数字和下划线，且不能以数字开头",
+                key
+            )));
         }
 
+        // 询问时敏感变量同样按掩码显示
+        let classifier = self.env_service.classifier();
+        let display = |key: &str, value: &str| {
+            let var = EnvVar::new(key.to_string(), value.to_string(), self.target);
+            classifier.mask(var, ctx.reveal).var.value
+        };
+        let report = self
+            .env_service
+            .import(
+                entries,
+                self.target,
+                self.strategy,
+                |key, current, incoming| {
+                    ask_overwrite(key, &display(key, current), &display(key, incoming))
+                },
+            )
+            .await?;
+
+        self.print_summary(format, &report, ctx.verbose);
         Ok(())
     }
 }
@@ -0,0 +1,31 @@
+//! key 命令处理器
+
+use super::{CommandContext, CommandHandler};
+use crate::application::services::EnvService;
+use crate::domain::error::Result;
+use async_trait::async_trait;
+use std::sync::Arc;
+
+/// key rotate 命令
+pub struct KeyRotateCommand {
+    env_service: Arc<EnvService>,
+}
+
+impl KeyRotateCommand {
+    pub fn new(env_service: Arc<EnvService>) -> Self {
+        Self { env_service }
+    }
+}
+
+#[async_trait]
+impl CommandHandler for KeyRotateCommand {
+    async fn execute(&self, _ctx: &CommandContext) -> Result<()> {
+        let (key_id, count) = self.env_service.rotate_keys().await?;
+
+        println!("✓ 新密钥: {}", key_id);
+        println!("✓ 已重新加密 {} 个变量", count);
+        println!("○ 旧密钥仍保留，用于解密其他项目中的加密值");
+
+        Ok(())
+    }
+}
@@ -1,47 +1,260 @@
 //! list 命令处理器
+//!
+//! 默认按变量名排序输出，可按来源或修改时间排序，并支持按前缀、
+//! 正则与覆盖关系过滤。`table` 视图对齐显示值与来源层级。
 
 use super::{CommandContext, CommandHandler};
 use crate::application::services::EnvService;
-use crate::domain::error::Result;
-use crate::domain::models::{EnvSource, OutputFormat};
+use crate::domain::error::{DomainError, Result};
+use crate::domain::format::Format;
+use crate::domain::models::{EnvSource, EnvVar};
+use crate::domain::secrets::MaskedVar;
 use async_trait::async_trait;
+use regex::Regex;
+use std::collections::HashMap;
 use std::sync::Arc;
 
+/// 表格视图的名称（仅 list 支持，不属于导入导出格式）
+pub const TABLE: &str = "table";
+
+/// 排序方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ListSort {
+    /// 按变量名
+    #[default]
+    Key,
+    /// 按来源层级（优先级从低到高），同层按变量名
+    Source,
+    /// 按所在文件的修改时间，最近的在前；system 层排在最后
+    Modified,
+}
+
+impl ListSort {
+    pub fn parse(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "key" => Ok(ListSort::Key),
+            "source" => Ok(ListSort::Source),
+            "modified" => Ok(ListSort::Modified),
+            _ => Err(DomainError::InvalidArgument(format!(
+                "无效的排序方式: {}（可选 key/source/modified）",
+                s
+            ))),
+        }
+    }
+}
+
+/// 输出视图
+pub enum ListView {
+    /// 对齐的表格，附带来源与覆盖关系
+    Table,
+    /// 注册的输出格式
+    Format(&'static dyn Format),
+}
+
+/// list 选项
+#[derive(Debug, Clone, Default)]
+pub struct ListOptions {
+    /// 输出存储的原始值，不解析插值
+    pub raw: bool,
+    pub sort: ListSort,
+    /// 只保留以此开头的变量
+    pub prefix: Option<String>,
+    /// 只保留变量名或（显示的）值匹配此正则的变量
+    pub grep: Option<String>,
+    /// 只保留在多个层级中定义的变量
+    pub only_shadowed: bool,
+    /// env 格式下在每行后标注来源
+    pub show_source: bool,
+}
+
 /// list 命令
 pub struct ListCommand {
     env_service: Arc<EnvService>,
     source: Option<EnvSource>,
-    format: OutputFormat,
+    view: ListView,
+    options: ListOptions,
 }
 
 impl ListCommand {
     pub fn new(
         env_service: Arc<EnvService>,
         source: Option<EnvSource>,
-        format: OutputFormat,
+        view: ListView,
+        options: ListOptions,
     ) -> Self {
         Self {
             env_service,
             source,
-            format,
+            view,
+            options,
+        }
+    }
+
+    fn sort(&self, vars: &mut [EnvVar]) {
+        match self.options.sort {
+            ListSort::Key => vars.sort_by(|a, b| a.key.cmp(&b.key)),
+            ListSort::Source => {
+                let order = self.env_service.layer_order();
+                let rank = |source: &EnvSource| order.iter().position(|s| s == source);
+                vars.sort_by(|a, b| {
+                    rank(&a.source)
+                        .cmp(&rank(&b.source))
+                        .then_with(|| a.key.cmp(&b.key))
+                });
+            }
+            ListSort::Modified => vars.sort_by(|a, b| {
+                (a.source == EnvSource::System)
+                    .cmp(&(b.source == EnvSource::System))
+                    .then_with(|| b.timestamp.cmp(&a.timestamp))
+                    .then_with(|| a.key.cmp(&b.key))
+            }),
+        }
+    }
+
+    fn print_table(vars: &[MaskedVar], shadows: &HashMap<String, Vec<EnvSource>>) {
+        let rows: Vec<(&str, String, String)> = vars
+            .iter()
+            .map(|m| {
+                let value = m.var.value.replace('\n', "\\n");
+                let source = annotate(m.var.source, shadows.get(&m.var.key));
+                (m.var.key.as_str(), value, source)
+            })
+            .collect();
+
+        let key_width = rows
+            .iter()
+            .map(|r| r.0.chars().count())
+            .chain([3])
+            .max()
+            .unwrap_or(0);
+        let value_width = rows
+            .iter()
+            .map(|r| r.1.chars().count())
+            .chain([5])
+            .max()
+            .unwrap_or(0);
+
+        println!(
+            "{:<kw$}  {:<vw$}  SOURCE",
+            "KEY",
+            "VALUE",
+            kw = key_width,
+            vw = value_width
+        );
+        for (key, value, source) in &rows {
+            println!(
+                "{}{}  {}{}  {}",
+                key,
+                " ".repeat(key_width - key.chars().count()),
+                value,
+                " ".repeat(value_width - value.chars().count()),
+                source
+            );
         }
     }
 }
 
+/// 来源层级及覆盖关系，如 `project（覆盖 user、system）`
+fn annotate(source: EnvSource, sources: Option<&Vec<EnvSource>>) -> String {
+    let Some(sources) = sources else {
+        return source.to_string();
+    };
+    let Some(index) = sources.iter().position(|s| *s == source) else {
+        return source.to_string();
+    };
+
+    let join = |sources: &[EnvSource]| {
+        sources
+            .iter()
+            .rev()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("、")
+    };
+    let mut notes = Vec::new();
+    if index + 1 < sources.len() {
+        notes.push(format!("被 {} 覆盖", join(&sources[index + 1..])));
+    }
+    if index > 0 {
+        notes.push(format!("覆盖 {}", join(&sources[..index])));
+    }
+    format!("{}（{}）", source, notes.join("，"))
+}
+
 #[async_trait]
 impl CommandHandler for ListCommand {
-    async fn execute(&self, _ctx: &CommandContext) -> Result<()> {
-        let vars = self.env_service.list(self.source).await?;
+    async fn execute(&self, ctx: &CommandContext) -> Result<()> {
+        if self.options.show_source
+            && let ListView::Format(format) = self.view
+            && !matches!(format.name(), "env" | "json")
+        {
+            return Err(DomainError::InvalidArgument(format!(
+                "--show-source 仅适用于 env、json 与 table 格式，不支持 {}",
+                format.name()
+            )));
+        }
+        let grep = self
+            .options
+            .grep
+            .as_deref()
+            .map(|pattern| {
+                Regex::new(pattern).map_err(|e| {
+                    DomainError::InvalidArgument(format!("无效的正则表达式 {}: {}", pattern, e))
+                })
+            })
+            .transpose()?;
 
-        match self.format {
-            OutputFormat::Env => {
-                for var in &vars {
-                    println!("{}={}", var.key, var.value);
-                }
+        let mut vars = if self.options.raw {
+            self.env_service.list(self.source).await?
+        } else {
+            let (vars, failures) = self.env_service.list_resolved_lossy(self.source).await?;
+            for e in failures {
+                eprintln!("警告: {}，显示存储的值", e);
             }
-            OutputFormat::Json => {
-                println!("{}", serde_json::to_string_pretty(&vars)?);
+            vars
+        };
+        if let Some(prefix) = &self.options.prefix {
+            vars.retain(|v| v.key.starts_with(prefix.as_str()));
+        }
+
+        // 定义在多个层级中的变量及其来源（优先级从低到高）
+        let shadows: HashMap<String, Vec<EnvSource>> =
+            if self.options.only_shadowed || matches!(self.view, ListView::Table) {
+                self.env_service
+                    .check_conflicts()
+                    .await?
+                    .into_iter()
+                    .collect()
+            } else {
+                HashMap::new()
+            };
+        if self.options.only_shadowed {
+            vars.retain(|v| shadows.contains_key(&v.key));
+        }
+        self.sort(&mut vars);
+
+        let mut vars = self.env_service.mask(vars, ctx.reveal);
+        // 匹配显示的值，敏感变量未 --reveal 时不会按原值命中
+        if let Some(grep) = &grep {
+            vars.retain(|m| grep.is_match(&m.var.key) || grep.is_match(&m.var.value));
+        }
+
+        match self.view {
+            ListView::Table => Self::print_table(&vars, &shadows),
+            // env 格式用于阅读，原样输出 KEY=VALUE，不按 dotenv 规则加引号
+            ListView::Format(format) if format.name() == "env" => {
+                for masked in &vars {
+                    if self.options.show_source {
+                        println!(
+                            "{}={}  # {}",
+                            masked.var.key, masked.var.value, masked.var.source
+                        );
+                    } else {
+                        println!("{}={}", masked.var.key, masked.var.value);
+                    }
+                }
             }
+            ListView::Format(format) => print!("{}", format.render(&vars)?),
         }
 
         Ok(())
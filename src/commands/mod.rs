@@ -7,21 +7,29 @@ use async_trait::async_trait;
 
 pub mod cache;
 pub mod config;
+pub mod diff;
 pub mod doctor;
+pub mod explain;
 pub mod export;
 pub mod get;
+pub mod hook;
 pub mod import;
+pub mod key;
 pub mod list;
 pub mod run;
 pub mod set;
+pub mod shell;
 pub mod status;
 pub mod system;
 pub mod unset;
+pub mod up;
 
 /// 命令上下文
 #[derive(Debug)]
 pub struct CommandContext {
     pub verbose: bool,
+    /// 显示敏感变量的完整值
+    pub reveal: bool,
 }
 
 /// 命令处理器 trait
@@ -1,111 +1,532 @@
 //! run 命令处理器
+//!
+//! Unix 上直接 `exec` 目标命令，信号与退出状态完全由目标命令决定；
+//! 其他平台启动子进程并传递其退出码。
+//!
+//! 子进程默认继承父进程环境，再叠加 envcli 管理的变量；`--clean` 从空环境开始，
+//! 只保留 `--keep`/`--keep-pattern` 指定的父进程变量。
+//!
+//! `--watch` 时 envcli 作为父进程监督子进程：层级文件或 `--from-file` 变化后重新解析，
+//! 环境确有变化时重启子进程，或按 `--reload-signal` 向其发送信号。
+//!
+//! `--secrets-as-files` 时敏感变量不进入子进程环境，而是写入临时目录中的文件，
+//! 子进程获得 `KEY_FILE=<路径>`；envcli 监督子进程，在其退出后删除该目录。
 
 use super::{CommandContext, CommandHandler};
 use crate::application::services::EnvService;
 use crate::domain::error::{DomainError, Result};
+use crate::domain::models::EnvSource;
+use crate::domain::secrets::{glob_match, mask_value};
+use crate::infrastructure::secret_files::SecretDir;
+use crate::infrastructure::watch::{FileWatcher, POLL_INTERVAL};
+use crate::infrastructure::{dotenv, process};
 use async_trait::async_trait;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::process::Child;
+
+/// 注入子进程的合并变量，`raw` 为 true 时不解析插值
+pub async fn merged_env(env_service: &EnvService, raw: bool) -> Result<HashMap<String, String>> {
+    let vars = if raw {
+        env_service.list(None).await?
+    } else {
+        env_service.list_resolved(None, true).await?
+    };
+    Ok(vars.into_iter().map(|v| (v.key, v.value)).collect())
+}
+
+/// 子进程变量的来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Origin {
+    /// 继承自父进程
+    Inherited,
+    /// envcli 层级
+    Layer(EnvSource),
+    /// `--from-file`
+    File,
+    /// `--env`
+    Override,
+    /// `up` 的进程专属变量
+    Process,
+    /// `--secrets-as-files` 生成的 `KEY_FILE`
+    SecretFile,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Origin::Inherited => write!(f, "inherited"),
+            Origin::Layer(source) => write!(f, "{}", source),
+            Origin::File => write!(f, "--from-file"),
+            Origin::Override => write!(f, "--env"),
+            Origin::Process => write!(f, "processes"),
+            Origin::SecretFile => write!(f, "--secrets-as-files"),
+        }
+    }
+}
+
+/// 注入子进程的变量
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChildVar {
+    pub value: String,
+    pub origin: Origin,
+    pub secret: bool,
+}
+
+/// 子进程环境：变量名 -> 变量
+pub type ChildEnv = BTreeMap<String, ChildVar>;
+
+/// run 选项
+#[derive(Debug, Clone, Default)]
+pub struct RunOptions {
+    /// 注入存储的原始值，不解析插值
+    pub raw: bool,
+    /// 从文件加载变量
+    pub from_file: Option<String>,
+    /// 临时变量 KEY=VALUE（最高优先级）
+    pub overrides: Vec<String>,
+    /// 不继承父进程环境
+    pub clean: bool,
+    /// `--clean` 时保留的父进程变量名
+    pub keep: Vec<String>,
+    /// `--clean` 时保留的父进程变量名模式（`*` 通配）
+    pub keep_patterns: Vec<String>,
+    /// 不继承匹配这些模式的父进程变量
+    pub drop: Vec<String>,
+    /// 只打印子进程将获得的环境，不执行命令
+    pub print_env: bool,
+    /// 文件变化时重启子进程
+    pub watch: bool,
+    /// 文件变化时改为向子进程发送此信号
+    pub reload_signal: Option<i32>,
+    /// 重启时等待子进程响应 SIGTERM 的时间，默认 [`process::STOP_TIMEOUT`]
+    pub stop_timeout: Option<Duration>,
+    /// 敏感变量以文件传递，子进程获得 `KEY_FILE`
+    pub secrets_as_files: bool,
+}
+
+impl RunOptions {
+    /// 父进程变量是否传给子进程
+    fn inherits(&self, key: &str) -> bool {
+        if self.clean {
+            self.keep.iter().any(|k| k == key)
+                || self.keep_patterns.iter().any(|p| glob_match(p, key))
+        } else {
+            !self.drop.iter().any(|p| glob_match(p, key))
+        }
+    }
+}
+
+/// 解析子进程的完整环境，优先级从低到高：父进程、各层级、`--from-file`、`--env`
+pub async fn child_env(env_service: &EnvService, options: &RunOptions) -> Result<ChildEnv> {
+    let classifier = env_service.classifier();
+    let mut env = ChildEnv::new();
+
+    for (key, value) in std::env::vars_os() {
+        let key = key.to_string_lossy().into_owned();
+        if options.inherits(&key) {
+            env.insert(
+                key.clone(),
+                ChildVar {
+                    value: value.to_string_lossy().into_owned(),
+                    origin: Origin::Inherited,
+                    secret: classifier.matches_key(&key),
+                },
+            );
+        }
+    }
+
+    let vars = if options.raw {
+        env_service.list(None).await?
+    } else {
+        env_service.list_resolved(None, true).await?
+    };
+    // system 层即父进程环境，已按 keep/drop 处理
+    for var in vars.into_iter().filter(|v| v.source != EnvSource::System) {
+        let secret = classifier.is_secret(&var);
+        env.insert(
+            var.key,
+            ChildVar {
+                value: var.value,
+                origin: Origin::Layer(var.source),
+                secret,
+            },
+        );
+    }
+
+    if let Some(file) = &options.from_file {
+        let content = tokio::fs::read_to_string(file)
+            .await
+            .map_err(|e| DomainError::Io(format!("读取文件失败: {}", e)))?;
+
+        let entries = dotenv::parse(&content).map_err(|e| dotenv::in_file(Path::new(file), e))?;
+        for entry in entries {
+            let secret = classifier.matches_key(&entry.key);
+            let value = entry.literal_value();
+            env.insert(
+                entry.key,
+                ChildVar {
+                    value,
+                    origin: Origin::File,
+                    secret,
+                },
+            );
+        }
+    }
+
+    for var in &options.overrides {
+        let (key, value) = parse_temp_var(var)?;
+        let secret = classifier.matches_key(&key);
+        env.insert(
+            key,
+            ChildVar {
+                value,
+                origin: Origin::Override,
+                secret,
+            },
+        );
+    }
+
+    Ok(env)
+}
+
+/// 解析临时变量 KEY=VALUE
+fn parse_temp_var(s: &str) -> Result<(String, String)> {
+    let pos = s
+        .find('=')
+        .ok_or_else(|| DomainError::EnvParse(format!("无效的环境变量格式: {}", s)))?;
+
+    let key = s[..pos].to_string();
+    let value = s[pos + 1..].to_string();
+
+    Ok((key, value))
+}
+
+/// 把敏感变量换成指向 `dir/KEY` 的 `KEY_FILE`，返回新环境与要写入文件的 (变量名, 值)
+fn file_secrets(env: &ChildEnv, dir: &Path) -> Result<(ChildEnv, Vec<(String, String)>)> {
+    let (secrets, mut result): (ChildEnv, ChildEnv) =
+        env.clone().into_iter().partition(|(_, var)| var.secret);
+
+    let mut files = Vec::new();
+    for (key, var) in secrets {
+        if key.is_empty() || key.contains(['/', '\\']) || key.starts_with('.') {
+            return Err(DomainError::InvalidArgument(format!(
+                "变量名 {} 不能用作文件名，无法以文件传递",
+                key
+            )));
+        }
+        let file_key = format!("{}_FILE", key);
+        if result
+            .get(&file_key)
+            .is_some_and(|v| v.origin != Origin::Inherited)
+        {
+            return Err(DomainError::InvalidArgument(format!(
+                "{} 已定义，无法以文件传递 {}",
+                file_key, key
+            )));
+        }
+        result.insert(
+            file_key,
+            ChildVar {
+                value: dir.join(&key).to_string_lossy().into_owned(),
+                origin: Origin::SecretFile,
+                secret: false,
+            },
+        );
+        files.push((key, var.value));
+    }
+    Ok((result, files))
+}
+
+/// 构建执行目标命令的进程，环境与 [`child_env`] 的结果完全一致
+pub fn build_command(
+    program: &str,
+    args: &[String],
+    env: &ChildEnv,
+    options: &RunOptions,
+) -> std::process::Command {
+    let mut cmd = std::process::Command::new(program);
+    cmd.args(args);
+    if options.clean {
+        cmd.env_clear();
+    } else {
+        // 被 --drop 排除或改为以文件传递的父进程变量
+        for (key, _) in std::env::vars_os() {
+            if !env.contains_key(key.to_string_lossy().as_ref()) {
+                cmd.env_remove(key);
+            }
+        }
+    }
+    cmd.envs(
+        env.iter()
+            .filter(|(_, var)| var.origin != Origin::Inherited)
+            .map(|(key, var)| (key, &var.value)),
+    );
+    cmd
+}
+
+/// 等待子进程退出，没有子进程时一直挂起
+async fn wait_child(child: &mut Option<Child>) -> Result<std::process::ExitStatus> {
+    match child {
+        Some(child) => child
+            .wait()
+            .await
+            .map_err(|e| DomainError::CommandExecutionFailed(e.to_string())),
+        None => std::future::pending().await,
+    }
+}
+
+/// 两次解析之间变化的变量，每处一行：`+ KEY=v`、`- KEY`、`~ KEY: old → new`
+fn describe_changes(old: &ChildEnv, new: &ChildEnv, reveal: bool) -> Vec<String> {
+    let display = |value: &str, secret: bool| {
+        if secret && !reveal {
+            mask_value(value)
+        } else {
+            value.to_string()
+        }
+    };
+
+    let mut changes = Vec::new();
+    for (key, var) in old {
+        match new.get(key) {
+            None => changes.push((key, format!("- {}", key))),
+            Some(next) if next.value != var.value => {
+                let secret = var.secret || next.secret;
+                changes.push((
+                    key,
+                    format!(
+                        "~ {}: {} → {}",
+                        key,
+                        display(&var.value, secret),
+                        display(&next.value, secret)
+                    ),
+                ));
+            }
+            Some(_) => {}
+        }
+    }
+    for (key, var) in new {
+        if !old.contains_key(key) {
+            changes.push((
+                key,
+                format!("+ {}={}", key, display(&var.value, var.secret)),
+            ));
+        }
+    }
+    changes.sort_by(|a, b| a.0.cmp(b.0));
+    changes.into_iter().map(|(_, line)| line).collect()
+}
+
+/// 文件路径尽量显示为相对当前目录的路径
+fn display_path(path: &std::path::Path) -> String {
+    std::env::current_dir()
+        .ok()
+        .and_then(|cwd| path.strip_prefix(cwd).ok())
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
 
 /// run 命令
 pub struct RunCommand {
     env_service: Arc<EnvService>,
-    temp_vars: Vec<String>,
-    from_file: Option<String>,
     command: Vec<String>,
+    options: RunOptions,
 }
 
 impl RunCommand {
-    pub fn new(
-        env_service: Arc<EnvService>,
-        temp_vars: Vec<String>,
-        from_file: Option<String>,
-        command: Vec<String>,
-    ) -> Self {
+    pub fn new(env_service: Arc<EnvService>, command: Vec<String>, options: RunOptions) -> Self {
         Self {
             env_service,
-            temp_vars,
-            from_file,
             command,
+            options,
         }
     }
 
-    /// 解析临时变量 KEY=VALUE
-    fn parse_temp_var(s: &str) -> Result<(String, String)> {
-        let pos = s
-            .find('=')
-            .ok_or_else(|| DomainError::EnvParse(format!("无效的环境变量格式: {}", s)))?;
+    /// `--secrets-as-files` 时把敏感值写入新的临时目录，返回子进程实际获得的环境
+    fn deliver_secrets(&self, env: &ChildEnv) -> Result<(ChildEnv, Option<SecretDir>)> {
+        if !self.options.secrets_as_files {
+            return Ok((env.clone(), None));
+        }
+        let dir = SecretDir::new()?;
+        let (env, files) = file_secrets(env, dir.path())?;
+        for (key, value) in &files {
+            dir.write(key, value)?;
+        }
+        Ok((env, Some(dir)))
+    }
 
-        let key = s[..pos].to_string();
-        let value = s[pos + 1..].to_string();
+    /// 启动子进程，返回的临时目录需保留到子进程退出
+    fn spawn(
+        &self,
+        program: &str,
+        args: &[String],
+        env: &ChildEnv,
+    ) -> Result<(Child, Option<SecretDir>)> {
+        let (env, secrets) = self.deliver_secrets(env)?;
+        let mut cmd = build_command(program, args, &env, &self.options).into();
+        Ok((process::spawn(&mut cmd)?, secrets))
+    }
 
-        Ok((key, value))
+    /// 需要监视的文件：各层级文件与 `--from-file`
+    async fn watched_files(&self) -> Result<Vec<PathBuf>> {
+        let mut files = self.env_service.source_files().await?;
+        files.extend(self.options.from_file.as_ref().map(PathBuf::from));
+        Ok(files)
     }
-}
 
-#[async_trait]
-impl CommandHandler for RunCommand {
-    async fn execute(&self, _ctx: &CommandContext) -> Result<()> {
-        // 1. 获取所有存储的环境变量
-        let mut env_vars: HashMap<String, String> = self
-            .env_service
-            .list(None)
-            .await?
-            .into_iter()
-            .map(|v| (v.key, v.value))
-            .collect();
-
-        // 2. 从文件加载变量
-        if let Some(file) = &self.from_file {
-            let content = tokio::fs::read_to_string(file)
-                .await
-                .map_err(|e| DomainError::Io(format!("读取文件失败: {}", e)))?;
-
-            for line in content.lines() {
-                let trimmed = line.trim();
-                if trimmed.is_empty() || trimmed.starts_with('#') {
-                    continue;
+    /// 监视文件并按需重启或通知子进程，子进程因转发的信号退出后以其状态退出
+    async fn watch(
+        &self,
+        program: &str,
+        args: &[String],
+        mut env: ChildEnv,
+        ctx: &CommandContext,
+    ) -> Result<()> {
+        let mut watcher = FileWatcher::new(self.watched_files().await?);
+        let mut signals = process::Signals::new()?;
+        let mut ticker = tokio::time::interval(POLL_INTERVAL);
+        let (running, mut secrets) = self.spawn(program, args, &env)?;
+        let mut child = Some(running);
+        let mut terminating = false;
+
+        if ctx.verbose {
+            for file in watcher.files() {
+                eprintln!("[envcli] 监视 {}", display_path(file));
+            }
+        }
+
+        loop {
+            tokio::select! {
+                status = wait_child(&mut child) => {
+                    child = None;
+                    drop(secrets.take());
+                    let code = process::exit_code(status?);
+                    if terminating {
+                        std::process::exit(code);
+                    }
+                    eprintln!("[envcli] {} 已退出（状态 {}），文件变化后重新启动", program, code);
                 }
+                signal = signals.recv() => match child.as_mut() {
+                    Some(child) => {
+                        process::kill(child, signal);
+                        terminating = true;
+                    }
+                    None => std::process::exit(128 + signal),
+                },
+                _ = ticker.tick() => {
+                    let changed = watcher.poll();
+                    if changed.is_empty() {
+                        continue;
+                    }
 
-                if let Some(pos) = trimmed.find('=') {
-                    let key = trimmed[..pos].to_string();
-                    let value = trimmed[pos + 1..].to_string();
-                    env_vars.insert(key, value);
+                    self.env_service.clear_cache().await;
+                    let resolved = match child_env(&self.env_service, &self.options).await {
+                        Ok(resolved) => resolved,
+                        Err(e) => {
+                            eprintln!("[envcli] 重新解析环境失败，保持当前进程: {}", e);
+                            continue;
+                        }
+                    };
+                    // profile 的 extends 可能改变，需要监视的文件随之变化
+                    watcher.set_files(self.watched_files().await?);
+
+                    let names: Vec<String> = changed.iter().map(|p| display_path(p)).collect();
+                    let changes = describe_changes(&env, &resolved, ctx.reveal);
+                    if changes.is_empty() {
+                        if ctx.verbose {
+                            eprintln!("[envcli] {} 已修改，环境未变化", names.join("、"));
+                        }
+                        continue;
+                    }
+                    eprintln!("[envcli] {} 已修改：", names.join("、"));
+                    for line in &changes {
+                        eprintln!("  {}", line);
+                    }
+                    env = resolved;
+
+                    match (self.options.reload_signal, child.as_mut()) {
+                        (Some(signal), Some(child)) => {
+                            eprintln!("[envcli] 向 {} 发送 {}", program, process::signal_name(signal));
+                            process::kill(child, signal);
+                        }
+                        _ => {
+                            if let Some(mut running) = child.take() {
+                                let timeout = self.options.stop_timeout.unwrap_or(process::STOP_TIMEOUT);
+                                process::stop(&mut running, timeout).await?;
+                            }
+                            drop(secrets.take());
+                            eprintln!("[envcli] 重新启动 {}", program);
+                            let (running, dir) = self.spawn(program, args, &env)?;
+                            child = Some(running);
+                            secrets = dir;
+                        }
+                    }
                 }
             }
         }
+    }
+
+    /// 按变量名输出子进程环境，标注每个变量的来源
+    fn print_env(env: &ChildEnv, reveal: bool) {
+        for (key, var) in env {
+            let value = if var.secret && !reveal {
+                mask_value(&var.value)
+            } else {
+                var.value.clone()
+            };
+            println!("{}={}  # {}", key, value, var.origin);
+        }
+    }
+}
+
+#[async_trait]
+impl CommandHandler for RunCommand {
+    async fn execute(&self, ctx: &CommandContext) -> Result<()> {
+        let env = child_env(&self.env_service, &self.options).await?;
 
-        // 3. 应用临时变量（最高优先级）
-        for var in &self.temp_vars {
-            let (key, value) = Self::parse_temp_var(var)?;
-            env_vars.insert(key, value);
+        if self.options.print_env {
+            let env = if self.options.secrets_as_files {
+                file_secrets(&env, Path::new("<临时目录>"))?.0
+            } else {
+                env
+            };
+            Self::print_env(&env, ctx.reveal);
+            return Ok(());
         }
 
-        // 4. 执行命令
-        if self.command.is_empty() {
+        let Some((program, args)) = self.command.split_first() else {
             return Err(DomainError::InvalidArgument(
                 "未指定要执行的命令".to_string(),
             ));
+        };
+        if self.options.watch {
+            return self.watch(program, args, env, ctx).await;
         }
+        if self.options.secrets_as_files {
+            // 先接管终止类信号，保证 envcli 在子进程退出前不会被信号直接终止而遗留目录
+            let mut signals = process::Signals::new()?;
+            let (env, secrets) = self.deliver_secrets(&env)?;
+            let cmd = build_command(program, args, &env, &self.options).into();
+            let status = process::supervise_with(cmd, &mut signals).await;
+            drop(secrets);
+            std::process::exit(process::exit_code(status?));
+        }
+        let mut cmd = build_command(program, args, &env, &self.options);
 
-        let program = &self.command[0];
-        let args = &self.command[1..];
-
-        let mut cmd = tokio::process::Command::new(program);
-        cmd.args(args);
-
-        // 设置环境变量
-        for (key, value) in &env_vars {
-            cmd.env(key, value);
+        #[cfg(unix)]
+        {
+            Err(process::exec(&mut cmd))
         }
 
         // 执行并传递退出码
-        let status = cmd
-            .status()
-            .await
-            .map_err(|e| DomainError::CommandExecutionFailed(e.to_string()))?;
-
-        let code = status.code().unwrap_or(1);
-        std::process::exit(code);
+        #[cfg(not(unix))]
+        {
+            let status = process::supervise(cmd.into()).await?;
+            std::process::exit(process::exit_code(status));
+        }
     }
 }
@@ -3,7 +3,8 @@
 use super::{CommandContext, CommandHandler};
 use crate::application::services::EnvService;
 use crate::domain::error::Result;
-use crate::domain::models::EnvSource;
+use crate::domain::models::{EnvSource, EnvVar};
+use crate::domain::repositories::SetOptions;
 use async_trait::async_trait;
 use std::sync::Arc;
 
@@ -13,6 +14,7 @@ pub struct SetCommand {
     key: String,
     value: String,
     source: EnvSource,
+    options: SetOptions,
 }
 
 impl SetCommand {
@@ -21,12 +23,14 @@ impl SetCommand {
         key: String,
         value: String,
         source: EnvSource,
+        options: SetOptions,
     ) -> Self {
         Self {
             env_service,
             key,
             value,
             source,
+            options,
         }
     }
 }
@@ -35,10 +39,15 @@ impl SetCommand {
 impl CommandHandler for SetCommand {
     async fn execute(&self, ctx: &CommandContext) -> Result<()> {
         self.env_service
-            .set(&self.key, &self.value, self.source)
+            .set_with_options(&self.key, &self.value, self.source, &self.options)
             .await?;
         if ctx.verbose {
-            println!("✓ 已设置变量 {} = {}", self.key, self.value);
+            let var = EnvVar {
+                secret: self.options.mark_secret,
+                ..EnvVar::new(self.key.clone(), self.value.clone(), self.source)
+            };
+            let shown = self.env_service.classifier().mask(var, ctx.reveal);
+            println!("✓ 已设置变量 {} = {}", self.key, shown.var.value);
         }
         Ok(())
     }
@@ -0,0 +1,157 @@
+//! shell 命令处理器
+//!
+//! 以合并后的变量启动交互式子 shell，退出后回到原 shell，原环境不受影响。
+
+use super::{CommandContext, CommandHandler};
+use crate::application::services::EnvService;
+use crate::commands::run::merged_env;
+use crate::domain::error::{DomainError, Result};
+use crate::domain::repositories::StorageConfig;
+use crate::infrastructure::process;
+use crate::infrastructure::secret_files::SecretDir;
+use crate::infrastructure::shell::Shell;
+use async_trait::async_trait;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// 标记当前进程位于 envcli shell 中的变量，值为项目根目录
+pub const ACTIVE_ENV: &str = "ENVCLI_ACTIVE";
+
+/// shell 命令
+pub struct ShellCommand {
+    env_service: Arc<EnvService>,
+    storage: StorageConfig,
+    force: bool,
+    raw: bool,
+}
+
+impl ShellCommand {
+    pub fn new(
+        env_service: Arc<EnvService>,
+        storage: StorageConfig,
+        force: bool,
+        raw: bool,
+    ) -> Self {
+        Self {
+            env_service,
+            storage,
+            force,
+            raw,
+        }
+    }
+
+    /// 提示符前缀，如 `(envcli:myapp) `、`(envcli:myapp/dev) `
+    fn prompt_prefix(&self, root: &Path) -> String {
+        let name = root
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| root.display().to_string());
+        match self.env_service.active_profile() {
+            Some(profile) => format!("(envcli:{}/{}) ", name, profile),
+            None => format!("(envcli:{}) ", name),
+        }
+    }
+}
+
+#[async_trait]
+impl CommandHandler for ShellCommand {
+    async fn execute(&self, ctx: &CommandContext) -> Result<()> {
+        if let Ok(active) = std::env::var(ACTIVE_ENV)
+            && !self.force
+        {
+            return Err(DomainError::InvalidArgument(format!(
+                "已在 envcli shell 中（{}={}），请先 exit 退出，或使用 --force 嵌套",
+                ACTIVE_ENV, active
+            )));
+        }
+
+        let root = self
+            .storage
+            .project_dir
+            .parent()
+            .unwrap_or(&self.storage.project_dir)
+            .to_path_buf();
+        let prefix = self.prompt_prefix(&root);
+        let program = default_shell();
+
+        let mut cmd = tokio::process::Command::new(&program);
+        cmd.envs(merged_env(&self.env_service, self.raw).await?);
+        cmd.env(ACTIVE_ENV, &root);
+
+        // 提示符前缀：通过各 shell 的启动参数在用户配置之后设置；
+        // 初始化文件放在随机命名、仅当前用户可访问的临时目录中
+        let mut init_dir = None;
+        match Shell::parse(&program.to_string_lossy()) {
+            Some(Shell::Bash) => {
+                let dir = init_dir.insert(SecretDir::new()?);
+                let rc = dir.write(
+                    "bashrc",
+                    &format!(
+                        "[ -f ~/.bashrc ] && . ~/.bashrc\nPS1={}\"$PS1\"\n",
+                        Shell::Bash.quote(&prefix)
+                    ),
+                )?;
+                cmd.arg("--rcfile").arg(rc).arg("-i");
+            }
+            Some(Shell::Zsh) => {
+                // 临时 ZDOTDIR 先加载用户配置，再还原 ZDOTDIR
+                let orig = std::env::var("ZDOTDIR").ok();
+                let dir = match &orig {
+                    Some(dir) => Shell::Zsh.quote(dir),
+                    None => "\"$HOME\"".to_string(),
+                };
+                let restore = match &orig {
+                    Some(_) => format!("export ZDOTDIR={}", dir),
+                    None => "unset ZDOTDIR".to_string(),
+                };
+                let zdotdir = init_dir.insert(SecretDir::new()?);
+                zdotdir.write(
+                    ".zshenv",
+                    &format!("[ -f {0}/.zshenv ] && . {0}/.zshenv\n", dir),
+                )?;
+                zdotdir.write(
+                    ".zshrc",
+                    &format!(
+                        "[ -f {0}/.zshrc ] && . {0}/.zshrc\nPS1={1}\"$PS1\"\n{2}\n",
+                        dir,
+                        Shell::Zsh.quote(&prefix),
+                        restore
+                    ),
+                )?;
+                cmd.env("ZDOTDIR", zdotdir.path()).arg("-i");
+            }
+            Some(Shell::Fish) => {
+                cmd.arg("--init-command").arg(format!(
+                    "functions -c fish_prompt __envcli_fish_prompt; \
+                     function fish_prompt; printf '%s' {}; __envcli_fish_prompt; end",
+                    Shell::Fish.quote(&prefix)
+                ));
+            }
+            _ if cfg!(windows) => {
+                cmd.env("PROMPT", format!("{}$P$G", prefix));
+            }
+            _ => {
+                cmd.env("PS1", format!("{}$ ", prefix));
+            }
+        }
+
+        if ctx.verbose {
+            eprintln!("启动 {}（{}）", program.display(), root.display());
+        }
+
+        // 监督子 shell 以便退出后清理临时初始化文件
+        let status = process::supervise(cmd).await;
+        drop(init_dir);
+
+        std::process::exit(process::exit_code(status?));
+    }
+}
+
+/// 用户的默认 shell：`$SHELL`，Windows 上为 `%COMSPEC%`
+fn default_shell() -> PathBuf {
+    let var = if cfg!(windows) { "COMSPEC" } else { "SHELL" };
+    std::env::var_os(var)
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(if cfg!(windows) { "cmd.exe" } else { "/bin/sh" }))
+}
@@ -4,18 +4,22 @@ use super::{CommandContext, CommandHandler};
 use crate::application::services::EnvService;
 use crate::domain::error::Result;
 use crate::domain::models::EnvSource;
-use crate::infrastructure::paths;
+use crate::domain::repositories::StorageConfig;
 use async_trait::async_trait;
 use std::sync::Arc;
 
 /// status 命令
 pub struct StatusCommand {
     env_service: Arc<EnvService>,
+    storage: StorageConfig,
 }
 
 impl StatusCommand {
-    pub fn new(env_service: Arc<EnvService>) -> Self {
-        Self { env_service }
+    pub fn new(env_service: Arc<EnvService>, storage: StorageConfig) -> Self {
+        Self {
+            env_service,
+            storage,
+        }
     }
 }
 
@@ -23,12 +27,28 @@ impl StatusCommand {
 impl CommandHandler for StatusCommand {
     async fn execute(&self, ctx: &CommandContext) -> Result<()> {
         // 配置目录
-        let config_dir = paths::get_config_dir()?;
-        println!("配置目录: {}", config_dir.display());
+        println!("配置目录: {}", self.storage.user_dir.display());
+        println!("项目目录: {}", self.storage.project_dir.display());
+
+        // 当前 profile
+        let profile = self.env_service.active_profile();
+        if profile.is_some() {
+            let chain = self.env_service.profile_chain().await?;
+            println!("当前 profile: {}", chain.join(" -> "));
+        }
 
         // 各层级状态
-        for source in [EnvSource::User, EnvSource::Project, EnvSource::Local] {
-            let path = paths::get_layer_path(&source)?;
+        for source in [
+            EnvSource::User,
+            EnvSource::Project,
+            EnvSource::Profile,
+            EnvSource::Local,
+        ] {
+            if source == EnvSource::Profile && profile.is_none() {
+                continue;
+            }
+
+            let path = self.env_service.layer_path(&source)?;
             let exists = path.exists();
             let status = if exists { "存在" } else { "不存在" };
 
@@ -56,7 +76,8 @@ impl CommandHandler for StatusCommand {
 
         if ctx.verbose && !all_vars.is_empty() {
             println!("\n当前所有变量:");
-            for var in &all_vars {
+            for masked in self.env_service.mask(all_vars, ctx.reveal) {
+                let var = masked.var;
                 println!("  {} = {} (来自 {})", var.key, var.value, var.source);
             }
         }
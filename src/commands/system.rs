@@ -1,80 +1,91 @@
-//! system-set/system-unset 命令处理器
+//! system-set/system-unset/system list/system diff 命令处理器
 
 use super::{CommandContext, CommandHandler};
 use crate::domain::error::{DomainError, Result};
+use crate::domain::secrets::SecretClassifier;
+use crate::infrastructure::paths;
+use crate::infrastructure::system_env::{self, PersistedVar, SystemScope};
+#[cfg(windows)]
+use crate::infrastructure::system_env::{managed_registry_keys, mark_managed, open_registry};
+#[cfg(not(windows))]
+use crate::infrastructure::{
+    managed_block::{self, ManagedBlock},
+    shell::Shell,
+    system_env::EnvFile,
+};
 use async_trait::async_trait;
+use std::path::PathBuf;
+
+/// system-set/system-unset 的公共选项
+#[derive(Debug, Clone)]
+#[cfg_attr(windows, allow(dead_code))]
+pub struct SystemOptions {
+    /// 作用域 (global/machine/session)
+    pub scope: String,
+    /// global 作用域写入的 shell 启动文件
+    pub shell: Option<String>,
+    /// machine 作用域文件所在的文件系统根目录
+    pub root: PathBuf,
+    /// 仅显示改动，不写入
+    pub dry_run: bool,
+}
 
 /// system-set 命令
 pub struct SystemSetCommand {
     key: String,
     value: String,
-    scope: String,
+    options: SystemOptions,
 }
 
 impl SystemSetCommand {
-    pub fn new(key: String, value: String, scope: String) -> Self {
-        Self { key, value, scope }
+    pub fn new(key: String, value: String, options: SystemOptions) -> Self {
+        Self {
+            key,
+            value,
+            options,
+        }
     }
 }
 
 #[async_trait]
 impl CommandHandler for SystemSetCommand {
-    async fn execute(&self, _ctx: &CommandContext) -> Result<()> {
-        if self.scope != "global" && self.scope != "machine" {
-            return Err(DomainError::InvalidArgument(
-                "scope 必须是 'global' 或 'machine'".to_string(),
-            ));
-        }
+    async fn execute(&self, ctx: &CommandContext) -> Result<()> {
+        let scope = SystemScope::parse(&self.options.scope)?;
+        validate_name(&self.key)?;
 
         #[cfg(windows)]
         {
-            use winreg::RegKey;
             use winreg::enums::*;
 
-            let hkcu = RegKey::predef(HKEY_CURRENT_USER);
-            let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
-
-            let (key, reg_path) = if self.scope == "machine" {
-                (
-                    hklm,
-                    "SYSTEM\\CurrentControlSet\\Control\\Session Manager\\Environment",
-                )
-            } else {
-                (hkcu, "Environment")
-            };
-
-            let env = key
-                .open_subkey_with_flags(reg_path, KEY_WRITE)
-                .map_err(|e| DomainError::SystemEnvWriteFailed(e.to_string()))?;
+            let env = open_registry(scope, KEY_WRITE)?;
+            if self.options.dry_run {
+                println!("将设置 {} 级系统环境变量 {}", scope, self.key);
+                return Ok(());
+            }
 
             env.set_value(&self.key, &self.value)
                 .map_err(|e| DomainError::SystemEnvWriteFailed(e.to_string()))?;
+            mark_managed(scope, &self.key, true)?;
+
+            if ctx.verbose {
+                println!("✓ 已设置系统环境变量 {}", self.key);
+            }
         }
 
         #[cfg(not(windows))]
         {
-            if self.scope == "machine" {
-                return Err(DomainError::PermissionDenied(
-                    "Unix 系统不支持机器级环境变量".to_string(),
-                ));
-            }
+            // Unix: 写入作用域对应文件中的受管区块
+            let file = EnvFile::for_scope(scope, Shell::from_arg(self.options.shell.as_deref())?)?;
+            let line = file.assignment(&self.key, &self.value)?;
+            let edit = edit_file(&self.options, file, ctx, |block| {
+                block.set(&self.key, line, |l| file.key_of(l));
+                Ok(())
+            })?;
 
-            // Unix: 写入 shell 配置文件
-            let home = dirs::home_dir()
-                .ok_or_else(|| DomainError::Config("无法获取主目录".to_string()))?;
-            let profile = home.join(".bashrc");
-
-            let content = format!("\nexport {}=\"{}\"\n", self.key, self.value);
-            tokio::fs::OpenOptions::new()
-                .append(true)
-                .create(true)
-                .open(&profile)
-                .await
-                .map_err(|e| DomainError::Io(e.to_string()))?;
-
-            tokio::fs::write(&profile, content)
-                .await
-                .map_err(|e| DomainError::Io(e.to_string()))?;
+            if let Some(path) = edit {
+                println!("✓ 已在 {} 中设置 {}", path.display(), self.key);
+                println!("  {}", file.activation_hint(&path));
+            }
         }
 
         Ok(())
@@ -84,58 +95,256 @@ impl CommandHandler for SystemSetCommand {
 /// system-unset 命令
 pub struct SystemUnsetCommand {
     key: String,
-    scope: String,
+    options: SystemOptions,
 }
 
 impl SystemUnsetCommand {
-    pub fn new(key: String, scope: String) -> Self {
-        Self { key, scope }
+    pub fn new(key: String, options: SystemOptions) -> Self {
+        Self { key, options }
     }
 }
 
 #[async_trait]
 impl CommandHandler for SystemUnsetCommand {
-    async fn execute(&self, _ctx: &CommandContext) -> Result<()> {
-        if self.scope != "global" && self.scope != "machine" {
-            return Err(DomainError::InvalidArgument(
-                "scope 必须是 'global' 或 'machine'".to_string(),
-            ));
-        }
+    async fn execute(&self, ctx: &CommandContext) -> Result<()> {
+        let scope = SystemScope::parse(&self.options.scope)?;
+        validate_name(&self.key)?;
 
         #[cfg(windows)]
         {
-            use winreg::RegKey;
             use winreg::enums::*;
 
-            let hkcu = RegKey::predef(HKEY_CURRENT_USER);
-            let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
+            // 只删除 envcli 写入过的变量，不触碰其他程序设置的值
+            if !managed_registry_keys(scope)?.contains(&self.key) {
+                return Err(DomainError::NotFound(format!(
+                    "{}（{} 级中由 envcli 设置的变量）",
+                    self.key, scope
+                )));
+            }
 
-            let (key, reg_path) = if self.scope == "machine" {
-                (
-                    hklm,
-                    "SYSTEM\\CurrentControlSet\\Control\\Session Manager\\Environment",
-                )
-            } else {
-                (hkcu, "Environment")
-            };
+            let env = open_registry(scope, KEY_WRITE)?;
+            if self.options.dry_run {
+                println!("将删除 {} 级系统环境变量 {}", scope, self.key);
+                return Ok(());
+            }
 
-            let env = key
-                .open_subkey_with_flags(reg_path, KEY_WRITE)
-                .map_err(|e| DomainError::SystemEnvWriteFailed(e.to_string()))?;
+            match env.delete_value(&self.key) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                    return Err(DomainError::SystemEnvWriteFailed(e.to_string()));
+                }
+                _ => {}
+            }
+            mark_managed(scope, &self.key, false)?;
 
-            env.delete_value(&self.key)
-                .map_err(|e| DomainError::SystemEnvWriteFailed(e.to_string()))?;
+            if ctx.verbose {
+                println!("✓ 已删除系统环境变量 {}", self.key);
+            }
         }
 
         #[cfg(not(windows))]
         {
-            // Unix: 从 shell 配置文件中移除（简化处理）
+            // Unix: 从作用域对应文件的受管区块中移除
+            let file = EnvFile::for_scope(scope, Shell::from_arg(self.options.shell.as_deref())?)?;
+            let edit = edit_file(&self.options, file, ctx, |block| {
+                if block.remove(&self.key, |l| file.key_of(l)) {
+                    Ok(())
+                } else {
+                    Err(DomainError::NotFound(format!(
+                        "{}（{} 的 envcli 区块中）",
+                        self.key,
+                        file.path(&self.options.root)?.display()
+                    )))
+                }
+            })?;
+
+            if let Some(path) = edit {
+                println!("✓ 已从 {} 中移除 {}", path.display(), self.key);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// 修改文件中的受管区块
+///
+/// `--dry-run` 时打印差异并返回 `None`，否则写回文件并返回路径。
+#[cfg(not(windows))]
+fn edit_file(
+    options: &SystemOptions,
+    file: EnvFile,
+    ctx: &CommandContext,
+    edit: impl FnOnce(&mut ManagedBlock) -> Result<()>,
+) -> Result<Option<PathBuf>> {
+    let path = file.path(&options.root)?;
+    let old = managed_block::read_content(&path)?;
+    let mut block = ManagedBlock::parse(&old)
+        .map_err(|e| DomainError::Parse(format!("{}: {}", path.display(), e)))?;
+    edit(&mut block)?;
+
+    if options.dry_run {
+        print!("{}", managed_block::diff(&path, &old, &block.render()));
+        return Ok(None);
+    }
+
+    let backup = managed_block::write_with_backup(&path, &block).map_err(|e| match e {
+        DomainError::PermissionDenied(msg) if file.scope() == SystemScope::Machine => {
+            DomainError::PermissionDenied(format!(
+                "{}（修改 {} 需要 root 权限，请使用 sudo 运行，或加 --dry-run 预览改动）",
+                msg,
+                path.display()
+            ))
+        }
+        e => e,
+    })?;
+
+    if ctx.verbose
+        && let Some(backup) = backup
+    {
+        println!("  备份: {}", backup.display());
+    }
+    Ok(Some(path))
+}
+
+/// system list 命令
+pub struct SystemListCommand {
+    scope: Option<String>,
+    root: PathBuf,
+    classifier: SecretClassifier,
+}
+
+impl SystemListCommand {
+    pub fn new(scope: Option<String>, root: PathBuf, classifier: SecretClassifier) -> Self {
+        Self {
+            scope,
+            root,
+            classifier,
+        }
+    }
+}
+
+#[async_trait]
+impl CommandHandler for SystemListCommand {
+    async fn execute(&self, ctx: &CommandContext) -> Result<()> {
+        let vars = read_persisted(self.scope.as_deref(), &self.root)?;
+        if vars.is_empty() {
+            println!("envcli 尚未持久化任何系统环境变量");
+            return Ok(());
+        }
+
+        for var in &vars {
             println!(
-                "⚠️ Unix 系统请手动从 ~/.bashrc 或 ~/.zshrc 中移除 {} 变量",
-                self.key
+                "{:<8} {}={}  ({})",
+                var.scope.to_string(),
+                var.key,
+                display_value(&self.classifier, &var.key, &var.value, ctx.reveal),
+                var.location,
             );
         }
+        Ok(())
+    }
+}
+
+/// system diff 命令
+pub struct SystemDiffCommand {
+    scope: Option<String>,
+    root: PathBuf,
+    classifier: SecretClassifier,
+}
+
+impl SystemDiffCommand {
+    pub fn new(scope: Option<String>, root: PathBuf, classifier: SecretClassifier) -> Self {
+        Self {
+            scope,
+            root,
+            classifier,
+        }
+    }
+}
+
+#[async_trait]
+impl CommandHandler for SystemDiffCommand {
+    async fn execute(&self, ctx: &CommandContext) -> Result<()> {
+        let vars = read_persisted(self.scope.as_deref(), &self.root)?;
+        let live = paths::get_system_env()?;
+        let show = |key: &str, value: &str| display_value(&self.classifier, key, value, ctx.reveal);
+
+        let mut stale = 0;
+        for var in &vars {
+            match live.get(&var.key) {
+                Some(current) if *current == var.value => {
+                    if ctx.verbose {
+                        println!("= {} [{}]", var.key, var.scope);
+                    }
+                }
+                Some(current) => {
+                    stale += 1;
+                    println!(
+                        "~ {} [{}]: 当前 '{}'，已持久化 '{}'（{}）",
+                        var.key,
+                        var.scope,
+                        show(&var.key, current),
+                        show(&var.key, &var.value),
+                        var.location
+                    );
+                }
+                None => {
+                    stale += 1;
+                    println!(
+                        "+ {} [{}]: 已持久化 '{}'，当前进程不可见（{}）",
+                        var.key,
+                        var.scope,
+                        show(&var.key, &var.value),
+                        var.location
+                    );
+                }
+            }
+        }
+
+        if stale == 0 {
+            println!("✓ {} 个持久化变量均已生效", vars.len());
+        } else {
+            println!("{} 个变量尚未生效，新开终端或重新登录后再检查", stale);
+        }
+        Ok(())
+    }
+}
+
+/// 读取已持久化的变量，可按作用域过滤
+fn read_persisted(scope: Option<&str>, root: &std::path::Path) -> Result<Vec<PersistedVar>> {
+    let scope = scope.map(SystemScope::parse).transpose()?;
+
+    #[cfg(windows)]
+    let vars = {
+        let _ = root;
+        system_env::read_registry()?
+    };
+    #[cfg(not(windows))]
+    let vars = system_env::read_persisted(root)?;
+
+    Ok(vars
+        .into_iter()
+        .filter(|var| scope.is_none_or(|scope| var.scope == scope))
+        .collect())
+}
+
+/// 按敏感变量规则显示值
+fn display_value(classifier: &SecretClassifier, key: &str, value: &str, reveal: bool) -> String {
+    if !reveal && classifier.matches_key(key) {
+        crate::domain::secrets::mask_value(value)
+    } else {
+        value.to_string()
+    }
+}
 
+/// 校验变量名可用于系统环境变量
+fn validate_name(key: &str) -> Result<()> {
+    if crate::infrastructure::shell::is_valid_name(key) {
         Ok(())
+    } else {
+        Err(DomainError::InvalidArgument(format!(
+            "无效的变量名: {}（仅允许字母、数字和下划线，且不能以数字开头）",
+            key
+        )))
     }
 }
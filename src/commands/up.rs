@@ -0,0 +1,350 @@
+//! up 命令处理器
+//!
+//! 按 Procfile 或配置中的 `[processes]` 同时启动多个进程。每个进程使用与 `run`
+//! 相同的环境解析结果，再叠加该进程的 `env`；输出按行加上进程名前缀。
+//! 任一进程退出后停止其余进程，退出状态取第一个以非零状态退出的进程（停止时
+//! 被信号结束的进程不算失败），都成功时为 0；收到终止类信号时转发给所有进程，
+//! 并以 `128 + 信号值` 退出。
+
+use super::run::{ChildVar, Origin, RunOptions, build_command, child_env};
+use super::{CommandContext, CommandHandler};
+use crate::application::services::EnvService;
+use crate::domain::error::{DomainError, Result};
+use crate::domain::repositories::StorageConfig;
+use crate::infrastructure::process;
+use crate::infrastructure::procfile::{self, PROCFILE_NAME};
+use crate::infrastructure::settings::ProcessConfig;
+use async_trait::async_trait;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::sync::{broadcast, mpsc};
+use tokio::time::Instant;
+
+/// 前缀颜色（ANSI 前景色），按进程顺序循环使用
+const COLORS: &[u8] = &[36, 33, 32, 35, 34, 31];
+
+/// envcli 自身消息的前缀名称
+const SELF_NAME: &str = "envcli";
+
+/// 要启动的进程
+#[derive(Debug, Clone)]
+struct Process {
+    name: String,
+    command: String,
+    env: Vec<(String, String)>,
+}
+
+/// 发给各进程监督任务的停止指令
+#[derive(Debug, Clone, Copy)]
+enum Stop {
+    Signal(i32),
+    Kill,
+}
+
+/// up 命令
+pub struct UpCommand {
+    env_service: Arc<EnvService>,
+    storage: StorageConfig,
+    processes: Vec<ProcessConfig>,
+    names: Vec<String>,
+    procfile: Option<PathBuf>,
+    stop_timeout: Duration,
+    options: RunOptions,
+}
+
+impl UpCommand {
+    pub fn new(
+        env_service: Arc<EnvService>,
+        storage: StorageConfig,
+        processes: Vec<ProcessConfig>,
+        names: Vec<String>,
+        procfile: Option<PathBuf>,
+        stop_timeout: Duration,
+        options: RunOptions,
+    ) -> Self {
+        Self {
+            env_service,
+            storage,
+            processes,
+            names,
+            procfile,
+            stop_timeout,
+            options,
+        }
+    }
+
+    /// 项目根目录，进程在此目录中启动
+    fn root(&self) -> PathBuf {
+        self.storage
+            .project_dir
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or(Path::new("."))
+            .to_path_buf()
+    }
+
+    /// 合并 Procfile 与 `[processes]`，同名进程以配置为准
+    fn load(&self) -> Result<Vec<Process>> {
+        let path = self
+            .procfile
+            .clone()
+            .unwrap_or_else(|| self.root().join(PROCFILE_NAME));
+        let mut processes: Vec<Process> = if path.exists() {
+            let content = std::fs::read_to_string(&path)
+                .map_err(|e| DomainError::Io(format!("{}: {}", path.display(), e)))?;
+            procfile::parse(&content)?
+                .into_iter()
+                .map(|(name, command)| Process {
+                    name,
+                    command,
+                    env: Vec::new(),
+                })
+                .collect()
+        } else if self.procfile.is_some() {
+            return Err(DomainError::FileNotFound(path));
+        } else {
+            Vec::new()
+        };
+
+        for config in &self.processes {
+            match processes.iter_mut().find(|p| p.name == config.name) {
+                Some(process) => {
+                    if let Some(command) = &config.command {
+                        process.command = command.clone();
+                    }
+                    process.env = config.env.clone();
+                }
+                None => {
+                    let command = config.command.clone().ok_or_else(|| {
+                        DomainError::Config(format!(
+                            "[processes] 中的 {} 没有 command，Procfile 中也没有同名进程",
+                            config.name
+                        ))
+                    })?;
+                    processes.push(Process {
+                        name: config.name.clone(),
+                        command,
+                        env: config.env.clone(),
+                    });
+                }
+            }
+        }
+
+        if processes.is_empty() {
+            return Err(DomainError::InvalidArgument(format!(
+                "未找到要启动的进程：在项目根目录创建 {}，或在 .envcli/config.toml 中定义 [processes]",
+                PROCFILE_NAME
+            )));
+        }
+        for name in &self.names {
+            if !processes.iter().any(|p| &p.name == name) {
+                let available: Vec<&str> = processes.iter().map(|p| p.name.as_str()).collect();
+                return Err(DomainError::InvalidArgument(format!(
+                    "未知进程: {}（可选 {}）",
+                    name,
+                    available.join("/")
+                )));
+            }
+        }
+        if !self.names.is_empty() {
+            processes.retain(|p| self.names.contains(&p.name));
+        }
+        Ok(processes)
+    }
+}
+
+/// 通过 shell 执行进程命令
+fn shell_command(command: &str) -> (&'static str, Vec<String>) {
+    if cfg!(windows) {
+        ("cmd", vec!["/C".to_string(), command.to_string()])
+    } else {
+        ("sh", vec!["-c".to_string(), command.to_string()])
+    }
+}
+
+/// 输出行前缀，如 `web    | `
+fn prefix(name: &str, width: usize, color: Option<u8>) -> String {
+    match color {
+        Some(color) => format!("\x1b[{}m{:<width$} |\x1b[0m ", color, name, width = width),
+        None => format!("{:<width$} | ", name, width = width),
+    }
+}
+
+/// 逐行转发子进程输出，非 UTF-8 内容按有损方式转换
+async fn forward(stream: Option<impl AsyncRead + Unpin>, prefix: String, stderr: bool) {
+    let Some(stream) = stream else {
+        return;
+    };
+    let mut reader = BufReader::new(stream);
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        match reader.read_until(b'\n', &mut line).await {
+            Ok(0) | Err(_) => return,
+            Ok(_) => {
+                let text = String::from_utf8_lossy(&line);
+                let text = text.trim_end_matches(['\n', '\r']);
+                if stderr {
+                    eprintln!("{}{}", prefix, text);
+                } else {
+                    println!("{}{}", prefix, text);
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl CommandHandler for UpCommand {
+    async fn execute(&self, _ctx: &CommandContext) -> Result<()> {
+        let processes = self.load()?;
+        let base = child_env(&self.env_service, &self.options).await?;
+        let classifier = self.env_service.classifier();
+        let root = self.root();
+
+        let width = processes
+            .iter()
+            .map(|p| p.name.len())
+            .chain([SELF_NAME.len()])
+            .max()
+            .unwrap_or(0);
+        let colored = std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+        let own_prefix = prefix(SELF_NAME, width, None);
+        let log = |message: String| println!("{}{}", own_prefix, message);
+
+        let mut signals = process::Signals::new()?;
+        let (exit_tx, mut exit_rx) = mpsc::unbounded_channel();
+        let (stop_tx, _) = broadcast::channel::<Stop>(16);
+        let mut outputs = Vec::new();
+
+        for (index, process) in processes.iter().enumerate() {
+            let mut env = base.clone();
+            for (key, value) in &process.env {
+                env.insert(
+                    key.clone(),
+                    ChildVar {
+                        value: value.clone(),
+                        origin: Origin::Process,
+                        secret: classifier.matches_key(key),
+                    },
+                );
+            }
+
+            let (program, args) = shell_command(&process.command);
+            let mut cmd: tokio::process::Command =
+                build_command(program, &args, &env, &self.options).into();
+            cmd.current_dir(&root)
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .kill_on_drop(true);
+            // sh -c 派生的进程与 shell 同组，停止时向整个进程组发送信号
+            process::isolate(&mut cmd);
+            let mut child = process::spawn(&mut cmd)?;
+            let mut guard = process::GroupGuard::new(&child);
+
+            let color = colored.then(|| COLORS[index % COLORS.len()]);
+            let line_prefix = prefix(&process.name, width, color);
+            outputs.push(tokio::spawn(forward(
+                child.stdout.take(),
+                line_prefix.clone(),
+                false,
+            )));
+            outputs.push(tokio::spawn(forward(
+                child.stderr.take(),
+                line_prefix,
+                true,
+            )));
+            log(format!(
+                "{} 已启动（pid {}）: {}",
+                process.name,
+                child.id().unwrap_or_default(),
+                process.command
+            ));
+
+            let exit_tx = exit_tx.clone();
+            let mut stop_rx = stop_tx.subscribe();
+            tokio::spawn(async move {
+                loop {
+                    tokio::select! {
+                        status = child.wait() => {
+                            guard.disarm();
+                            let _ = exit_tx.send((index, status));
+                            return;
+                        }
+                        Ok(stop) = stop_rx.recv() => match stop {
+                            Stop::Signal(signal) => process::kill_group(&mut child, signal),
+                            Stop::Kill => process::kill_group(&mut child, process::SIGKILL),
+                        },
+                    }
+                }
+            });
+        }
+
+        let mut running = processes.len();
+        // 是否已开始停止所有进程、第一个失败的进程的退出码，以及停止所有进程的信号
+        let mut stopping = false;
+        let mut failure: Option<i32> = None;
+        let mut interrupted: Option<i32> = None;
+        let mut deadline: Option<Instant> = None;
+
+        while running > 0 {
+            tokio::select! {
+                Some((index, status)) = exit_rx.recv() => {
+                    running -= 1;
+                    let (code, signaled) = match status {
+                        Ok(status) => (process::exit_code(status), status.code().is_none()),
+                        Err(_) => (1, false),
+                    };
+                    log(format!("{} 已退出（状态 {}）", processes[index].name, code));
+                    // 停止过程中被信号结束是预期的，不算失败
+                    if code != 0 && failure.is_none() && !(stopping && signaled) {
+                        failure = Some(code);
+                    }
+                    if !stopping {
+                        stopping = true;
+                        if running > 0 {
+                            log("停止其余进程".to_string());
+                            let _ = stop_tx.send(Stop::Signal(process::SIGTERM));
+                            deadline = Some(Instant::now() + self.stop_timeout);
+                        }
+                    }
+                }
+                signal = signals.recv() => {
+                    if !stopping {
+                        stopping = true;
+                        interrupted = Some(signal);
+                        log(format!("收到 {}，停止所有进程", process::signal_name(signal)));
+                        let _ = stop_tx.send(Stop::Signal(signal));
+                        deadline = Some(Instant::now() + self.stop_timeout);
+                    } else {
+                        // 停止过程中再次收到信号时立即强制结束
+                        let _ = stop_tx.send(Stop::Kill);
+                    }
+                }
+                _ = tokio::time::sleep_until(deadline.unwrap_or_else(Instant::now)), if deadline.is_some() => {
+                    log("等待超时，强制结束剩余进程".to_string());
+                    let _ = stop_tx.send(Stop::Kill);
+                    deadline = None;
+                }
+            }
+        }
+
+        // 后台进程可能仍持有输出管道，只等待片刻
+        let _ = tokio::time::timeout(Duration::from_secs(1), async {
+            for output in outputs {
+                let _ = output.await;
+            }
+        })
+        .await;
+
+        match interrupted.map(|s| 128 + s).or(failure) {
+            Some(code) => Err(DomainError::ExitStatus(code)),
+            None => Ok(()),
+        }
+    }
+}
@@ -31,7 +31,7 @@ pub enum DomainError {
     #[error("无效的环境层级: {0}")]
     #[diagnostic(
         code(envcli::invalid_source),
-        help("有效层级: system, user, project, local")
+        help("有效层级: system, user, project, profile[:<name>], local")
     )]
     InvalidSource(String),
 
@@ -67,9 +67,42 @@ pub enum DomainError {
     #[diagnostic(code(envcli::config))]
     Config(String),
 
+    #[error("变量插值错误: {0}")]
+    #[diagnostic(
+        code(envcli::interpolation),
+        help("使用 --raw 查看存储的原始值，或用 \\$ 表示字面量 $")
+    )]
+    Interpolation(String),
+
+    #[error("加密错误: {0}")]
+    #[diagnostic(
+        code(envcli::crypto),
+        help(
+            "密钥保存在用户配置目录的 keys/ 下（`envcli status` 显示该目录），确认密钥文件存在且未被修改"
+        )
+    )]
+    Crypto(String),
+
     #[error("系统环境变量写入失败: {0}")]
     #[diagnostic(code(envcli::system_env_write))]
     SystemEnvWriteFailed(String),
+
+    /// 命令已输出结果，只需以指定状态退出（如 diff 存在差异时），不打印错误信息
+    #[error("退出状态 {0}")]
+    #[diagnostic(code(envcli::exit_status))]
+    ExitStatus(i32),
+}
+
+impl DomainError {
+    /// 进程退出码：命令未找到为 127（与 shell 一致），`ExitStatus` 为其携带的状态，其余为 1
+    #[must_use]
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            DomainError::CommandNotFound(_) => 127,
+            DomainError::ExitStatus(code) => *code,
+            _ => 1,
+        }
+    }
 }
 
 impl From<std::io::Error> for DomainError {
@@ -0,0 +1,56 @@
+//! 导入导出格式
+//!
+//! 每种格式实现 [`Format`]，由基础设施层的注册表统一查找；
+//! `import` 用它解析文件，`list`/`export` 用它渲染输出。
+
+use crate::domain::error::{DomainError, Result};
+use crate::domain::secrets::MaskedVar;
+
+/// 导入导出格式
+pub trait Format: Send + Sync {
+    /// 格式名称（`--format` 参数）
+    fn name(&self) -> &'static str;
+
+    /// 格式名称的别名
+    fn aliases(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// 对应的文件扩展名（不含 `.`）
+    fn extensions(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// 内容是否像该格式，用于扩展名无法判断时的自动检测
+    fn sniff(&self, _content: &str) -> bool {
+        false
+    }
+
+    /// 解析为按出现顺序排列的键值对
+    ///
+    /// 值使用插值语法：只有 dotenv 中双引号与未加引号的值保留 `${VAR}` 引用，
+    /// 其余格式的值按字面量处理，`$` 写为 `\$`（见 [`escape`](crate::domain::interpolation::escape)）。
+    fn parse(&self, content: &str) -> Result<Vec<(String, String)>>;
+
+    /// 渲染变量
+    fn render(&self, vars: &[MaskedVar]) -> Result<String>;
+
+    /// 是否输出可由 shell 求值的语句
+    fn is_shell(&self) -> bool {
+        false
+    }
+
+    /// 渲染删除变量的语句（仅 shell 格式）
+    fn render_unset(&self, _keys: &[&str]) -> Result<String> {
+        Err(DomainError::InvalidArgument(format!(
+            "--unset 需要 shell 格式 (bash/fish/powershell/nu)，不支持 {}",
+            self.name()
+        )))
+    }
+}
+
+/// 只能导出的格式解析时的错误
+#[must_use]
+pub fn export_only(format: &dyn Format) -> DomainError {
+    DomainError::InvalidArgument(format!("{} 格式仅支持导出", format.name()))
+}
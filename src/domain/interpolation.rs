@@ -0,0 +1,292 @@
+//! 变量插值
+//!
+//! 支持的语法：
+//! - `$VAR`、`${VAR}`：引用变量，未定义时为空字符串
+//! - `${VAR:-default}`：变量未定义或为空时使用默认值
+//! - `${VAR:?message}`：变量未定义或为空时报错
+//! - `\$`：字面量 `$`
+//!
+//! 引用按合并后的层级视图解析；变量引用自身（如 `PATH=${PATH}:/opt/bin`）
+//! 时解析为更低层级中的同名变量。系统层的值不做插值。
+//! 加密信封只在被解析或被引用时才解密，解密后的值按字面量处理。
+
+use crate::domain::error::{DomainError, Result};
+use crate::domain::models::EnvSource;
+use crate::domain::secrets::is_envelope;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// 变量值中的一处引用
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Reference {
+    pub name: String,
+    /// 引用解析到的层级，未定义时为 `None`
+    pub source: Option<EnvSource>,
+    /// 解析后的值，未定义时为 `None`
+    pub value: Option<String>,
+    /// 引用的变量是否为敏感变量（插值时不判断，由调用方按标记与变量名填写）
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub secret: bool,
+}
+
+/// 解密函数，参数为变量名与加密信封
+pub type Decrypt = Box<dyn Fn(&str, &str) -> Result<String> + Send + Sync>;
+
+/// 按层级解析变量引用
+pub struct Interpolator {
+    /// 按优先级从低到高排列的层级
+    layers: Vec<(EnvSource, HashMap<String, String>)>,
+    decrypt: Option<Decrypt>,
+    resolved: HashMap<(String, usize), String>,
+    stack: Vec<(String, usize)>,
+    /// 记录被解释变量的直接引用
+    trace: Option<Vec<Reference>>,
+}
+
+impl Interpolator {
+    /// 创建解析器，`layers` 按优先级从低到高排列
+    #[must_use]
+    pub fn new(layers: Vec<(EnvSource, HashMap<String, String>)>) -> Self {
+        Self {
+            layers,
+            decrypt: None,
+            resolved: HashMap::new(),
+            stack: Vec::new(),
+            trace: None,
+        }
+    }
+
+    /// 解析到加密信封时使用 `decrypt` 解密，未设置时信封按原文参与插值
+    #[must_use]
+    pub fn with_decrypt(mut self, decrypt: Decrypt) -> Self {
+        self.decrypt = Some(decrypt);
+        self
+    }
+
+    /// 解析合并视图中变量的最终值，并返回其值中直接引用的变量
+    pub fn trace(&mut self, key: &str) -> Result<(Option<String>, Vec<Reference>)> {
+        self.resolved.clear();
+        self.trace = Some(Vec::new());
+        let value = self.resolve(key);
+        let references = self.trace.take().unwrap_or_default();
+        Ok((value?, references))
+    }
+
+    /// 解析合并视图中变量的最终值
+    pub fn resolve(&mut self, key: &str) -> Result<Option<String>> {
+        self.lookup(key, self.layers.len())
+    }
+
+    /// 解析变量在指定层级中的值（只考虑该层及更低层级中的定义）
+    pub fn resolve_in(&mut self, key: &str, source: EnvSource) -> Result<Option<String>> {
+        match self.layers.iter().position(|(s, _)| *s == source) {
+            Some(index) => self.lookup(key, index + 1),
+            None => Ok(None),
+        }
+    }
+
+    /// 在层级 `[0, below)` 中查找并解析变量
+    fn lookup(&mut self, key: &str, below: usize) -> Result<Option<String>> {
+        let Some(index) = self.find(key, below) else {
+            return Ok(None);
+        };
+
+        let (source, vars) = &self.layers[index];
+        let raw = vars[key].clone();
+        if *source == EnvSource::System {
+            return Ok(Some(raw));
+        }
+
+        let id = (key.to_string(), index);
+        if let Some(value) = self.resolved.get(&id) {
+            return Ok(Some(value.clone()));
+        }
+
+        if let Some(start) = self.stack.iter().position(|entry| *entry == id) {
+            let chain: Vec<&str> = self.stack[start..]
+                .iter()
+                .map(|(k, _)| k.as_str())
+                .chain(std::iter::once(key))
+                .collect();
+            return Err(DomainError::Interpolation(format!(
+                "循环引用: {}",
+                chain.join(" -> ")
+            )));
+        }
+
+        let raw = match &self.decrypt {
+            Some(decrypt) if is_envelope(&raw) => escape(&decrypt(key, &raw)?),
+            _ => raw,
+        };
+
+        self.stack.push(id.clone());
+        let result = self.expand(&raw, key, index);
+        self.stack.pop();
+
+        let value = result?;
+        self.resolved.insert(id, value.clone());
+        Ok(Some(value))
+    }
+
+    /// 在层级 `[0, below)` 中查找定义变量的最高层级
+    fn find(&self, key: &str, below: usize) -> Option<usize> {
+        (0..below)
+            .rev()
+            .find(|&i| self.layers[i].1.contains_key(key))
+    }
+
+    /// 展开文本中的引用，`owner` 为文本所属变量
+    fn expand(&mut self, text: &str, owner: &str, owner_index: usize) -> Result<String> {
+        let chars: Vec<char> = text.chars().collect();
+        let mut out = String::with_capacity(text.len());
+        let mut i = 0;
+
+        while i < chars.len() {
+            match chars[i] {
+                '\\' if chars.get(i + 1) == Some(&'$') => {
+                    out.push('$');
+                    i += 2;
+                }
+                '$' if chars.get(i + 1) == Some(&'{') => {
+                    let end = find_closing_brace(&chars, i + 2).ok_or_else(|| {
+                        DomainError::Interpolation(format!("{}: 未闭合的 '${{'", owner))
+                    })?;
+                    let expr: String = chars[i + 2..end].iter().collect();
+                    out.push_str(&self.expand_braced(&expr, owner, owner_index)?);
+                    i = end + 1;
+                }
+                '$' if chars.get(i + 1).is_some_and(|c| is_name_start(*c)) => {
+                    let end = (i + 1..chars.len())
+                        .find(|&j| !is_name_char(chars[j]))
+                        .unwrap_or(chars.len());
+                    let name: String = chars[i + 1..end].iter().collect();
+                    out.push_str(
+                        &self
+                            .reference(&name, owner, owner_index)?
+                            .unwrap_or_default(),
+                    );
+                    i = end;
+                }
+                c => {
+                    out.push(c);
+                    i += 1;
+                }
+            }
+        }
+
+        Ok(out)
+    }
+
+    /// 展开 `${...}` 内部表达式
+    fn expand_braced(&mut self, expr: &str, owner: &str, owner_index: usize) -> Result<String> {
+        let name_end = expr.find(|c: char| !is_name_char(c)).unwrap_or(expr.len());
+        let (name, operator) = expr.split_at(name_end);
+
+        if !name.starts_with(is_name_start) {
+            return Err(DomainError::Interpolation(format!(
+                "{}: 无效的引用 '${{{}}}'",
+                owner, expr
+            )));
+        }
+
+        let value = self
+            .reference(name, owner, owner_index)?
+            .filter(|v| !v.is_empty());
+
+        if operator.is_empty() {
+            return Ok(value.unwrap_or_default());
+        }
+
+        if let Some(default) = operator.strip_prefix(":-") {
+            return match value {
+                Some(v) => Ok(v),
+                None => self.expand(default, owner, owner_index),
+            };
+        }
+
+        if let Some(message) = operator.strip_prefix(":?") {
+            return value.ok_or_else(|| {
+                let message = if message.is_empty() {
+                    "未设置或为空"
+                } else {
+                    message
+                };
+                DomainError::Interpolation(format!("{}: {}", name, message))
+            });
+        }
+
+        Err(DomainError::Interpolation(format!(
+            "{}: 不支持的插值运算符 '${{{}}}'",
+            owner, expr
+        )))
+    }
+
+    /// 解析引用，自引用指向更低层级
+    fn reference(&mut self, name: &str, owner: &str, owner_index: usize) -> Result<Option<String>> {
+        let below = if name == owner {
+            owner_index
+        } else {
+            self.layers.len()
+        };
+        let value = self.lookup(name, below)?;
+
+        // 只记录被解释变量自身的引用
+        if self.stack.len() == 1
+            && let Some(trace) = &self.trace
+        {
+            let source = self.find(name, below).map(|i| self.layers[i].0);
+            let reference = Reference {
+                name: name.to_string(),
+                source,
+                value: value.clone(),
+                secret: false,
+            };
+            if !trace.contains(&reference) {
+                self.trace.get_or_insert_default().push(reference);
+            }
+        }
+        Ok(value)
+    }
+}
+
+fn is_name_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// 查找与 `${` 匹配的 `}`，支持嵌套
+fn find_closing_brace(chars: &[char], start: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (offset, c) in chars[start..].iter().enumerate() {
+        match c {
+            '{' => depth += 1,
+            '}' if depth == 0 => return Some(start + offset),
+            '}' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// 将字面量写为插值语法：`$` 写为 `\$`
+#[must_use]
+pub fn escape(value: &str) -> String {
+    value.replace('$', "\\$")
+}
+
+/// 不做插值时的值：`\$` 还原为 `$`，引用原样保留
+#[must_use]
+pub fn unescape(template: &str) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\\' && chars.peek() == Some(&'$') {
+            continue;
+        }
+        out.push(c);
+    }
+    out
+}
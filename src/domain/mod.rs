@@ -2,13 +2,22 @@
 //!
 //! 包含：
 //! - models: 领域实体
+//! - format: 导入导出格式接口
 //! - repositories: 存储接口（输出端口）
 //! - error: 领域错误类型
+//! - interpolation: 变量插值
+//! - secrets: 敏感变量识别与掩码
 
 pub mod error;
+pub mod format;
+pub mod interpolation;
 pub mod models;
 pub mod repositories;
+pub mod secrets;
 
 pub use error::{DomainError, Result};
-pub use models::{EnvSource, EnvVar, OutputFormat};
-pub use repositories::{EnvRepository, RepositoryFactory};
+pub use format::Format;
+pub use models::{EnvSource, EnvVar};
+pub use repositories::{
+    Definition, EnvRepository, ImportChanges, RepositoryFactory, SecretCipher, SetOptions,
+};
@@ -1,5 +1,6 @@
 //! 环境变量实体模型
 
+use crate::domain::interpolation;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
@@ -12,6 +13,8 @@ pub enum EnvSource {
     User,
     /// 项目级配置 ./.envcli/project.env
     Project,
+    /// 命名 profile ./.envcli/profiles/<name>.env
+    Profile,
     /// 本地级配置 ./.envcli/local.env (gitignored)
     #[default]
     Local,
@@ -23,24 +26,46 @@ impl fmt::Display for EnvSource {
             EnvSource::System => write!(f, "system"),
             EnvSource::User => write!(f, "user"),
             EnvSource::Project => write!(f, "project"),
+            EnvSource::Profile => write!(f, "profile"),
             EnvSource::Local => write!(f, "local"),
         }
     }
 }
 
 impl EnvSource {
-    /// 从字符串解析
+    /// 所有层级，按优先级从低到高排列
+    pub const ALL: [EnvSource; 5] = [
+        EnvSource::System,
+        EnvSource::User,
+        EnvSource::Project,
+        EnvSource::Profile,
+        EnvSource::Local,
+    ];
+
+    /// 从字符串解析，`profile:<name>` 解析为 Profile 层
     #[must_use]
     pub fn parse(s: &str) -> Option<Self> {
-        match s.to_lowercase().as_str() {
+        let s = s.to_lowercase();
+        if s.starts_with("profile:") {
+            return Some(EnvSource::Profile);
+        }
+
+        match s.as_str() {
             "system" => Some(EnvSource::System),
             "user" => Some(EnvSource::User),
             "project" => Some(EnvSource::Project),
+            "profile" => Some(EnvSource::Profile),
             "local" => Some(EnvSource::Local),
             _ => None,
         }
     }
 
+    /// 提取 `profile:<name>` 中的 profile 名称
+    #[must_use]
+    pub fn profile_name(s: &str) -> Option<&str> {
+        s.strip_prefix("profile:").filter(|name| !name.is_empty())
+    }
+
     /// 从字符串转换 (已弃用，请使用 `parse`)
     #[deprecated(since = "0.2.0", note = "请使用 `parse` 方法代替")]
     #[allow(clippy::should_implement_trait)]
@@ -62,7 +87,8 @@ impl EnvSource {
             EnvSource::System => 0,
             EnvSource::User => 1,
             EnvSource::Project => 2,
-            EnvSource::Local => 3,
+            EnvSource::Profile => 3,
+            EnvSource::Local => 4,
         }
     }
 }
@@ -74,6 +100,12 @@ pub struct EnvVar {
     pub value: String,
     pub source: EnvSource,
     pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// 是否显式标记为敏感变量（`# @secret`）
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub secret: bool,
+    /// 值按字面量保存（单引号或反引号），不做插值
+    #[serde(skip)]
+    pub literal: bool,
 }
 
 impl EnvVar {
@@ -85,6 +117,8 @@ impl EnvVar {
             value,
             source,
             timestamp: chrono::Utc::now(),
+            secret: false,
+            literal: false,
         }
     }
 
@@ -93,21 +127,24 @@ impl EnvVar {
     pub fn system(key: String, value: String) -> Self {
         Self::new(key, value, EnvSource::System)
     }
-}
 
-/// 输出格式类型
-#[derive(Debug, Clone, PartialEq, Default)]
-pub enum OutputFormat {
-    #[default]
-    Env,
-    Json,
-}
+    /// 插值语法表示的值：字面量值中的 `$` 写为 `\$`
+    #[must_use]
+    pub fn template(&self) -> String {
+        if self.literal {
+            interpolation::escape(&self.value)
+        } else {
+            self.value.clone()
+        }
+    }
 
-impl From<&str> for OutputFormat {
-    fn from(s: &str) -> Self {
-        match s.to_lowercase().as_str() {
-            "json" | "j" => OutputFormat::Json,
-            _ => OutputFormat::Env,
+    /// 不做插值时的值：`\$` 还原为 `$`，引用原样保留
+    #[must_use]
+    pub fn literal_value(&self) -> String {
+        if self.literal {
+            self.value.clone()
+        } else {
+            interpolation::unescape(&self.value)
         }
     }
 }
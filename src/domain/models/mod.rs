@@ -2,4 +2,4 @@
 
 mod env_var;
 
-pub use env_var::{EnvSource, EnvVar, OutputFormat};
+pub use env_var::{EnvSource, EnvVar};
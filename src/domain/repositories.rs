@@ -3,20 +3,35 @@
 use crate::domain::error::Result;
 use crate::domain::models::{EnvSource, EnvVar};
 use async_trait::async_trait;
-use std::path::Path;
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// 值改写函数：参数为变量名与原值
+pub type ValueRewriter = dyn Fn(&str, &str) -> Result<Option<String>> + Send + Sync;
 
 /// 环境变量存储接口
 #[async_trait]
 pub trait EnvRepository: Send + Sync {
-    /// 获取单个变量（按优先级合并）
+    /// 获取单个变量（按优先级合并），返回不做插值时的值
     async fn get(&self, key: &str) -> Result<Option<String>>;
 
-    /// 从指定源获取变量
+    /// 从指定源获取变量，返回不做插值时的值
     async fn get_from_source(&self, key: &str, source: &EnvSource) -> Result<Option<String>>;
 
     /// 设置变量
     async fn set(&self, key: &str, value: &str, source: EnvSource) -> Result<()>;
 
+    /// 按选项设置变量
+    async fn set_with_options(
+        &self,
+        key: &str,
+        value: &str,
+        source: EnvSource,
+        _options: &SetOptions,
+    ) -> Result<()> {
+        self.set(key, value, source).await
+    }
+
     /// 删除变量
     async fn delete(&self, key: &str, source: &EnvSource) -> Result<bool>;
 
@@ -28,14 +43,85 @@ pub trait EnvRepository: Send + Sync {
         self.list(None).await
     }
 
-    /// 导入 .env 文件
-    async fn import(&self, file_path: &Path, target_source: EnvSource) -> Result<usize>;
-
-    /// 导出变量为 .env 格式
-    async fn export(&self, source_filter: Option<EnvSource>) -> Result<String>;
+    /// 在一次写入中应用导入改动
+    async fn import(&self, changes: &ImportChanges, target_source: EnvSource) -> Result<()>;
 
     /// 清除缓存
     async fn clear_cache(&self);
+
+    /// 就地改写层级文件中的值，`rewrite` 返回 `Some` 时替换该值，返回改写数量
+    ///
+    /// Profile 层会改写 `profiles/` 目录下的所有文件，不限于当前 profile 的继承链。
+    async fn rewrite_values(&self, source: &EnvSource, rewrite: &ValueRewriter) -> Result<usize>;
+
+    /// 变量在各层级文件中的定义，按优先级从低到高排列（最后一个生效）
+    async fn definitions(&self, key: &str) -> Result<Vec<Definition>>;
+
+    /// 各层级包含的文件（含尚不存在的），按优先级从低到高排列，不含 system 层
+    async fn source_files(&self) -> Result<Vec<PathBuf>>;
+
+    /// 层级对应的文件路径
+    fn layer_path(&self, source: &EnvSource) -> Result<PathBuf>;
+
+    /// 当前激活的 profile
+    fn active_profile(&self) -> Option<String>;
+
+    /// 层级优先级，从低到高
+    fn layer_order(&self) -> Vec<EnvSource> {
+        EnvSource::ALL.to_vec()
+    }
+
+    /// 当前 profile 及其 `extends` 继承链（由近及远），未激活 profile 时为空
+    async fn profile_chain(&self) -> Result<Vec<String>>;
+}
+
+/// 静态加密接口
+///
+/// 加密值以 `ENC[...]` 信封形式保存在层级文件中，`name` 为变量名，
+/// 作为附加认证数据绑定到密文上。
+pub trait SecretCipher: Send + Sync {
+    /// 加密明文，返回信封
+    fn encrypt(&self, name: &str, plaintext: &str) -> Result<String>;
+
+    /// 解密信封
+    fn decrypt(&self, name: &str, envelope: &str) -> Result<String>;
+
+    /// 生成新密钥并设为当前密钥，返回新密钥 ID
+    fn rotate_key(&self) -> Result<String>;
+}
+
+/// 变量写入选项
+#[derive(Debug, Clone, Default)]
+pub struct SetOptions {
+    /// 新变量写入的分节
+    pub section: Option<String>,
+    /// 标记为敏感变量
+    pub mark_secret: bool,
+    /// 加密后再写入
+    pub encrypt: bool,
+}
+
+/// 变量的一处定义
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Definition {
+    pub source: EnvSource,
+    /// 存储的原始值
+    pub value: String,
+    /// 所在文件，system 层为 `None`
+    pub path: Option<PathBuf>,
+    /// 行号（从 1 开始）
+    pub line: Option<usize>,
+    /// 以 `# @secret` 标记
+    pub secret: bool,
+}
+
+/// 导入对目标层级的改动
+#[derive(Debug, Clone, Default)]
+pub struct ImportChanges {
+    /// 新增或覆盖的变量，值使用插值语法
+    pub set: Vec<(String, String)>,
+    /// 删除的变量
+    pub remove: Vec<String>,
 }
 
 /// Repository 工厂
@@ -48,6 +134,12 @@ pub trait RepositoryFactory: Send + Sync {
 pub struct StorageConfig {
     pub user_dir: std::path::PathBuf,
     pub project_dir: std::path::PathBuf,
+    /// 当前激活的 profile
+    pub profile: Option<String>,
+    /// 级联合并祖先目录中的 `.envcli/project.env`
+    pub cascade: bool,
+    /// 层级优先级，从低到高
+    pub layer_order: Vec<EnvSource>,
     pub cache_enabled: bool,
     pub cache_ttl_seconds: u64,
 }
@@ -59,6 +151,9 @@ impl Default for StorageConfig {
                 .map(|h| h.join(".envcli"))
                 .unwrap_or_default(),
             project_dir: std::path::PathBuf::from(".envcli"),
+            profile: None,
+            cascade: false,
+            layer_order: EnvSource::ALL.to_vec(),
             cache_enabled: true,
             cache_ttl_seconds: 60,
         }
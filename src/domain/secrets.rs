@@ -0,0 +1,129 @@
+//! 敏感变量识别与掩码
+//!
+//! 变量满足以下任一条件即视为敏感：
+//! - 变量名匹配敏感模式（如 `*_TOKEN`、`*_PASSWORD`，不区分大小写）
+//! - 在层级文件中以 `# @secret` 注释显式标记
+//! - 由加密信封 `ENC[...]` 解密而来
+//!
+//! 未解密的信封本身不含明文，输出时不做掩码。
+
+use crate::domain::models::EnvVar;
+use serde::Serialize;
+
+/// 默认敏感变量名模式
+pub const DEFAULT_SECRET_PATTERNS: &[&str] = &[
+    "*TOKEN",
+    "*PASSWORD",
+    "*PASSWD",
+    "*SECRET",
+    "*_KEY",
+    "*APIKEY",
+    "*CREDENTIALS",
+];
+
+/// 加密信封前缀
+pub const ENVELOPE_PREFIX: &str = "ENC[";
+
+/// 值是否为加密信封
+#[must_use]
+pub fn is_envelope(value: &str) -> bool {
+    value.starts_with(ENVELOPE_PREFIX) && value.ends_with(']')
+}
+
+/// 掩码占位符
+const MASK: &str = "****";
+
+/// 敏感变量分类器
+#[derive(Debug, Clone)]
+pub struct SecretClassifier {
+    patterns: Vec<String>,
+}
+
+impl Default for SecretClassifier {
+    fn default() -> Self {
+        Self::new(DEFAULT_SECRET_PATTERNS.iter().map(|p| p.to_string()))
+    }
+}
+
+impl SecretClassifier {
+    /// 使用自定义模式创建分类器
+    pub fn new(patterns: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            patterns: patterns.into_iter().map(|p| p.to_uppercase()).collect(),
+        }
+    }
+
+    /// 变量名是否匹配敏感模式
+    #[must_use]
+    pub fn matches_key(&self, key: &str) -> bool {
+        let key = key.to_uppercase();
+        self.patterns.iter().any(|p| glob_match(p, &key))
+    }
+
+    /// 变量是否敏感
+    #[must_use]
+    pub fn is_secret(&self, var: &EnvVar) -> bool {
+        var.secret || self.matches_key(&var.key)
+    }
+
+    /// 按需掩码变量，`reveal` 为 true 时保留原值
+    #[must_use]
+    pub fn mask(&self, var: EnvVar, reveal: bool) -> MaskedVar {
+        if reveal || is_envelope(&var.value) || !self.is_secret(&var) {
+            return MaskedVar { var, masked: false };
+        }
+
+        let value = mask_value(&var.value);
+        MaskedVar {
+            var: EnvVar { value, ..var },
+            masked: true,
+        }
+    }
+}
+
+/// 掩码后的变量（用于展示）
+#[derive(Debug, Clone, Serialize)]
+pub struct MaskedVar {
+    #[serde(flatten)]
+    pub var: EnvVar,
+    /// 值是否已被掩码
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub masked: bool,
+}
+
+/// 掩码值：保留首尾各两个字符，较短的值完全隐藏
+#[must_use]
+pub fn mask_value(value: &str) -> String {
+    let chars: Vec<char> = value.chars().collect();
+    if chars.len() < 10 {
+        return MASK.to_string();
+    }
+
+    let head: String = chars[..2].iter().collect();
+    let tail: String = chars[chars.len() - 2..].iter().collect();
+    format!("{}{}{}", head, MASK, tail)
+}
+
+/// 简单通配符匹配，`*` 匹配任意长度字符
+#[must_use]
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == text;
+    }
+
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if text.len() < first.len() + last.len() || !text.starts_with(first) || !text.ends_with(last) {
+        return false;
+    }
+
+    let mut rest = &text[first.len()..text.len() - last.len()];
+
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(pos) => rest = &rest[pos + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
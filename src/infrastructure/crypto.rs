@@ -0,0 +1,204 @@
+//! 基于本地密钥文件的静态加密
+//!
+//! 信封格式: `ENC[v1,<key-id>,<nonce>,<ciphertext>]`，nonce 与密文使用 base64 编码。
+//! 算法为 ChaCha20-Poly1305，变量名作为附加认证数据，密文无法被挪用到其他变量。
+//!
+//! 密钥目录结构：
+//! - `<key-id>.key`: base64 编码的 256 位密钥
+//! - `current`: 当前用于加密的密钥 ID
+//!
+//! 轮换后旧密钥仍保留，用于解密其他项目中尚未轮换的信封。
+
+use crate::domain::error::{DomainError, Result};
+use crate::domain::repositories::SecretCipher;
+use crate::domain::secrets::{ENVELOPE_PREFIX, is_envelope};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use std::path::{Path, PathBuf};
+
+/// 信封版本
+const ENVELOPE_VERSION: &str = "v1";
+
+/// 当前密钥指针文件名
+const CURRENT_FILE: &str = "current";
+
+/// 密钥 ID 的字节数，ID 为其十六进制表示
+const KEY_ID_BYTES: usize = 4;
+
+/// 本地密钥文件加密器
+pub struct LocalKeyCipher {
+    dir: PathBuf,
+}
+
+impl LocalKeyCipher {
+    /// 使用指定密钥目录创建加密器
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// 密钥目录
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn key_path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.key", id))
+    }
+
+    /// 读取当前密钥 ID
+    fn current_key_id(&self) -> Result<Option<String>> {
+        let path = self.dir.join(CURRENT_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let id = std::fs::read_to_string(&path).map_err(|e| DomainError::Io(e.to_string()))?;
+        Ok(Some(id.trim().to_string()))
+    }
+
+    /// 读取密钥，ID 来自信封等不可信输入，必须是固定长度的十六进制
+    fn load_key(&self, id: &str) -> Result<ChaCha20Poly1305> {
+        if id.len() != KEY_ID_BYTES * 2 || !id.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(DomainError::Crypto(format!("无效的密钥 ID: {}", id)));
+        }
+        let path = self.key_path(id);
+        let encoded = std::fs::read_to_string(&path)
+            .map_err(|_| DomainError::Crypto(format!("找不到密钥 {}: {}", id, path.display())))?;
+        let bytes = BASE64
+            .decode(encoded.trim())
+            .map_err(|_| DomainError::Crypto(format!("密钥文件已损坏: {}", path.display())))?;
+        if bytes.len() != 32 {
+            return Err(DomainError::Crypto(format!(
+                "密钥文件已损坏: {}",
+                path.display()
+            )));
+        }
+        Ok(ChaCha20Poly1305::new(Key::from_slice(&bytes)))
+    }
+
+    /// 获取当前密钥，不存在时自动生成
+    fn current_key(&self) -> Result<(String, ChaCha20Poly1305)> {
+        let id = match self.current_key_id()? {
+            Some(id) => id,
+            None => self.generate_key()?,
+        };
+        let key = self.load_key(&id)?;
+        Ok((id, key))
+    }
+
+    /// 生成新密钥并设为当前密钥
+    fn generate_key(&self) -> Result<String> {
+        std::fs::create_dir_all(&self.dir).map_err(|e| DomainError::Io(e.to_string()))?;
+
+        let mut id_bytes = [0u8; KEY_ID_BYTES];
+        OsRng.fill_bytes(&mut id_bytes);
+        let id: String = id_bytes.iter().map(|b| format!("{:02x}", b)).collect();
+
+        let key = ChaCha20Poly1305::generate_key(&mut OsRng);
+        write_private(&self.key_path(&id), &BASE64.encode(key))?;
+        write_private(&self.dir.join(CURRENT_FILE), &id)?;
+
+        Ok(id)
+    }
+}
+
+impl SecretCipher for LocalKeyCipher {
+    fn encrypt(&self, name: &str, plaintext: &str) -> Result<String> {
+        let (id, cipher) = self.current_key()?;
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: plaintext.as_bytes(),
+                    aad: name.as_bytes(),
+                },
+            )
+            .map_err(|_| DomainError::Crypto("加密失败".to_string()))?;
+
+        Ok(format!(
+            "{}{},{},{},{}]",
+            ENVELOPE_PREFIX,
+            ENVELOPE_VERSION,
+            id,
+            BASE64.encode(nonce),
+            BASE64.encode(ciphertext)
+        ))
+    }
+
+    fn decrypt(&self, name: &str, envelope: &str) -> Result<String> {
+        let invalid = || DomainError::Crypto(format!("{}: 无效的加密信封", name));
+
+        if !is_envelope(envelope) {
+            return Err(invalid());
+        }
+        let body = &envelope[ENVELOPE_PREFIX.len()..envelope.len() - 1];
+        let parts: Vec<&str> = body.split(',').collect();
+        let [version, id, nonce, ciphertext] = parts.as_slice() else {
+            return Err(invalid());
+        };
+        if *version != ENVELOPE_VERSION {
+            return Err(DomainError::Crypto(format!(
+                "{}: 不支持的信封版本 {}",
+                name, version
+            )));
+        }
+
+        let nonce = BASE64.decode(nonce).map_err(|_| invalid())?;
+        let ciphertext = BASE64.decode(ciphertext).map_err(|_| invalid())?;
+        if nonce.len() != 12 {
+            return Err(invalid());
+        }
+
+        let cipher = self.load_key(id)?;
+        let plaintext = cipher
+            .decrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &ciphertext,
+                    aad: name.as_bytes(),
+                },
+            )
+            .map_err(|_| {
+                DomainError::Crypto(format!(
+                    "{}: 解密失败，密文或密钥不匹配（密钥目录 {}）",
+                    name,
+                    self.dir.display()
+                ))
+            })?;
+
+        String::from_utf8(plaintext).map_err(|_| invalid())
+    }
+
+    fn rotate_key(&self) -> Result<String> {
+        self.generate_key()
+    }
+}
+
+/// 写入仅当前用户可读写的文件
+fn write_private(path: &Path, content: &str) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use std::io::Write;
+        use std::os::unix::fs::OpenOptionsExt;
+
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(path)
+            .map_err(|e| DomainError::Io(e.to_string()))?;
+        file.write_all(content.as_bytes())
+            .map_err(|e| DomainError::Io(e.to_string()))?;
+    }
+
+    #[cfg(not(unix))]
+    {
+        std::fs::write(path, content).map_err(|e| DomainError::Io(e.to_string()))?;
+    }
+
+    Ok(())
+}
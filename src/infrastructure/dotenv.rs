@@ -0,0 +1,591 @@
+//! dotenv 格式解析与序列化
+//!
+//! 支持的语法：
+//! - `export` 前缀
+//! - 单引号（原样）、双引号（支持转义）、反引号（原样）取值；原样取值不做插值，
+//!   双引号与未加引号的值中 `\$` 表示字面量 `$`
+//! - 引号内跨行取值（如 PEM 证书）
+//! - 行内注释：未加引号的值中，空白后的 `#` 开始注释
+//!
+//! [`DotenvDocument`] 保留注释、空行与原始排版，修改时只改动受影响的行。
+
+use crate::domain::error::{DomainError, Result};
+use crate::domain::interpolation;
+use std::path::Path;
+
+/// 值的引号形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quote {
+    None,
+    Single,
+    Double,
+    Backtick,
+}
+
+impl Quote {
+    fn of(value_raw: &str) -> Self {
+        match value_raw.chars().next() {
+            Some('\'') => Quote::Single,
+            Some('"') => Quote::Double,
+            Some('`') => Quote::Backtick,
+            _ => Quote::None,
+        }
+    }
+
+    /// 单引号与反引号中的值原样保存，不做插值
+    #[must_use]
+    pub fn is_literal(self) -> bool {
+        matches!(self, Quote::Single | Quote::Backtick)
+    }
+}
+
+/// 解析得到的键值条目
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DotenvEntry {
+    pub key: String,
+    pub value: String,
+    /// 条目起始行号（从 1 开始）
+    pub line: usize,
+    /// 行内注释中是否包含 `@secret` 标记
+    pub secret: bool,
+    /// 值的引号形式
+    pub quote: Quote,
+}
+
+impl DotenvEntry {
+    /// 不做插值时的值：双引号与未加引号的值中 `\$` 还原为 `$`
+    #[must_use]
+    pub fn literal_value(&self) -> String {
+        if self.quote.is_literal() {
+            self.value.clone()
+        } else {
+            interpolation::unescape(&self.value)
+        }
+    }
+
+    /// 插值语法表示的值：原样取值中的 `$` 写为 `\$`
+    #[must_use]
+    pub fn template(&self) -> String {
+        if self.quote.is_literal() {
+            interpolation::escape(&self.value)
+        } else {
+            self.value.clone()
+        }
+    }
+}
+
+/// 解析 dotenv 内容
+pub fn parse(content: &str) -> Result<Vec<DotenvEntry>> {
+    Ok(DotenvDocument::parse(content)?.entries())
+}
+
+/// 解析 dotenv 内容，跳过无法解析的行并返回对应的错误
+pub fn parse_lenient(content: &str) -> (Vec<DotenvEntry>, Vec<DomainError>) {
+    let (document, errors) = DotenvDocument::parse_lenient(content);
+    (document.entries(), errors)
+}
+
+/// 在解析错误中加上文件路径，如 `.envcli/local.env: 第 3 行: 无效的变量名`
+#[must_use]
+pub fn in_file(path: &Path, e: DomainError) -> DomainError {
+    match e {
+        DomainError::Parse(msg) => DomainError::Parse(format!("{}: {}", path.display(), msg)),
+        e => e,
+    }
+}
+
+/// 将值格式化为可被 [`parse`] 还原的形式
+///
+/// 仅包含安全字符的值原样输出，其余值使用双引号并转义；`$` 写为 `\$`，
+/// 读回时按字面量处理而不是插值。
+#[must_use]
+pub fn format_value(value: &str) -> String {
+    format_template(&interpolation::escape(value))
+}
+
+/// 将插值语法表示的值格式化为可被 [`parse`] 还原的形式，`${VAR}` 读回时仍是引用
+#[must_use]
+pub fn format_template(template: &str) -> String {
+    if template.chars().all(is_bare_char) {
+        return template.to_string();
+    }
+
+    let mut out = String::with_capacity(template.len() + 2);
+    out.push('"');
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'$') => {
+                chars.next();
+                out.push_str("\\$");
+            }
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            _ => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// 格式化单行 `KEY=VALUE`
+#[must_use]
+pub fn format_entry(key: &str, value: &str) -> String {
+    format!("{}={}", key, format_value(value))
+}
+
+/// 无需加引号的字符
+fn is_bare_char(c: char) -> bool {
+    c.is_ascii_alphanumeric()
+        || matches!(
+            c,
+            '_' | '-' | '.' | '/' | ':' | '@' | ',' | '+' | '=' | '%' | '^' | '~'
+        )
+        || (!c.is_ascii() && !c.is_whitespace())
+}
+
+/// 保留原始排版的 dotenv 文档
+///
+/// 文档由若干条目组成，渲染时按顺序拼接每个条目的原始文本，
+/// 未修改的部分与原文件逐字节一致。
+#[derive(Debug, Clone, Default)]
+pub struct DotenvDocument {
+    items: Vec<Item>,
+}
+
+#[derive(Debug, Clone)]
+enum Item {
+    /// 空行或注释行（含换行符）
+    Trivia(String),
+    /// 变量定义
+    Entry(EntryItem),
+}
+
+#[derive(Debug, Clone)]
+struct EntryItem {
+    key: String,
+    value: String,
+    /// 值之前的原文（缩进、`export` 前缀、`KEY=`）
+    prefix: String,
+    /// 值的原文（含引号）
+    value_raw: String,
+    /// 值之后的原文（行内注释、换行符）
+    suffix: String,
+}
+
+impl Item {
+    fn text(&self) -> String {
+        match self {
+            Item::Trivia(text) => text.clone(),
+            Item::Entry(entry) => format!("{}{}{}", entry.prefix, entry.value_raw, entry.suffix),
+        }
+    }
+
+    fn section(&self) -> Option<&str> {
+        match self {
+            Item::Trivia(text) => section_name(text),
+            Item::Entry(_) => None,
+        }
+    }
+}
+
+/// 敏感变量标记
+const SECRET_MARKER: &str = "@secret";
+
+impl EntryItem {
+    /// 行内注释中是否包含 `@secret`
+    fn is_marked_secret(&self) -> bool {
+        self.suffix
+            .trim_start()
+            .strip_prefix('#')
+            .is_some_and(|comment| comment.contains(SECRET_MARKER))
+    }
+}
+
+/// 识别 `# [name]` 形式的分节注释
+fn section_name(line: &str) -> Option<&str> {
+    let rest = line.trim().strip_prefix('#')?.trim();
+    let name = rest.strip_prefix('[')?.strip_suffix(']')?.trim();
+    (!name.is_empty()).then_some(name)
+}
+
+impl DotenvDocument {
+    /// 解析文档
+    pub fn parse(content: &str) -> Result<Self> {
+        Ok(Parser::new(content).parse_document(false)?.0)
+    }
+
+    /// 解析文档，无法解析的条目按原文保留为普通文本，并返回对应的错误
+    pub fn parse_lenient(content: &str) -> (Self, Vec<DomainError>) {
+        match Parser::new(content).parse_document(true) {
+            Ok(parsed) => parsed,
+            Err(e) => (Self::default(), vec![e]),
+        }
+    }
+
+    /// 按出现顺序返回所有变量
+    #[must_use]
+    pub fn entries(&self) -> Vec<DotenvEntry> {
+        let mut line = 1;
+        let mut entries = Vec::new();
+
+        for item in &self.items {
+            if let Item::Entry(entry) = item {
+                entries.push(DotenvEntry {
+                    key: entry.key.clone(),
+                    value: entry.value.clone(),
+                    line,
+                    secret: entry.is_marked_secret(),
+                    quote: Quote::of(&entry.value_raw),
+                });
+            }
+            line += item.text().matches('\n').count();
+        }
+
+        entries
+    }
+
+    /// 获取变量值（重复定义时以最后一个为准）
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.items.iter().rev().find_map(|item| match item {
+            Item::Entry(entry) if entry.key == key => Some(entry.value.as_str()),
+            _ => None,
+        })
+    }
+
+    /// 设置变量，新变量追加到文件末尾
+    pub fn set(&mut self, key: &str, value: &str) {
+        self.set_in_section(key, value, None);
+    }
+
+    /// 以插值语法设置变量，值中的 `${VAR}` 保留为引用
+    pub fn set_template(&mut self, key: &str, template: &str) {
+        self.insert(key, template, None);
+    }
+
+    /// 设置变量
+    ///
+    /// 已存在的变量原地修改，保留其前缀与行内注释；
+    /// 新变量追加到指定分节（`# [name]`）末尾，分节不存在时在文件末尾创建。
+    pub fn set_in_section(&mut self, key: &str, value: &str, section: Option<&str>) {
+        self.insert(key, &interpolation::escape(value), section);
+    }
+
+    fn insert(&mut self, key: &str, template: &str, section: Option<&str>) {
+        let mut found = false;
+        for item in &mut self.items {
+            if let Item::Entry(entry) = item
+                && entry.key == key
+            {
+                entry.value = template.to_string();
+                entry.value_raw = format_template(template);
+                if entry.suffix.starts_with('#') {
+                    entry.suffix.insert(0, ' ');
+                }
+                found = true;
+            }
+        }
+        if found {
+            return;
+        }
+
+        let new_item = Item::Entry(EntryItem {
+            key: key.to_string(),
+            value: template.to_string(),
+            prefix: format!("{}=", key),
+            value_raw: format_template(template),
+            suffix: "\n".to_string(),
+        });
+
+        let Some(section) = section else {
+            self.ensure_trailing_newline();
+            self.items.push(new_item);
+            return;
+        };
+
+        match self
+            .items
+            .iter()
+            .position(|item| item.section() == Some(section))
+        {
+            Some(header) => {
+                // 插入到该分节最后一个变量之后
+                let end = self.items[header + 1..]
+                    .iter()
+                    .position(|item| item.section().is_some())
+                    .map_or(self.items.len(), |offset| header + 1 + offset);
+                let insert_at = self.items[header + 1..end]
+                    .iter()
+                    .rposition(|item| matches!(item, Item::Entry(_)))
+                    .map_or(header + 1, |offset| header + 2 + offset);
+                if insert_at == self.items.len() {
+                    self.ensure_trailing_newline();
+                }
+                self.items.insert(insert_at, new_item);
+            }
+            None => {
+                self.ensure_trailing_newline();
+                if !self.items.is_empty() {
+                    self.items.push(Item::Trivia("\n".to_string()));
+                }
+                self.items.push(Item::Trivia(format!("# [{}]\n", section)));
+                self.items.push(new_item);
+            }
+        }
+    }
+
+    /// 在变量的行内注释中添加 `@secret` 标记
+    pub fn mark_secret(&mut self, key: &str) {
+        for item in &mut self.items {
+            if let Item::Entry(entry) = item
+                && entry.key == key
+                && !entry.is_marked_secret()
+            {
+                let newline = if entry.suffix.ends_with('\n') {
+                    "\n"
+                } else {
+                    ""
+                };
+                let comment = entry.suffix.trim_end_matches(['\r', '\n']);
+                entry.suffix = if comment.trim().is_empty() {
+                    format!(" # {}{}", SECRET_MARKER, newline)
+                } else {
+                    format!("{} {}{}", comment, SECRET_MARKER, newline)
+                };
+            }
+        }
+    }
+
+    /// 删除变量的所有定义，返回是否存在
+    pub fn remove(&mut self, key: &str) -> bool {
+        let before = self.items.len();
+        self.items
+            .retain(|item| !matches!(item, Item::Entry(entry) if entry.key == key));
+        self.items.len() != before
+    }
+
+    /// 文档是否不含任何内容（空白除外）
+    #[must_use]
+    pub fn is_blank(&self) -> bool {
+        self.items
+            .iter()
+            .all(|item| matches!(item, Item::Trivia(text) if text.trim().is_empty()))
+    }
+
+    /// 渲染为文本
+    #[must_use]
+    pub fn render(&self) -> String {
+        self.items.iter().map(Item::text).collect()
+    }
+
+    fn ensure_trailing_newline(&mut self) {
+        match self.items.last_mut() {
+            Some(Item::Trivia(text)) if !text.ends_with('\n') => text.push('\n'),
+            Some(Item::Entry(entry)) if !entry.suffix.ends_with('\n') => entry.suffix.push('\n'),
+            _ => {}
+        }
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl Parser {
+    fn new(content: &str) -> Self {
+        Self {
+            chars: content.chars().collect(),
+            pos: 0,
+            line: 1,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn text(&self, start: usize, end: usize) -> String {
+        self.chars[start..end].iter().collect()
+    }
+
+    fn error(line: usize, msg: &str) -> DomainError {
+        DomainError::Parse(format!("第 {} 行: {}", line, msg))
+    }
+
+    fn skip_inline_ws(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.bump();
+        }
+    }
+
+    fn skip_line(&mut self) {
+        while let Some(c) = self.bump() {
+            if c == '\n' {
+                break;
+            }
+        }
+    }
+
+    /// 解析整个文档，`lenient` 为 true 时出错的条目从其起始行之后继续解析
+    fn parse_document(mut self, lenient: bool) -> Result<(DotenvDocument, Vec<DomainError>)> {
+        let mut items = Vec::new();
+        let mut errors = Vec::new();
+
+        while self.peek().is_some() {
+            let (start, line) = (self.pos, self.line);
+            self.skip_inline_ws();
+
+            match self.peek() {
+                None | Some('\n' | '\r' | '#') => {
+                    self.skip_line();
+                    items.push(Item::Trivia(self.text(start, self.pos)));
+                }
+                Some(_) => match self.parse_entry(start) {
+                    Ok(entry) => items.push(Item::Entry(entry)),
+                    Err(e) if lenient => {
+                        (self.pos, self.line) = (start, line);
+                        self.skip_line();
+                        items.push(Item::Trivia(self.text(start, self.pos)));
+                        errors.push(e);
+                    }
+                    Err(e) => return Err(e),
+                },
+            }
+        }
+
+        Ok((DotenvDocument { items }, errors))
+    }
+
+    fn parse_entry(&mut self, start: usize) -> Result<EntryItem> {
+        let line = self.line;
+        let mut key = self.read_key();
+
+        if key == "export" && matches!(self.peek(), Some(' ' | '\t')) {
+            self.skip_inline_ws();
+            key = self.read_key();
+        }
+
+        if !key.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+            return Err(Self::error(line, "无效的变量名"));
+        }
+
+        self.skip_inline_ws();
+        if self.peek() != Some('=') {
+            return Err(Self::error(line, &format!("变量 {} 缺少 '='", key)));
+        }
+        self.bump();
+        self.skip_inline_ws();
+
+        let value_start = self.pos;
+        let (value, value_end) = match self.peek() {
+            Some(quote @ ('\'' | '"' | '`')) => {
+                self.bump();
+                let value = self.read_quoted(quote, line)?;
+                let value_end = self.pos;
+                self.finish_line()?;
+                (value, value_end)
+            }
+            _ => {
+                let value = self.read_unquoted();
+                let value_end = value_start + value.chars().count();
+                (value, value_end)
+            }
+        };
+
+        Ok(EntryItem {
+            key,
+            value,
+            prefix: self.text(start, value_start),
+            value_raw: self.text(value_start, value_end),
+            suffix: self.text(value_end, self.pos),
+        })
+    }
+
+    fn read_key(&mut self) -> String {
+        let mut key = String::new();
+        while let Some(c) = self.peek() {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                key.push(c);
+                self.bump();
+            } else {
+                break;
+            }
+        }
+        key
+    }
+
+    /// 读取未加引号的值，直到行尾或行内注释
+    fn read_unquoted(&mut self) -> String {
+        let mut value = String::new();
+        let mut prev_ws = true;
+
+        while let Some(c) = self.peek() {
+            if c == '\n' || (c == '#' && prev_ws) {
+                break;
+            }
+            prev_ws = c == ' ' || c == '\t';
+            value.push(c);
+            self.bump();
+        }
+        self.skip_line();
+
+        value.trim_end_matches([' ', '\t', '\r']).to_string()
+    }
+
+    /// 读取引号内的值（可跨行）
+    fn read_quoted(&mut self, quote: char, line: usize) -> Result<String> {
+        let mut value = String::new();
+
+        loop {
+            let c = self.bump().ok_or_else(|| Self::error(line, "引号未闭合"))?;
+
+            match c {
+                c if c == quote => return Ok(value),
+                '\\' if quote == '"' => {
+                    let next = self.bump().ok_or_else(|| Self::error(line, "引号未闭合"))?;
+                    match next {
+                        'n' => value.push('\n'),
+                        'r' => value.push('\r'),
+                        't' => value.push('\t'),
+                        '"' => value.push('"'),
+                        '\\' => value.push('\\'),
+                        other => {
+                            value.push('\\');
+                            value.push(other);
+                        }
+                    }
+                }
+                '\r' if self.peek() == Some('\n') => {}
+                _ => value.push(c),
+            }
+        }
+    }
+
+    /// 引号闭合后只允许空白和注释
+    fn finish_line(&mut self) -> Result<()> {
+        self.skip_inline_ws();
+        match self.peek() {
+            None => Ok(()),
+            Some('\n' | '\r' | '#') => {
+                self.skip_line();
+                Ok(())
+            }
+            Some(_) => Err(Self::error(self.line, "引号后存在多余字符")),
+        }
+    }
+}
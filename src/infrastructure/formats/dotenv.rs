@@ -0,0 +1,40 @@
+//! dotenv 格式
+
+use crate::domain::error::Result;
+use crate::domain::format::Format;
+use crate::domain::secrets::MaskedVar;
+use crate::infrastructure::dotenv;
+
+/// dotenv（`KEY=VALUE`）
+pub struct Dotenv;
+
+impl Format for Dotenv {
+    fn name(&self) -> &'static str {
+        "env"
+    }
+
+    fn aliases(&self) -> &'static [&'static str] {
+        &["dotenv"]
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["env"]
+    }
+
+    fn parse(&self, content: &str) -> Result<Vec<(String, String)>> {
+        Ok(dotenv::parse(content)?
+            .into_iter()
+            .map(|entry| {
+                let value = entry.template();
+                (entry.key, value)
+            })
+            .collect())
+    }
+
+    fn render(&self, vars: &[MaskedVar]) -> Result<String> {
+        Ok(vars
+            .iter()
+            .map(|v| dotenv::format_entry(&v.var.key, &v.var.value) + "\n")
+            .collect())
+    }
+}
@@ -0,0 +1,114 @@
+//! JSON 格式
+//!
+//! 解析时同时接受两种形状：
+//! - `EnvVar` 数组：`[{"key": "A", "value": "1", ...}]`（`export --format json` 的输出）
+//! - 扁平对象：`{"A": "1"}`，数字与布尔值转为字符串，`null` 视为空字符串
+
+use crate::domain::error::{DomainError, Result};
+use crate::domain::format::Format;
+use crate::domain::interpolation::escape;
+use crate::domain::secrets::MaskedVar;
+use serde_json::Value;
+
+/// `EnvVar` 数组
+pub struct JsonArray;
+
+/// 扁平对象
+pub struct JsonFlat;
+
+impl Format for JsonArray {
+    fn name(&self) -> &'static str {
+        "json"
+    }
+
+    fn aliases(&self) -> &'static [&'static str] {
+        &["j"]
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["json"]
+    }
+
+    fn sniff(&self, content: &str) -> bool {
+        matches!(
+            serde_json::from_str::<Value>(content),
+            Ok(Value::Array(_) | Value::Object(_))
+        )
+    }
+
+    fn parse(&self, content: &str) -> Result<Vec<(String, String)>> {
+        parse_json(content)
+    }
+
+    fn render(&self, vars: &[MaskedVar]) -> Result<String> {
+        Ok(serde_json::to_string_pretty(vars)? + "\n")
+    }
+}
+
+impl Format for JsonFlat {
+    fn name(&self) -> &'static str {
+        "json-flat"
+    }
+
+    fn aliases(&self) -> &'static [&'static str] {
+        &["json-object"]
+    }
+
+    fn parse(&self, content: &str) -> Result<Vec<(String, String)>> {
+        parse_json(content)
+    }
+
+    fn render(&self, vars: &[MaskedVar]) -> Result<String> {
+        let object: serde_json::Map<String, Value> = vars
+            .iter()
+            .map(|v| (v.var.key.clone(), Value::String(v.var.value.clone())))
+            .collect();
+        Ok(serde_json::to_string_pretty(&object)? + "\n")
+    }
+}
+
+fn parse_json(content: &str) -> Result<Vec<(String, String)>> {
+    let value: Value =
+        serde_json::from_str(content).map_err(|e| DomainError::Parse(format!("JSON: {}", e)))?;
+
+    match value {
+        Value::Array(items) => items
+            .into_iter()
+            .enumerate()
+            .map(|(index, item)| {
+                let key = item.get("key").and_then(Value::as_str);
+                let value = item.get("value").map(scalar);
+                match (key, value) {
+                    (Some(key), Some(Ok(value))) => Ok((key.to_string(), escape(&value))),
+                    _ => Err(DomainError::Parse(format!(
+                        "JSON: 第 {} 个元素应包含字符串 key 与标量 value",
+                        index + 1
+                    ))),
+                }
+            })
+            .collect(),
+        Value::Object(map) => map
+            .into_iter()
+            .map(|(key, value)| {
+                let value = scalar(&value)
+                    .map_err(|kind| DomainError::Parse(format!("JSON: {} 的值是{}", key, kind)))?;
+                Ok((key, escape(&value)))
+            })
+            .collect(),
+        _ => Err(DomainError::Parse(
+            "JSON: 顶层应为对象或 EnvVar 数组".to_string(),
+        )),
+    }
+}
+
+/// 标量转为字符串，嵌套值返回其类型名
+fn scalar(value: &Value) -> std::result::Result<String, &'static str> {
+    match value {
+        Value::String(s) => Ok(s.clone()),
+        Value::Number(n) => Ok(n.to_string()),
+        Value::Bool(b) => Ok(b.to_string()),
+        Value::Null => Ok(String::new()),
+        Value::Array(_) => Err("数组"),
+        Value::Object(_) => Err("对象"),
+    }
+}
@@ -0,0 +1,96 @@
+//! 导入导出格式注册表
+//!
+//! 支持的格式：
+//! - `env`: dotenv
+//! - `json`: [`EnvVar`](crate::domain::models::EnvVar) 数组；`json-flat`: 扁平对象
+//! - `yaml`、`toml`: 扁平的键值映射
+//! - `properties`: Java `.properties`
+//! - `bash`、`fish`、`powershell`、`nu`: 可由 shell 求值的语句（仅导出）
+
+mod dotenv;
+mod json;
+mod properties;
+mod shell;
+mod toml;
+mod yaml;
+
+use crate::domain::error::{DomainError, Result};
+use crate::domain::format::Format;
+use std::path::Path;
+
+/// 已注册的格式，自动检测时按此顺序尝试
+static FORMATS: &[&dyn Format] = &[
+    &dotenv::Dotenv,
+    &json::JsonArray,
+    &json::JsonFlat,
+    &yaml::Yaml,
+    &toml::Toml,
+    &properties::Properties,
+    &shell::ShellFormat::BASH,
+    &shell::ShellFormat::FISH,
+    &shell::ShellFormat::POWERSHELL,
+    &shell::ShellFormat::NU,
+];
+
+/// 全部格式
+#[must_use]
+pub fn all() -> &'static [&'static dyn Format] {
+    FORMATS
+}
+
+/// 按名称或别名查找格式
+#[must_use]
+pub fn find(name: &str) -> Option<&'static dyn Format> {
+    let name = name.to_lowercase();
+    FORMATS
+        .iter()
+        .copied()
+        .find(|f| f.name() == name || f.aliases().contains(&name.as_str()))
+}
+
+/// 按名称查找，未知格式时列出可选值
+pub fn lookup(name: &str) -> Result<&'static dyn Format> {
+    find(name).ok_or_else(|| {
+        DomainError::InvalidArgument(format!(
+            "无效的格式: {}（可选 {}）",
+            name,
+            names().join("/")
+        ))
+    })
+}
+
+/// 全部格式名称
+#[must_use]
+pub fn names() -> Vec<&'static str> {
+    FORMATS.iter().map(|f| f.name()).collect()
+}
+
+/// 根据文件扩展名与内容检测导入格式，无法判断时按 dotenv 处理
+#[must_use]
+pub fn detect(path: Option<&Path>, content: &str) -> &'static dyn Format {
+    let extension = path
+        .and_then(Path::extension)
+        .map(|e| e.to_string_lossy().to_lowercase());
+    if let Some(extension) = extension
+        && let Some(format) = FORMATS
+            .iter()
+            .copied()
+            .find(|f| f.extensions().contains(&extension.as_str()))
+    {
+        return format;
+    }
+
+    FORMATS
+        .iter()
+        .copied()
+        .find(|f| f.sniff(content))
+        .unwrap_or(&dotenv::Dotenv)
+}
+
+/// 第一个非空、非注释行
+fn first_line<'a>(content: &'a str, comment: &[char]) -> Option<&'a str> {
+    content
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with(comment))
+}
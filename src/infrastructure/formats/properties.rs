@@ -0,0 +1,152 @@
+//! Java `.properties` 格式
+//!
+//! 支持 `=`、`:` 或空白分隔键值，`#`/`!` 注释，行尾 `\` 续行，
+//! 以及 `\t`、`\n`、`\uXXXX` 等转义。渲染时非 ASCII 字符写作 `\uXXXX`，
+//! 与按 ISO-8859-1 读取的 `Properties.load` 兼容。
+
+use crate::domain::error::{DomainError, Result};
+use crate::domain::format::Format;
+use crate::domain::interpolation;
+use crate::domain::secrets::MaskedVar;
+
+/// Java properties
+pub struct Properties;
+
+impl Format for Properties {
+    fn name(&self) -> &'static str {
+        "properties"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["properties"]
+    }
+
+    fn parse(&self, content: &str) -> Result<Vec<(String, String)>> {
+        let mut vars = Vec::new();
+        let mut lines = content.lines().enumerate();
+
+        while let Some((index, line)) = lines.next() {
+            let line = line.trim_start();
+            if line.is_empty() || line.starts_with(['#', '!']) {
+                continue;
+            }
+
+            // 合并续行：奇数个结尾反斜杠表示续行
+            let mut logical = line.to_string();
+            while ends_with_continuation(&logical) {
+                logical.pop();
+                match lines.next() {
+                    Some((_, next)) => logical.push_str(next.trim_start()),
+                    None => break,
+                }
+            }
+
+            let (key, value) = split_entry(&logical);
+            let error =
+                |e: String| DomainError::Parse(format!("properties 第 {} 行: {}", index + 1, e));
+            vars.push((
+                unescape(key).map_err(error)?,
+                interpolation::escape(&unescape(value).map_err(error)?),
+            ));
+        }
+        Ok(vars)
+    }
+
+    fn render(&self, vars: &[MaskedVar]) -> Result<String> {
+        Ok(vars
+            .iter()
+            .map(|v| {
+                format!(
+                    "{}={}\n",
+                    escape(&v.var.key, true),
+                    escape(&v.var.value, false)
+                )
+            })
+            .collect())
+    }
+}
+
+fn ends_with_continuation(line: &str) -> bool {
+    line.chars().rev().take_while(|&c| c == '\\').count() % 2 == 1
+}
+
+/// 拆分键与值：键在第一个未转义的 `=`、`:` 或空白处结束
+fn split_entry(line: &str) -> (&str, &str) {
+    let mut escaped = false;
+    for (index, c) in line.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match c {
+            '\\' => escaped = true,
+            '=' | ':' | ' ' | '\t' | '\x0c' => {
+                let key = &line[..index];
+                let rest = line[index..].trim_start_matches([' ', '\t', '\x0c']);
+                // 空白之后还可以再跟一个 `=` 或 `:`
+                let rest = rest.strip_prefix(['=', ':']).unwrap_or(rest);
+                return (key, rest.trim_start_matches([' ', '\t', '\x0c']));
+            }
+            _ => {}
+        }
+    }
+    (line, "")
+}
+
+fn unescape(s: &str) -> std::result::Result<String, String> {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => out.push('\t'),
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some('f') => out.push('\x0c'),
+            Some('u') => {
+                let hex: String = chars.by_ref().take(4).collect();
+                let c = u32::from_str_radix(&hex, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| format!("无效的转义 \\u{}", hex))?;
+                out.push(c);
+            }
+            Some(c) => out.push(c),
+            None => {}
+        }
+    }
+    Ok(out)
+}
+
+fn escape(s: &str, is_key: bool) -> String {
+    let mut out = String::with_capacity(s.len());
+    for (index, c) in s.chars().enumerate() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\x0c' => out.push_str("\\f"),
+            '=' | ':' if is_key => {
+                out.push('\\');
+                out.push(c);
+            }
+            '#' | '!' if index == 0 => {
+                out.push('\\');
+                out.push(c);
+            }
+            ' ' if is_key || index == 0 => out.push_str("\\ "),
+            c if !c.is_ascii() || c.is_control() => {
+                let mut buf = [0u16; 2];
+                for unit in c.encode_utf16(&mut buf) {
+                    out.push_str(&format!("\\u{:04X}", unit));
+                }
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
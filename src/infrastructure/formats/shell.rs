@@ -0,0 +1,125 @@
+//! 可由 shell 求值的导出语句
+//!
+//! `export --format bash|fish|powershell|nu` 输出的每一行都可以直接 `eval`，
+//! 值按目标 shell 的规则加引号，空格、引号与 `$` 均按字面量生效。
+
+use crate::domain::error::Result;
+use crate::domain::format::{Format, export_only};
+use crate::domain::secrets::MaskedVar;
+use crate::infrastructure::shell::Shell;
+
+/// 导出语句的语法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Syntax {
+    /// bash/zsh/sh: `export KEY='value'`
+    Posix,
+    /// fish: `set -gx KEY 'value'`
+    Fish,
+    /// PowerShell: `$env:KEY = 'value'`
+    PowerShell,
+    /// nushell: `$env.KEY = "value"`
+    Nu,
+}
+
+/// shell 导出格式
+pub struct ShellFormat {
+    name: &'static str,
+    aliases: &'static [&'static str],
+    syntax: Syntax,
+}
+
+impl ShellFormat {
+    pub const BASH: ShellFormat = ShellFormat {
+        name: "bash",
+        aliases: &["zsh", "sh"],
+        syntax: Syntax::Posix,
+    };
+    pub const FISH: ShellFormat = ShellFormat {
+        name: "fish",
+        aliases: &[],
+        syntax: Syntax::Fish,
+    };
+    pub const POWERSHELL: ShellFormat = ShellFormat {
+        name: "powershell",
+        aliases: &["pwsh"],
+        syntax: Syntax::PowerShell,
+    };
+    pub const NU: ShellFormat = ShellFormat {
+        name: "nu",
+        aliases: &["nushell"],
+        syntax: Syntax::Nu,
+    };
+
+    /// 设置变量的语句
+    fn export(&self, key: &str, value: &str) -> String {
+        match self.syntax {
+            Syntax::Posix => Shell::Bash.export(key, value),
+            Syntax::Fish => Shell::Fish.export(key, value),
+            // 单引号字符串中只有 ' 需要转义（写作 ''）
+            Syntax::PowerShell => format!("$env:{} = '{}'", key, value.replace('\'', "''")),
+            Syntax::Nu => format!("$env.{} = {}", key, nu_quote(value)),
+        }
+    }
+
+    /// 删除变量的语句
+    fn unset(&self, key: &str) -> String {
+        match self.syntax {
+            Syntax::Posix => Shell::Bash.unset(key),
+            Syntax::Fish => Shell::Fish.unset(key),
+            Syntax::PowerShell => {
+                format!("Remove-Item Env:{} -ErrorAction SilentlyContinue", key)
+            }
+            Syntax::Nu => format!("hide-env -i {}", key),
+        }
+    }
+}
+
+impl Format for ShellFormat {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn aliases(&self) -> &'static [&'static str] {
+        self.aliases
+    }
+
+    fn parse(&self, _content: &str) -> Result<Vec<(String, String)>> {
+        Err(export_only(self))
+    }
+
+    fn render(&self, vars: &[MaskedVar]) -> Result<String> {
+        Ok(vars
+            .iter()
+            .map(|v| format!("{}\n", self.export(&v.var.key, &v.var.value)))
+            .collect())
+    }
+
+    fn is_shell(&self) -> bool {
+        true
+    }
+
+    fn render_unset(&self, keys: &[&str]) -> Result<String> {
+        Ok(keys
+            .iter()
+            .map(|k| format!("{}\n", self.unset(k)))
+            .collect())
+    }
+}
+
+/// nushell 双引号字符串
+fn nu_quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
@@ -0,0 +1,66 @@
+//! TOML 格式（扁平表）
+//!
+//! 只读取顶层的键值对，字符串之外的标量（整数、浮点、布尔、日期）按 TOML 文本取值；
+//! 表与数组会报错。
+
+use super::first_line;
+use crate::domain::error::{DomainError, Result};
+use crate::domain::format::Format;
+use crate::domain::interpolation::escape;
+use crate::domain::secrets::MaskedVar;
+use toml_edit::{DocumentMut, Item, Value};
+
+/// TOML
+pub struct Toml;
+
+impl Format for Toml {
+    fn name(&self) -> &'static str {
+        "toml"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["toml"]
+    }
+
+    fn sniff(&self, content: &str) -> bool {
+        // `KEY = value`（等号两侧有空格）在 dotenv 中少见
+        first_line(content, &['#']).is_some_and(|line| line.contains(" = "))
+            && content.parse::<DocumentMut>().is_ok()
+    }
+
+    fn parse(&self, content: &str) -> Result<Vec<(String, String)>> {
+        let document: DocumentMut = content
+            .parse()
+            .map_err(|e| DomainError::Parse(format!("TOML: {}", e)))?;
+
+        document
+            .iter()
+            .map(|(key, item)| {
+                let value = match item {
+                    Item::Value(Value::String(s)) => s.value().clone(),
+                    Item::Value(
+                        value @ (Value::Integer(_)
+                        | Value::Float(_)
+                        | Value::Boolean(_)
+                        | Value::Datetime(_)),
+                    ) => value.clone().decorated("", "").to_string(),
+                    _ => {
+                        return Err(DomainError::Parse(format!(
+                            "TOML: {} 不是标量值，仅支持扁平的键值对",
+                            key
+                        )));
+                    }
+                };
+                Ok((key.to_string(), escape(&value)))
+            })
+            .collect()
+    }
+
+    fn render(&self, vars: &[MaskedVar]) -> Result<String> {
+        let mut document = DocumentMut::new();
+        for v in vars {
+            document.insert(&v.var.key, toml_edit::value(v.var.value.as_str()));
+        }
+        Ok(document.to_string())
+    }
+}
@@ -0,0 +1,208 @@
+//! YAML 格式（扁平映射）
+//!
+//! 环境变量是扁平的字符串映射，只支持 YAML 的相应子集：
+//! - 顶层 `KEY: value` 映射，`#` 注释，文档起始标记 `---`
+//! - 普通、单引号（`''` 转义）与双引号（反斜杠转义）标量
+//! - `null`/`~` 视为空字符串，其余普通标量按原文本取值
+//!
+//! 嵌套映射、序列与块标量会报错。渲染时所有值都使用双引号。
+
+use super::first_line;
+use crate::domain::error::{DomainError, Result};
+use crate::domain::format::Format;
+use crate::domain::interpolation::escape;
+use crate::domain::secrets::MaskedVar;
+
+/// YAML
+pub struct Yaml;
+
+impl Format for Yaml {
+    fn name(&self) -> &'static str {
+        "yaml"
+    }
+
+    fn aliases(&self) -> &'static [&'static str] {
+        &["yml"]
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["yaml", "yml"]
+    }
+
+    fn sniff(&self, content: &str) -> bool {
+        first_line(content, &['#']).is_some_and(|line| {
+            line == "---" || split_key(line).is_some_and(|(key, _)| !key.contains('='))
+        })
+    }
+
+    fn parse(&self, content: &str) -> Result<Vec<(String, String)>> {
+        let mut vars = Vec::new();
+        for (index, raw) in content.lines().enumerate() {
+            let error = |message: &str| {
+                DomainError::Parse(format!("YAML 第 {} 行: {}", index + 1, message))
+            };
+
+            let line = raw.trim_end();
+            let trimmed = line.trim_start();
+            if trimmed.is_empty() || trimmed.starts_with('#') || trimmed == "---" {
+                continue;
+            }
+            if trimmed == "..." {
+                break;
+            }
+            if line.starts_with([' ', '\t']) {
+                return Err(error("仅支持扁平映射，不支持嵌套"));
+            }
+            if trimmed.starts_with("- ") || trimmed == "-" {
+                return Err(error("仅支持映射，不支持序列"));
+            }
+
+            let (key, value) = split_key(trimmed).ok_or_else(|| error("应为 KEY: value"))?;
+            let key = unquote_key(key).map_err(|m| error(&m))?;
+            let value = parse_scalar(value).map_err(|m| error(&m))?;
+            vars.push((key, escape(&value)));
+        }
+        Ok(vars)
+    }
+
+    fn render(&self, vars: &[MaskedVar]) -> Result<String> {
+        Ok(vars
+            .iter()
+            .map(|v| format!("{}: {}\n", v.var.key, double_quote(&v.var.value)))
+            .collect())
+    }
+}
+
+/// 拆分 `key: value`，冒号后须为空白或行尾
+fn split_key(line: &str) -> Option<(&str, &str)> {
+    let mut in_quote = None;
+    for (index, c) in line.char_indices() {
+        match (in_quote, c) {
+            (None, '"' | '\'') if index == 0 => in_quote = Some(c),
+            (Some(q), c) if c == q => in_quote = None,
+            (None, ':') => {
+                let rest = &line[index + 1..];
+                if rest.is_empty() || rest.starts_with([' ', '\t']) {
+                    return Some((line[..index].trim_end(), rest.trim()));
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn unquote_key(key: &str) -> std::result::Result<String, String> {
+    if key.starts_with(['"', '\'']) {
+        parse_scalar(key)
+    } else {
+        Ok(key.to_string())
+    }
+}
+
+/// 解析标量值
+fn parse_scalar(value: &str) -> std::result::Result<String, String> {
+    if let Some(rest) = value.strip_prefix('\'') {
+        let end = find_single_quote_end(rest).ok_or("单引号未闭合")?;
+        check_trailing(&rest[end + 1..])?;
+        return Ok(rest[..end].replace("''", "'"));
+    }
+
+    if let Some(rest) = value.strip_prefix('"') {
+        let mut out = String::new();
+        let mut chars = rest.char_indices();
+        while let Some((index, c)) = chars.next() {
+            match c {
+                '"' => {
+                    check_trailing(&rest[index + 1..])?;
+                    return Ok(out);
+                }
+                '\\' => {
+                    let (_, escaped) = chars.next().ok_or("双引号未闭合")?;
+                    match escaped {
+                        'n' => out.push('\n'),
+                        't' => out.push('\t'),
+                        'r' => out.push('\r'),
+                        '0' => out.push('\0'),
+                        '"' | '\\' | '/' => out.push(escaped),
+                        'u' => {
+                            let hex: String = (0..4)
+                                .filter_map(|_| chars.next().map(|(_, c)| c))
+                                .collect();
+                            let c = u32::from_str_radix(&hex, 16)
+                                .ok()
+                                .and_then(char::from_u32)
+                                .ok_or_else(|| format!("无效的转义 \\u{}", hex))?;
+                            out.push(c);
+                        }
+                        other => return Err(format!("不支持的转义 \\{}", other)),
+                    }
+                }
+                c => out.push(c),
+            }
+        }
+        return Err("双引号未闭合".to_string());
+    }
+
+    if value.starts_with(['|', '>']) {
+        return Err("不支持块标量".to_string());
+    }
+    if value.starts_with(['[', '{', '&', '*', '!']) {
+        return Err("仅支持标量值".to_string());
+    }
+
+    // 普通标量：空白后的 # 开始注释
+    let value = match value.find(" #").or_else(|| value.find("\t#")) {
+        Some(index) => value[..index].trim_end(),
+        None => value,
+    };
+    Ok(match value {
+        "" | "~" | "null" | "Null" | "NULL" => String::new(),
+        value => value.to_string(),
+    })
+}
+
+fn find_single_quote_end(s: &str) -> Option<usize> {
+    let bytes = s.as_bytes();
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'\'' {
+            if bytes.get(index + 1) == Some(&b'\'') {
+                index += 2;
+                continue;
+            }
+            return Some(index);
+        }
+        index += 1;
+    }
+    None
+}
+
+/// 引号之后只允许注释
+fn check_trailing(rest: &str) -> std::result::Result<(), String> {
+    let rest = rest.trim_start();
+    if rest.is_empty() || rest.starts_with('#') {
+        Ok(())
+    } else {
+        Err(format!("引号后存在多余字符: {}", rest))
+    }
+}
+
+/// 双引号标量，转义反斜杠、引号与控制字符
+fn double_quote(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
@@ -0,0 +1,207 @@
+//! 受管区块
+//!
+//! 在用户维护的文件（如 `~/.bashrc`）中以标记行划出一段由 envcli 维护的区域：
+//!
+//! ```text
+//! # >>> envcli >>>
+//! export API_URL='https://example.com'
+//! # <<< envcli <<<
+//! ```
+//!
+//! 区块外的内容原样保留；区块为空时整个区块被移除。
+
+use crate::domain::error::{DomainError, Result};
+use std::path::{Path, PathBuf};
+
+/// 区块起始标记
+pub const BLOCK_START: &str = "# >>> envcli >>>";
+
+/// 区块结束标记
+pub const BLOCK_END: &str = "# <<< envcli <<<";
+
+/// 备份文件后缀
+const BACKUP_SUFFIX: &str = ".envcli.bak";
+
+/// 含受管区块的文件
+#[derive(Debug, Clone, Default)]
+pub struct ManagedBlock {
+    /// 区块之前的内容
+    before: String,
+    /// 区块内的行（不含标记与换行符）
+    lines: Vec<String>,
+    /// 区块之后的内容
+    after: String,
+}
+
+impl ManagedBlock {
+    /// 解析文件内容
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut block = Self::default();
+        let mut state = 0; // 0: 区块前，1: 区块内，2: 区块后
+
+        for (index, raw) in content.split_inclusive('\n').enumerate() {
+            let line = raw.trim_end_matches(['\n', '\r']);
+            match state {
+                0 if line.trim() == BLOCK_START => state = 1,
+                0 => block.before.push_str(raw),
+                1 if line.trim() == BLOCK_END => state = 2,
+                1 if line.trim() == BLOCK_START => {
+                    return Err(DomainError::Parse(format!(
+                        "第 {} 行: 重复的区块起始标记 '{}'",
+                        index + 1,
+                        BLOCK_START
+                    )));
+                }
+                1 => block.lines.push(line.to_string()),
+                _ => block.after.push_str(raw),
+            }
+        }
+
+        if state == 1 {
+            return Err(DomainError::Parse(format!(
+                "缺少区块结束标记 '{}'",
+                BLOCK_END
+            )));
+        }
+
+        Ok(block)
+    }
+
+    /// 区块内的行
+    #[must_use]
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    /// 设置变量：替换 `key_of` 识别为同名变量的行，否则追加到区块末尾
+    pub fn set(&mut self, key: &str, line: String, key_of: impl Fn(&str) -> Option<String>) {
+        match self
+            .lines
+            .iter()
+            .position(|l| key_of(l).as_deref() == Some(key))
+        {
+            Some(index) => self.lines[index] = line,
+            None => self.lines.push(line),
+        }
+    }
+
+    /// 移除变量，返回是否存在
+    pub fn remove(&mut self, key: &str, key_of: impl Fn(&str) -> Option<String>) -> bool {
+        let len = self.lines.len();
+        self.lines.retain(|l| key_of(l).as_deref() != Some(key));
+        self.lines.len() != len
+    }
+
+    /// 渲染文件内容
+    #[must_use]
+    pub fn render(&self) -> String {
+        let mut out = self.before.clone();
+
+        if self.lines.is_empty() {
+            // 移除追加区块时插入的空行
+            if self.after.is_empty() && out.ends_with("\n\n") {
+                out.pop();
+            }
+        } else {
+            if !out.is_empty() && !out.ends_with('\n') {
+                out.push('\n');
+            }
+            if !out.is_empty() && !out.ends_with("\n\n") && self.after.is_empty() {
+                out.push('\n');
+            }
+            out.push_str(BLOCK_START);
+            out.push('\n');
+            for line in &self.lines {
+                out.push_str(line);
+                out.push('\n');
+            }
+            out.push_str(BLOCK_END);
+            out.push('\n');
+        }
+
+        out.push_str(&self.after);
+        out
+    }
+}
+
+/// 读取文件内容，文件不存在时视为空文件
+pub fn read_content(path: &Path) -> Result<String> {
+    if !path.exists() {
+        return Ok(String::new());
+    }
+    std::fs::read_to_string(path).map_err(|e| io_error(path, e))
+}
+
+/// 读取文件中的受管区块，文件不存在时视为空文件
+pub fn read(path: &Path) -> Result<ManagedBlock> {
+    ManagedBlock::parse(&read_content(path)?)
+        .map_err(|e| DomainError::Parse(format!("{}: {}", path.display(), e)))
+}
+
+/// 写回文件，已有文件先备份为 `<文件名>.envcli.bak`，返回备份路径
+pub fn write_with_backup(path: &Path, block: &ManagedBlock) -> Result<Option<PathBuf>> {
+    let backup = if path.exists() {
+        // 先确认目标可写，避免留下无用的备份
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(path)
+            .map_err(|e| io_error(path, e))?;
+
+        let mut name = path.as_os_str().to_owned();
+        name.push(BACKUP_SUFFIX);
+        let backup = PathBuf::from(name);
+        std::fs::copy(path, &backup).map_err(|e| io_error(&backup, e))?;
+        Some(backup)
+    } else {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| io_error(dir, e))?;
+        }
+        None
+    };
+
+    std::fs::write(path, block.render()).map_err(|e| io_error(path, e))?;
+    Ok(backup)
+}
+
+fn io_error(path: &Path, e: std::io::Error) -> DomainError {
+    if e.kind() == std::io::ErrorKind::PermissionDenied {
+        DomainError::PermissionDenied(format!("{}: {}", path.display(), e))
+    } else {
+        DomainError::Io(format!("{}: {}", path.display(), e))
+    }
+}
+
+/// 逐行比较两段文本，输出统一格式的差异（仅包含变化的行）
+#[must_use]
+pub fn diff(path: &Path, old: &str, new: &str) -> String {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // 最长公共子序列
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut out = format!("--- {}\n+++ {}\n", path.display(), path.display());
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            i += 1;
+            j += 1;
+        } else if j < new.len() && (i == old.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            out.push_str(&format!("+{}\n", new[j]));
+            j += 1;
+        } else {
+            out.push_str(&format!("-{}\n", old[i]));
+            i += 1;
+        }
+    }
+    out
+}
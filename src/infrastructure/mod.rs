@@ -3,10 +3,34 @@
 //! 包含：
 //! - storage: 文件系统存储实现
 //! - cache: 缓存实现
+//! - crypto: 本地密钥文件加密
+//! - dotenv: dotenv 格式解析与序列化
+//! - formats: 导入导出格式注册表
+//! - managed_block: 用户文件中的 envcli 受管区块
 //! - paths: 路径工具
+//! - process: 子进程执行、信号转发与退出码
+//! - procfile: `up` 使用的 Procfile 解析
+//! - secret_files: 以文件传递敏感变量的临时目录
+//! - settings: 配置文件 config.toml
+//! - shell: shell 识别与赋值语法
+//! - system_env: 持久化系统环境变量文件
+//! - trust: shell 钩子的受信任目录列表
+//! - watch: 轮询文件变化
 
 pub mod cache;
+pub mod crypto;
+pub mod dotenv;
+pub mod formats;
+pub mod managed_block;
 pub mod paths;
+pub mod process;
+pub mod procfile;
+pub mod secret_files;
+pub mod settings;
+pub mod shell;
 pub mod storage;
+pub mod system_env;
+pub mod trust;
+pub mod watch;
 
 pub use storage::FileEnvRepository;
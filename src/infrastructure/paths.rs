@@ -1,56 +1,231 @@
 //! 路径管理工具
+//!
+//! 层级文件路径均由注入的 [`StorageConfig`] 决定；本模块负责在启动时解析
+//! 用户配置目录（`ENVCLI_HOME` / XDG / `~/.envcli`）与项目目录（向上查找）。
 
 use crate::domain::error::{DomainError, Result};
 use crate::domain::models::EnvSource;
+use crate::domain::repositories::StorageConfig;
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::RwLock;
 
-/// 获取配置目录
-pub fn get_config_dir() -> Result<PathBuf> {
+/// 项目配置目录名
+pub const PROJECT_DIR_NAME: &str = ".envcli";
+
+/// 覆盖用户配置目录的环境变量
+pub const HOME_ENV: &str = "ENVCLI_HOME";
+
+/// 启用 XDG 配置目录的环境变量
+pub const XDG_ENV: &str = "ENVCLI_XDG";
+
+/// 解析用户配置目录
+///
+/// 优先级：
+/// 1. `ENVCLI_HOME`
+/// 2. `$XDG_CONFIG_HOME/envcli`（默认 `~/.config/envcli`）：设置了 `ENVCLI_XDG` 或该目录已存在时使用
+/// 3. `~/.envcli`
+pub fn resolve_user_dir() -> Result<PathBuf> {
+    if let Some(dir) = std::env::var_os(HOME_ENV).filter(|v| !v.is_empty()) {
+        return Ok(PathBuf::from(dir));
+    }
+
+    let xdg = xdg_config_dir()?;
+    if is_truthy(XDG_ENV) || xdg.is_dir() {
+        return Ok(xdg);
+    }
+
+    legacy_user_dir()
+}
+
+/// 旧版用户配置目录 `~/.envcli`
+pub fn legacy_user_dir() -> Result<PathBuf> {
     dirs::home_dir()
-        .map(|h| h.join(".envcli"))
+        .map(|h| h.join(PROJECT_DIR_NAME))
         .ok_or_else(|| DomainError::Config("无法确定主目录".to_string()))
 }
 
+/// XDG 配置目录 `$XDG_CONFIG_HOME/envcli`
+fn xdg_config_dir() -> Result<PathBuf> {
+    let base = match std::env::var_os("XDG_CONFIG_HOME").map(PathBuf::from) {
+        Some(dir) if dir.is_absolute() => dir,
+        _ => dirs::home_dir()
+            .map(|h| h.join(".config"))
+            .ok_or_else(|| DomainError::Config("无法确定主目录".to_string()))?,
+    };
+    Ok(base.join("envcli"))
+}
+
+fn is_truthy(name: &str) -> bool {
+    std::env::var(name)
+        .map(|v| !matches!(v.to_lowercase().as_str(), "" | "0" | "false" | "no" | "off"))
+        .unwrap_or(false)
+}
+
+/// 将旧版 `~/.envcli` 迁移到 XDG 配置目录
+///
+/// 仅当 `user_dir` 是 XDG 默认目录（未设置 `ENVCLI_HOME`）、尚不存在且旧目录存在时迁移，
+/// 返回被迁移的旧目录。显式指定的 `ENVCLI_HOME` 从不触发迁移。
+pub fn migrate_legacy_user_dir(user_dir: &Path) -> Result<Option<PathBuf>> {
+    if std::env::var_os(HOME_ENV).is_some_and(|v| !v.is_empty()) || user_dir != xdg_config_dir()? {
+        return Ok(None);
+    }
+    let legacy = legacy_user_dir()?;
+    if user_dir.exists() || !legacy.is_dir() {
+        return Ok(None);
+    }
+
+    let io = |e: std::io::Error| DomainError::Io(format!("迁移 {} 失败: {}", legacy.display(), e));
+    if let Some(parent) = user_dir.parent() {
+        std::fs::create_dir_all(parent).map_err(io)?;
+    }
+    if std::fs::rename(&legacy, user_dir).is_err() {
+        // 跨文件系统时回退为复制后删除
+        copy_dir_all(&legacy, user_dir).map_err(io)?;
+        std::fs::remove_dir_all(&legacy).map_err(io)?;
+    }
+
+    Ok(Some(legacy))
+}
+
+fn copy_dir_all(from: &Path, to: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir_all(&entry.path(), &target)?;
+        } else {
+            std::fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// 从当前目录向上查找项目配置目录（`<项目根目录>/.envcli`）
+pub fn discover_project_dir() -> Result<PathBuf> {
+    let cwd = std::env::current_dir().map_err(|e| DomainError::Io(e.to_string()))?;
+    Ok(find_project_root(&cwd, dirs::home_dir().as_deref()).join(PROJECT_DIR_NAME))
+}
+
 /// 获取特定层级的文件路径
-pub fn get_layer_path(source: &EnvSource) -> Result<PathBuf> {
+pub fn get_layer_path(config: &StorageConfig, source: &EnvSource) -> Result<PathBuf> {
     match source {
         EnvSource::System => Err(DomainError::InvalidSource(
             "System 层没有文件路径".to_string(),
         )),
-        EnvSource::User => {
-            let config_dir = get_config_dir()?;
-            Ok(config_dir.join("user.env"))
+        EnvSource::User => Ok(config.user_dir.join("user.env")),
+        EnvSource::Profile => Err(DomainError::InvalidSource(
+            "Profile 层需要指定名称".to_string(),
+        )),
+        EnvSource::Project => Ok(config.project_dir.join("project.env")),
+        EnvSource::Local => Ok(config.project_dir.join("local.env")),
+    }
+}
+
+/// 从 `start` 向上查找最近的含 `.envcli` 目录的祖先
+///
+/// 查找在含 `.git` 的目录（仓库根）处停止，且不进入主目录（`~/.envcli` 是用户层）。
+/// 未找到时使用仓库根目录，不在仓库中则使用 `start`。
+#[must_use]
+pub fn find_project_root(start: &Path, home: Option<&Path>) -> PathBuf {
+    for dir in start.ancestors() {
+        if Some(dir) == home {
+            break;
         }
-        EnvSource::Project => {
-            let cwd = std::env::current_dir().map_err(|e| DomainError::Io(e.to_string()))?;
-            Ok(cwd.join(".envcli").join("project.env"))
+        if dir.join(PROJECT_DIR_NAME).is_dir() {
+            return dir.to_path_buf();
         }
-        EnvSource::Local => {
-            let cwd = std::env::current_dir().map_err(|e| DomainError::Io(e.to_string()))?;
-            Ok(cwd.join(".envcli").join("local.env"))
+        if dir.join(".git").exists() {
+            return dir.to_path_buf();
         }
     }
+    start.to_path_buf()
 }
 
-/// 确保配置目录存在
-pub fn ensure_config_dir() -> Result<PathBuf> {
-    let dir = get_config_dir()?;
-    if !dir.exists() {
-        std::fs::create_dir_all(&dir).map_err(|e| DomainError::Io(e.to_string()))?;
+/// 项目根目录之上各级祖先的 `.envcli` 目录，按从远到近排列
+///
+/// 用于 monorepo 级联：边界规则与 [`find_project_root`] 相同。
+#[must_use]
+pub fn get_ancestor_project_dirs(config: &StorageConfig) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    let Some(root) = config.project_dir.parent() else {
+        return dirs;
+    };
+    if root.join(".git").exists() {
+        return dirs;
+    }
+
+    let home = dirs::home_dir();
+    for dir in root.ancestors().skip(1) {
+        if Some(dir) == home.as_deref() {
+            break;
+        }
+        let project_dir = dir.join(PROJECT_DIR_NAME);
+        if project_dir.is_dir() {
+            dirs.push(project_dir);
+        }
+        if dir.join(".git").exists() {
+            break;
+        }
+    }
+
+    dirs.reverse();
+    dirs
+}
+
+/// 获取 profile 文件路径
+pub fn get_profile_path(config: &StorageConfig, name: &str) -> Result<PathBuf> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(DomainError::InvalidArgument(format!(
+            "无效的 profile 名称: {}（仅允许字母、数字、- 和 _）",
+            name
+        )));
+    }
+
+    Ok(config
+        .project_dir
+        .join("profiles")
+        .join(format!("{}.env", name)))
+}
+
+/// 列出项目中所有 profile 文件（`profiles/*.env`），按文件名排序
+pub fn list_profile_paths(config: &StorageConfig) -> Result<Vec<PathBuf>> {
+    let dir = config.project_dir.join("profiles");
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut paths = Vec::new();
+    for entry in std::fs::read_dir(&dir).map_err(|e| DomainError::Io(e.to_string()))? {
+        let path = entry.map_err(|e| DomainError::Io(e.to_string()))?.path();
+        if path.is_file() && path.extension() == Some("env".as_ref()) {
+            paths.push(path);
+        }
     }
-    Ok(dir)
+    paths.sort();
+    Ok(paths)
+}
+
+/// 确保用户配置目录存在
+pub fn ensure_config_dir(config: &StorageConfig) -> Result<PathBuf> {
+    ensure_dir(&config.user_dir)
 }
 
 /// 确保项目目录存在
-pub fn ensure_project_dir() -> Result<PathBuf> {
-    let cwd = std::env::current_dir().map_err(|e| DomainError::Io(e.to_string()))?;
-    let project_dir = cwd.join(".envcli");
-    if !project_dir.exists() {
-        std::fs::create_dir_all(&project_dir).map_err(|e| DomainError::Io(e.to_string()))?;
+pub fn ensure_project_dir(config: &StorageConfig) -> Result<PathBuf> {
+    ensure_dir(&config.project_dir)
+}
+
+fn ensure_dir(dir: &Path) -> Result<PathBuf> {
+    if !dir.exists() {
+        std::fs::create_dir_all(dir).map_err(|e| DomainError::Io(e.to_string()))?;
     }
-    Ok(project_dir)
+    Ok(dir.to_path_buf())
 }
 
 // ============ 系统环境变量缓存 ============
@@ -0,0 +1,303 @@
+//! 子进程执行
+//!
+//! Unix 上 `run` 默认以 `exec` 直接替换为目标命令，不在进程树中留下 envcli；
+//! 需要监督子进程时改用 [`supervise`]：等待期间把 SIGINT/SIGTERM/SIGHUP/SIGQUIT
+//! 转发给子进程，退出码按 shell 的规则把信号终止映射为 `128 + 信号值`。
+
+use crate::domain::error::{DomainError, Result};
+use std::io;
+use std::process::ExitStatus;
+use std::time::Duration;
+use tokio::process::{Child, Command};
+
+/// 停止子进程时等待 SIGTERM 生效的默认时间
+pub const STOP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// SIGTERM 的信号值（非 Unix 平台上 [`kill`] 忽略信号值）
+pub const SIGTERM: i32 = 15;
+
+/// SIGKILL 的信号值（非 Unix 平台上 [`kill_group`] 忽略信号值）
+pub const SIGKILL: i32 = 9;
+
+/// 可按名称指定的信号
+#[cfg(unix)]
+const SIGNALS: &[(&str, i32)] = &[
+    ("HUP", libc::SIGHUP),
+    ("INT", libc::SIGINT),
+    ("QUIT", libc::SIGQUIT),
+    ("TERM", libc::SIGTERM),
+    ("USR1", libc::SIGUSR1),
+    ("USR2", libc::SIGUSR2),
+];
+
+/// 解析信号名称（`HUP`、`SIGHUP` 或信号值）
+#[cfg(unix)]
+pub fn parse_signal(name: &str) -> Result<i32> {
+    let upper = name.to_uppercase();
+    let short = upper.strip_prefix("SIG").unwrap_or(&upper);
+    SIGNALS
+        .iter()
+        .find(|(n, _)| *n == short)
+        .map(|(_, signal)| *signal)
+        .or_else(|| name.parse().ok().filter(|n| *n > 0))
+        .ok_or_else(|| {
+            let names: Vec<&str> = SIGNALS.iter().map(|(n, _)| *n).collect();
+            DomainError::InvalidArgument(format!(
+                "无效的信号: {}（可选 {}）",
+                name,
+                names.join("/")
+            ))
+        })
+}
+
+/// 解析信号名称（仅 Unix 支持）
+#[cfg(not(unix))]
+pub fn parse_signal(name: &str) -> Result<i32> {
+    Err(DomainError::InvalidArgument(format!(
+        "当前平台不支持发送信号 {}",
+        name
+    )))
+}
+
+/// 信号的显示名称，如 `SIGHUP`
+#[must_use]
+pub fn signal_name(signal: i32) -> String {
+    #[cfg(unix)]
+    if let Some((name, _)) = SIGNALS.iter().find(|(_, s)| *s == signal) {
+        return format!("SIG{}", name);
+    }
+    format!("信号 {}", signal)
+}
+
+/// 启动失败时的错误，找不到命令映射为 [`DomainError::CommandNotFound`]
+pub fn spawn_error(program: &str, err: io::Error) -> DomainError {
+    match err.kind() {
+        io::ErrorKind::NotFound => DomainError::CommandNotFound(program.to_string()),
+        _ => DomainError::CommandExecutionFailed(format!("{}: {}", program, err)),
+    }
+}
+
+/// 以目标命令替换当前进程，只在失败时返回
+#[cfg(unix)]
+pub fn exec(command: &mut std::process::Command) -> DomainError {
+    use std::os::unix::process::CommandExt;
+
+    let program = command.get_program().to_string_lossy().into_owned();
+    spawn_error(&program, command.exec())
+}
+
+/// 启动子进程
+pub fn spawn(command: &mut Command) -> Result<Child> {
+    let program = command
+        .as_std()
+        .get_program()
+        .to_string_lossy()
+        .into_owned();
+    command.spawn().map_err(|e| spawn_error(&program, e))
+}
+
+/// 启动子进程并等待其退出，期间转发终止类信号
+pub async fn supervise(command: Command) -> Result<ExitStatus> {
+    let mut signals = Signals::new()?;
+    supervise_with(command, &mut signals).await
+}
+
+/// 同 [`supervise`]，使用调用方事先注册的信号，避免注册前到达的信号被遗漏
+pub async fn supervise_with(mut command: Command, signals: &mut Signals) -> Result<ExitStatus> {
+    let mut child = spawn(&mut command)?;
+    wait(&mut child, signals).await
+}
+
+/// 等待子进程退出，期间转发终止类信号
+#[cfg(unix)]
+pub async fn wait(child: &mut Child, signals: &mut Signals) -> Result<ExitStatus> {
+    loop {
+        tokio::select! {
+            status = child.wait() => {
+                return status.map_err(|e| DomainError::CommandExecutionFailed(e.to_string()));
+            }
+            signal = signals.recv() => kill(child, signal),
+        }
+    }
+}
+
+/// 等待子进程退出（控制台的 Ctrl-C 会同时发给子进程）
+#[cfg(not(unix))]
+pub async fn wait(child: &mut Child, _signals: &mut Signals) -> Result<ExitStatus> {
+    child
+        .wait()
+        .await
+        .map_err(|e| DomainError::CommandExecutionFailed(e.to_string()))
+}
+
+/// 向子进程发送信号，子进程已退出时忽略
+#[cfg(unix)]
+pub fn kill(child: &mut Child, signal: i32) {
+    if let Some(pid) = child.id() {
+        // SAFETY: kill(2) 只读取参数，pid 来自仍被持有的子进程
+        unsafe {
+            libc::kill(pid as libc::pid_t, signal);
+        }
+    }
+}
+
+/// 结束子进程（非 Unix 平台无法发送信号）
+#[cfg(not(unix))]
+pub fn kill(child: &mut Child, _signal: i32) {
+    let _ = child.start_kill();
+}
+
+/// 让子进程在独立的进程组中运行，以便 [`kill_group`] 同时结束它派生的进程
+///
+/// 子进程不再收到终端发给 envcli 的信号，需要由调用方转发。
+pub fn isolate(command: &mut Command) {
+    #[cfg(unix)]
+    command.process_group(0);
+    #[cfg(not(unix))]
+    let _ = command;
+}
+
+/// 向子进程所在的进程组发送信号（子进程需经 [`isolate`] 启动），子进程已退出时忽略
+#[cfg(unix)]
+pub fn kill_group(child: &mut Child, signal: i32) {
+    if let Some(pid) = child.id() {
+        // SAFETY: kill(2) 只读取参数；子进程尚未被回收，其进程组 ID 不会被复用
+        unsafe {
+            libc::kill(-(pid as libc::pid_t), signal);
+        }
+    }
+}
+
+/// 结束子进程（非 Unix 平台无法发送信号）
+#[cfg(not(unix))]
+pub fn kill_group(child: &mut Child, _signal: i32) {
+    let _ = child.start_kill();
+}
+
+/// 释放时强制结束仍在运行的进程组
+///
+/// `kill_on_drop` 只结束子进程本身，经 shell 启动的进程会被遗留；
+/// 子进程退出后调用 [`disarm`](Self::disarm)，避免向已回收的进程组发送信号。
+pub struct GroupGuard {
+    pgid: Option<u32>,
+}
+
+impl GroupGuard {
+    /// 守护经 [`isolate`] 启动的子进程
+    #[must_use]
+    pub fn new(child: &Child) -> Self {
+        Self { pgid: child.id() }
+    }
+
+    /// 子进程已退出，释放时不再发送信号
+    pub fn disarm(&mut self) {
+        self.pgid = None;
+    }
+}
+
+impl Drop for GroupGuard {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if let Some(pgid) = self.pgid {
+            // SAFETY: kill(2) 只读取参数
+            unsafe {
+                libc::kill(-(pgid as libc::pid_t), SIGKILL);
+            }
+        }
+    }
+}
+
+/// 停止子进程：先发送 SIGTERM，超时后强制结束
+#[cfg(unix)]
+pub async fn stop(child: &mut Child, timeout: Duration) -> Result<ExitStatus> {
+    kill(child, libc::SIGTERM);
+    if let Ok(status) = tokio::time::timeout(timeout, child.wait()).await {
+        return status.map_err(|e| DomainError::CommandExecutionFailed(e.to_string()));
+    }
+    force_stop(child).await
+}
+
+/// 停止子进程
+#[cfg(not(unix))]
+pub async fn stop(child: &mut Child, _timeout: Duration) -> Result<ExitStatus> {
+    force_stop(child).await
+}
+
+/// 强制结束子进程（Unix 上为 SIGKILL）并等待退出
+async fn force_stop(child: &mut Child) -> Result<ExitStatus> {
+    let _ = child.kill().await;
+    child
+        .wait()
+        .await
+        .map_err(|e| DomainError::CommandExecutionFailed(e.to_string()))
+}
+
+/// 需要转发给子进程的信号
+///
+/// 注册后 envcli 自身不再因这些信号退出，由调用方决定如何处理。
+#[cfg(unix)]
+pub struct Signals {
+    interrupt: tokio::signal::unix::Signal,
+    terminate: tokio::signal::unix::Signal,
+    hangup: tokio::signal::unix::Signal,
+    quit: tokio::signal::unix::Signal,
+}
+
+#[cfg(unix)]
+impl Signals {
+    pub fn new() -> Result<Self> {
+        use tokio::signal::unix::{SignalKind, signal};
+
+        let register = |kind: SignalKind| {
+            signal(kind).map_err(|e| DomainError::Io(format!("注册信号处理失败: {}", e)))
+        };
+        Ok(Self {
+            interrupt: register(SignalKind::interrupt())?,
+            terminate: register(SignalKind::terminate())?,
+            hangup: register(SignalKind::hangup())?,
+            quit: register(SignalKind::quit())?,
+        })
+    }
+
+    /// 等待下一个信号，返回信号值
+    pub async fn recv(&mut self) -> i32 {
+        tokio::select! {
+            _ = self.interrupt.recv() => libc::SIGINT,
+            _ = self.terminate.recv() => libc::SIGTERM,
+            _ = self.hangup.recv() => libc::SIGHUP,
+            _ = self.quit.recv() => libc::SIGQUIT,
+        }
+    }
+}
+
+/// 需要转发给子进程的信号（非 Unix 平台上只有 Ctrl-C）
+#[cfg(not(unix))]
+pub struct Signals;
+
+#[cfg(not(unix))]
+impl Signals {
+    pub fn new() -> Result<Self> {
+        Ok(Self)
+    }
+
+    /// 等待 Ctrl-C，按 SIGINT 的值返回
+    pub async fn recv(&mut self) -> i32 {
+        if tokio::signal::ctrl_c().await.is_err() {
+            std::future::pending::<()>().await;
+        }
+        2
+    }
+}
+
+/// 与 shell 一致的退出码：正常退出取其状态码，被信号终止为 `128 + 信号值`
+#[must_use]
+pub fn exit_code(status: ExitStatus) -> i32 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return 128 + signal;
+        }
+    }
+    status.code().unwrap_or(1)
+}
@@ -0,0 +1,48 @@
+//! Procfile 解析
+//!
+//! 每行 `<名称>: <命令>`，名称只能包含字母、数字、`_` 与 `-`；
+//! 空行与 `#` 开头的行忽略，命令交给 shell 执行。
+
+use crate::domain::error::{DomainError, Result};
+
+/// Procfile 文件名
+pub const PROCFILE_NAME: &str = "Procfile";
+
+/// 解析为按出现顺序排列的 (名称, 命令)
+pub fn parse(content: &str) -> Result<Vec<(String, String)>> {
+    let mut entries: Vec<(String, String)> = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let error = |message: String| {
+            DomainError::Parse(format!("Procfile 第 {} 行: {}", index + 1, message))
+        };
+
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (name, command) = line
+            .split_once(':')
+            .ok_or_else(|| error("应为 <名称>: <命令>".to_string()))?;
+        let name = name.trim();
+        let command = command.trim();
+        if !is_valid_name(name) {
+            return Err(error(format!("无效的进程名 '{}'", name)));
+        }
+        if command.is_empty() {
+            return Err(error(format!("进程 {} 缺少命令", name)));
+        }
+        if entries.iter().any(|(n, _)| n == name) {
+            return Err(error(format!("进程 {} 重复定义", name)));
+        }
+        entries.push((name.to_string(), command.to_string()));
+    }
+    Ok(entries)
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
@@ -0,0 +1,97 @@
+//! 以文件传递敏感变量
+//!
+//! `run --secrets-as-files` 为每次运行创建仅当前用户可访问的临时目录，优先放在
+//! 内存文件系统上（`$XDG_RUNTIME_DIR`、`/dev/shm`），每个敏感值写入一个 `0600` 文件。
+//! [`SecretDir`] 被丢弃时删除整个目录。`shell` 的初始化文件同样写在这种目录中，
+//! 避免使用可预测的共享路径。
+
+use crate::domain::error::{DomainError, Result};
+use chacha20poly1305::aead::OsRng;
+use chacha20poly1305::aead::rand_core::RngCore;
+use std::path::{Path, PathBuf};
+
+/// 临时目录的候选位置，依次尝试
+fn candidates() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if cfg!(unix) {
+        dirs.extend(std::env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from));
+        dirs.push(PathBuf::from("/dev/shm"));
+    }
+    dirs.push(std::env::temp_dir());
+    dirs
+}
+
+/// 创建仅当前用户可访问的目录
+fn create_private_dir(path: &Path) -> std::io::Result<()> {
+    let mut builder = std::fs::DirBuilder::new();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder.create(path)
+}
+
+/// 随机命名、仅当前用户可访问的临时目录，丢弃时删除
+#[derive(Debug)]
+pub struct SecretDir {
+    path: PathBuf,
+}
+
+impl SecretDir {
+    /// 在第一个可写的候选位置创建目录
+    pub fn new() -> Result<Self> {
+        let mut id = [0u8; 8];
+        OsRng.fill_bytes(&mut id);
+        let name = format!(
+            "envcli-{}-{}",
+            std::process::id(),
+            id.iter().map(|b| format!("{:02x}", b)).collect::<String>()
+        );
+
+        let mut last_error = None;
+        for base in candidates() {
+            if !base.is_dir() {
+                continue;
+            }
+            let path = base.join(&name);
+            match create_private_dir(&path) {
+                Ok(()) => return Ok(Self { path }),
+                Err(e) => last_error = Some(format!("{}: {}", path.display(), e)),
+            }
+        }
+        Err(DomainError::Io(format!(
+            "无法创建敏感变量临时目录: {}",
+            last_error.unwrap_or_else(|| "没有可用的临时目录".to_string())
+        )))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 以 `key` 为文件名新建 `0600` 文件并写入内容，返回文件路径
+    pub fn write(&self, key: &str, value: &str) -> Result<PathBuf> {
+        let path = self.path.join(key);
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+
+        let write = || -> std::io::Result<()> {
+            use std::io::Write;
+            options.open(&path)?.write_all(value.as_bytes())
+        };
+        write().map_err(|e| DomainError::Io(format!("{}: {}", path.display(), e)))?;
+        Ok(path)
+    }
+}
+
+impl Drop for SecretDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
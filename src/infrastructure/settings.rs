@@ -0,0 +1,483 @@
+//! 配置文件 `config.toml`
+//!
+//! 用户配置位于 `<用户配置目录>/config.toml`，项目配置位于 `.envcli/config.toml`，
+//! 同一配置项以项目配置为准。支持的配置项：
+//!
+//! ```toml
+//! default_target = "local"        # set/unset/import 的默认层级
+//! default_format = "env"          # list/export 的默认格式
+//! secret_patterns = ["*TOKEN"]    # 敏感变量名模式
+//! layer_order = ["system", "user", "project", "profile", "local"]  # 优先级从低到高
+//!
+//! [cache]
+//! enabled = true
+//! ttl_seconds = 60
+//!
+//! [processes]                     # envcli up 启动的进程
+//! web = "cargo run --bin web"
+//! worker = { command = "cargo run --bin worker", env = { QUEUE = "default" } }
+//! ```
+
+use crate::domain::error::{DomainError, Result};
+use crate::domain::models::EnvSource;
+use crate::domain::secrets::DEFAULT_SECRET_PATTERNS;
+use crate::infrastructure::formats;
+use crate::infrastructure::shell::is_valid_name;
+use std::path::Path;
+use toml_edit::{Array, DocumentMut, ImDocument, Item, TableLike, Value};
+
+/// 配置文件名
+pub const CONFIG_FILE_NAME: &str = "config.toml";
+
+/// 配置项类型
+#[derive(Debug, Clone, Copy)]
+enum Kind {
+    Target,
+    Format,
+    Patterns,
+    LayerOrder,
+    Bool,
+    Seconds,
+    Processes,
+}
+
+/// 支持的配置项（点分路径）
+const SCHEMA: &[(&str, Kind)] = &[
+    ("default_target", Kind::Target),
+    ("default_format", Kind::Format),
+    ("secret_patterns", Kind::Patterns),
+    ("layer_order", Kind::LayerOrder),
+    ("cache.enabled", Kind::Bool),
+    ("cache.ttl_seconds", Kind::Seconds),
+    ("processes", Kind::Processes),
+];
+
+/// 所有配置项名称
+pub fn keys() -> impl Iterator<Item = &'static str> {
+    SCHEMA.iter().map(|(key, _)| *key)
+}
+
+fn kind_of(key: &str) -> Option<Kind> {
+    SCHEMA
+        .iter()
+        .find(|(k, _)| *k == key)
+        .map(|(_, kind)| *kind)
+}
+
+/// `[processes]` 中的一个进程
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProcessConfig {
+    pub name: String,
+    /// 要执行的 shell 命令，未指定时只为 Procfile 中的同名进程提供 `env`
+    pub command: Option<String>,
+    /// 只注入该进程的变量
+    pub env: Vec<(String, String)>,
+}
+
+/// 配置文件中的设置，未出现的项为 `None`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Settings {
+    pub default_target: Option<EnvSource>,
+    /// 格式名称（已规范化为注册表中的名称）
+    pub default_format: Option<String>,
+    pub secret_patterns: Option<Vec<String>>,
+    pub layer_order: Option<Vec<EnvSource>>,
+    pub cache_enabled: Option<bool>,
+    pub cache_ttl_seconds: Option<u64>,
+    pub processes: Option<Vec<ProcessConfig>>,
+}
+
+/// 已校验的配置值
+enum Setting {
+    Target(EnvSource),
+    Format(String),
+    Patterns(Vec<String>),
+    LayerOrder(Vec<EnvSource>),
+    Bool(bool),
+    Seconds(u64),
+    Processes(Vec<ProcessConfig>),
+}
+
+impl Settings {
+    /// 内置默认值
+    #[must_use]
+    pub fn defaults() -> Self {
+        Self {
+            default_target: Some(EnvSource::Local),
+            default_format: Some("env".to_string()),
+            secret_patterns: Some(
+                DEFAULT_SECRET_PATTERNS
+                    .iter()
+                    .map(|p| p.to_string())
+                    .collect(),
+            ),
+            layer_order: Some(EnvSource::ALL.to_vec()),
+            cache_enabled: Some(true),
+            cache_ttl_seconds: Some(60),
+            processes: Some(Vec::new()),
+        }
+    }
+
+    /// 以 `other` 中出现的项覆盖当前设置
+    #[must_use]
+    pub fn merge(self, other: Settings) -> Self {
+        Self {
+            default_target: other.default_target.or(self.default_target),
+            default_format: other.default_format.or(self.default_format),
+            secret_patterns: other.secret_patterns.or(self.secret_patterns),
+            layer_order: other.layer_order.or(self.layer_order),
+            cache_enabled: other.cache_enabled.or(self.cache_enabled),
+            cache_ttl_seconds: other.cache_ttl_seconds.or(self.cache_ttl_seconds),
+            processes: other.processes.or(self.processes),
+        }
+    }
+
+    /// 配置项的显示值（数组以逗号分隔，可直接用于 `config set`）
+    #[must_use]
+    pub fn value(&self, key: &str) -> Option<String> {
+        match key {
+            "default_target" => self.default_target.map(|s| s.to_string()),
+            "default_format" => self.default_format.clone(),
+            "secret_patterns" => self.secret_patterns.as_ref().map(|p| p.join(",")),
+            "layer_order" => self.layer_order.as_ref().map(|order| {
+                order
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(",")
+            }),
+            "cache.enabled" => self.cache_enabled.map(|v| v.to_string()),
+            "cache.ttl_seconds" => self.cache_ttl_seconds.map(|v| v.to_string()),
+            "processes" => self.processes.as_ref().map(|processes| {
+                processes
+                    .iter()
+                    .map(|p| p.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            }),
+            _ => None,
+        }
+    }
+
+    fn apply(&mut self, key: &str, setting: Setting) {
+        match (key, setting) {
+            ("default_target", Setting::Target(v)) => self.default_target = Some(v),
+            ("default_format", Setting::Format(v)) => self.default_format = Some(v),
+            ("secret_patterns", Setting::Patterns(v)) => self.secret_patterns = Some(v),
+            ("layer_order", Setting::LayerOrder(v)) => self.layer_order = Some(v),
+            ("cache.enabled", Setting::Bool(v)) => self.cache_enabled = Some(v),
+            ("cache.ttl_seconds", Setting::Seconds(v)) => self.cache_ttl_seconds = Some(v),
+            ("processes", Setting::Processes(v)) => self.processes = Some(v),
+            _ => {}
+        }
+    }
+}
+
+/// 配置文件中的问题
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issue {
+    /// 行号（从 1 开始）
+    pub line: usize,
+    pub message: String,
+}
+
+/// 解析配置内容，返回已识别的设置与发现的问题
+#[must_use]
+pub fn parse(content: &str) -> (Settings, Vec<Issue>) {
+    let mut settings = Settings::default();
+    let mut issues = Vec::new();
+
+    let document = match ImDocument::parse(content) {
+        Ok(document) => document,
+        Err(e) => {
+            let line = e.span().map_or(1, |span| line_of(content, span.start));
+            issues.push(Issue {
+                line,
+                message: format!("TOML 语法错误: {}", e.message().trim()),
+            });
+            return (settings, issues);
+        }
+    };
+
+    walk(
+        document.as_table(),
+        "",
+        1,
+        content,
+        &mut settings,
+        &mut issues,
+    );
+    issues.sort_by_key(|issue| issue.line);
+    (settings, issues)
+}
+
+/// 递归检查表中的配置项
+fn walk(
+    table: &dyn TableLike,
+    prefix: &str,
+    parent_line: usize,
+    content: &str,
+    settings: &mut Settings,
+    issues: &mut Vec<Issue>,
+) {
+    for (name, item) in table.iter() {
+        let path = if prefix.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}.{}", prefix, name)
+        };
+        let line = table
+            .key(name)
+            .and_then(|key| key.span())
+            .or_else(|| item.span())
+            .map_or(parent_line, |span| line_of(content, span.start));
+
+        if let Some(kind) = kind_of(&path) {
+            let result = match (kind, item.as_table_like(), item.as_value()) {
+                (Kind::Processes, Some(table), _) => {
+                    convert_processes(table).map(Setting::Processes)
+                }
+                (_, _, Some(value)) => convert(kind, value),
+                _ => Err(expected(kind).to_string()),
+            };
+            match result {
+                Ok(setting) => settings.apply(&path, setting),
+                Err(message) => issues.push(Issue {
+                    line,
+                    message: format!("{}: {}", path, message),
+                }),
+            }
+        } else if let Some(child) = item.as_table_like()
+            && SCHEMA
+                .iter()
+                .any(|(key, _)| key.starts_with(&format!("{}.", path)))
+        {
+            walk(child, &path, line, content, settings, issues);
+        } else {
+            issues.push(Issue {
+                line,
+                message: format!("未知配置项: {}", path),
+            });
+        }
+    }
+}
+
+/// 类型说明，用于错误信息
+fn expected(kind: Kind) -> &'static str {
+    match kind {
+        Kind::Target => "应为层级名称字符串 (local/project/user/profile)",
+        Kind::Format => "应为格式名称字符串 (env/json/yaml/…)",
+        Kind::Patterns => "应为字符串数组",
+        Kind::LayerOrder => "应为包含全部层级的字符串数组",
+        Kind::Bool => "应为布尔值",
+        Kind::Seconds => "应为非负整数",
+        Kind::Processes => {
+            "应为进程表：名称 = \"命令\" 或 { command = \"…\", env = { KEY = \"value\" } }"
+        }
+    }
+}
+
+/// 将 TOML 值转换为配置值
+fn convert(kind: Kind, value: &Value) -> std::result::Result<Setting, String> {
+    let invalid = || expected(kind).to_string();
+
+    match kind {
+        Kind::Target => {
+            let name = value.as_str().ok_or_else(invalid)?;
+            parse_target(name).map(Setting::Target)
+        }
+        Kind::Format => {
+            let name = value.as_str().ok_or_else(invalid)?;
+            parse_format(name).map(Setting::Format)
+        }
+        Kind::Patterns => string_array(value)
+            .ok_or_else(invalid)
+            .map(Setting::Patterns),
+        Kind::LayerOrder => {
+            let names = string_array(value).ok_or_else(invalid)?;
+            parse_layer_order(&names).map(Setting::LayerOrder)
+        }
+        Kind::Bool => value.as_bool().map(Setting::Bool).ok_or_else(invalid),
+        Kind::Seconds => value
+            .as_integer()
+            .and_then(|v| u64::try_from(v).ok())
+            .map(Setting::Seconds)
+            .ok_or_else(invalid),
+        Kind::Processes => Err(invalid()),
+    }
+}
+
+/// 解析 `[processes]` 表
+fn convert_processes(table: &dyn TableLike) -> std::result::Result<Vec<ProcessConfig>, String> {
+    let mut processes = Vec::new();
+    for (name, item) in table.iter() {
+        let invalid = |message: &str| format!("processes.{}: {}", name, message);
+        let mut process = ProcessConfig {
+            name: name.to_string(),
+            ..Default::default()
+        };
+
+        if let Some(command) = item.as_str() {
+            process.command = Some(command.to_string());
+        } else if let Some(fields) = item.as_table_like() {
+            for (field, value) in fields.iter() {
+                match field {
+                    "command" => {
+                        let command = value
+                            .as_str()
+                            .ok_or_else(|| invalid("command 应为字符串"))?;
+                        process.command = Some(command.to_string());
+                    }
+                    "env" => {
+                        let env = value.as_table_like().ok_or_else(|| invalid("env 应为表"))?;
+                        for (key, value) in env.iter() {
+                            if !is_valid_name(key) {
+                                return Err(invalid(&format!("无效的变量名 '{}'", key)));
+                            }
+                            let value = value
+                                .as_str()
+                                .ok_or_else(|| invalid(&format!("env.{} 应为字符串", key)))?;
+                            process.env.push((key.to_string(), value.to_string()));
+                        }
+                    }
+                    other => return Err(invalid(&format!("未知字段 {}", other))),
+                }
+            }
+        } else {
+            return Err(invalid("应为命令字符串或表"));
+        }
+        processes.push(process);
+    }
+    Ok(processes)
+}
+
+fn string_array(value: &Value) -> Option<Vec<String>> {
+    value
+        .as_array()?
+        .iter()
+        .map(|v| v.as_str().map(str::to_string))
+        .collect()
+}
+
+fn parse_target(name: &str) -> std::result::Result<EnvSource, String> {
+    match EnvSource::parse(name) {
+        Some(source) if source.is_writable() && EnvSource::profile_name(name).is_none() => {
+            Ok(source)
+        }
+        _ => Err(format!(
+            "无效的默认层级 '{}'，可选 local/project/user/profile",
+            name
+        )),
+    }
+}
+
+fn parse_format(name: &str) -> std::result::Result<String, String> {
+    formats::find(name)
+        .map(|f| f.name().to_string())
+        .ok_or_else(|| {
+            format!(
+                "无效的输出格式 '{}'，可选 {}",
+                name,
+                formats::names().join("/")
+            )
+        })
+}
+
+fn parse_layer_order(names: &[String]) -> std::result::Result<Vec<EnvSource>, String> {
+    let mut order = Vec::new();
+    for name in names {
+        let source = EnvSource::parse(name)
+            .filter(|_| EnvSource::profile_name(name).is_none())
+            .ok_or_else(|| format!("未知层级 '{}'", name))?;
+        if order.contains(&source) {
+            return Err(format!("层级 '{}' 重复", name));
+        }
+        order.push(source);
+    }
+
+    if order.len() != EnvSource::ALL.len() {
+        return Err("必须包含全部层级: system, user, project, profile, local".to_string());
+    }
+    Ok(order)
+}
+
+/// 字节偏移所在的行号
+fn line_of(content: &str, offset: usize) -> usize {
+    content[..offset.min(content.len())].matches('\n').count() + 1
+}
+
+/// 读取配置文件，不存在时返回空设置，存在问题时报错
+pub fn load(path: &Path) -> Result<Settings> {
+    if !path.exists() {
+        return Ok(Settings::default());
+    }
+
+    let content = std::fs::read_to_string(path).map_err(|e| DomainError::Io(e.to_string()))?;
+    let (settings, issues) = parse(&content);
+    match issues.first() {
+        None => Ok(settings),
+        Some(issue) => Err(DomainError::Config(format!(
+            "{}:{}: {}（运行 envcli config validate 查看全部问题）",
+            path.display(),
+            issue.line,
+            issue.message
+        ))),
+    }
+}
+
+/// 在配置内容中设置配置项，保留其余内容与注释
+///
+/// 数组类型的值以逗号分隔，例如 `*TOKEN,*SECRET`。
+pub fn set_value(content: &str, key: &str, raw: &str) -> Result<String> {
+    let kind =
+        kind_of(key).ok_or_else(|| DomainError::InvalidArgument(format!("未知配置项: {}", key)))?;
+
+    let value: Value = match kind {
+        Kind::Target | Kind::Format => raw.into(),
+        Kind::Patterns | Kind::LayerOrder => {
+            let items: Array = raw
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .collect();
+            items.into()
+        }
+        Kind::Bool => match raw.to_lowercase().as_str() {
+            "true" => true.into(),
+            "false" => false.into(),
+            _ => return Err(invalid_value(key, kind)),
+        },
+        Kind::Seconds => raw
+            .parse::<i64>()
+            .map_err(|_| invalid_value(key, kind))?
+            .into(),
+        Kind::Processes => {
+            return Err(DomainError::InvalidArgument(format!(
+                "{} 不支持 config set，请直接编辑配置文件",
+                key
+            )));
+        }
+    };
+    convert(kind, &value)
+        .map_err(|message| DomainError::InvalidArgument(format!("{}: {}", key, message)))?;
+
+    let mut document: DocumentMut = content
+        .parse()
+        .map_err(|e: toml_edit::TomlError| DomainError::Parse(e.message().to_string()))?;
+
+    match key.split_once('.') {
+        Some((table, name)) => {
+            if !document.contains_key(table) {
+                document[table] = toml_edit::table();
+            }
+            document[table][name] = Item::Value(value);
+        }
+        None => document[key] = Item::Value(value),
+    }
+
+    Ok(document.to_string())
+}
+
+fn invalid_value(key: &str, kind: Kind) -> DomainError {
+    DomainError::InvalidArgument(format!("{}: {}", key, expected(kind)))
+}
@@ -0,0 +1,171 @@
+//! Shell 类型识别与变量赋值语法
+
+use crate::domain::error::{DomainError, Result};
+use std::fmt;
+use std::path::PathBuf;
+
+/// 支持的 shell
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    /// POSIX sh（`~/.profile`）
+    Posix,
+}
+
+impl fmt::Display for Shell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Shell::Bash => write!(f, "bash"),
+            Shell::Zsh => write!(f, "zsh"),
+            Shell::Fish => write!(f, "fish"),
+            Shell::Posix => write!(f, "sh"),
+        }
+    }
+}
+
+impl Shell {
+    /// 从名称或路径解析（如 `zsh`、`/usr/bin/fish`）
+    #[must_use]
+    pub fn parse(name: &str) -> Option<Self> {
+        let name = name.rsplit(['/', '\\']).next().unwrap_or(name);
+        match name.to_lowercase().as_str() {
+            "bash" => Some(Shell::Bash),
+            "zsh" => Some(Shell::Zsh),
+            "fish" => Some(Shell::Fish),
+            "sh" | "posix" | "dash" | "ksh" | "profile" => Some(Shell::Posix),
+            _ => None,
+        }
+    }
+
+    /// 从 `$SHELL` 检测，无法识别时使用 POSIX sh
+    #[must_use]
+    pub fn detect() -> Self {
+        std::env::var("SHELL")
+            .ok()
+            .and_then(|shell| Self::parse(&shell))
+            .unwrap_or(Shell::Posix)
+    }
+
+    /// 解析 `--shell` 参数，未指定时自动检测
+    pub fn from_arg(name: Option<&str>) -> Result<Self> {
+        match name {
+            Some(name) => Self::parse(name).ok_or_else(|| {
+                DomainError::InvalidArgument(format!(
+                    "不支持的 shell: {}（可选 bash/zsh/fish/sh）",
+                    name
+                ))
+            }),
+            None => Ok(Self::detect()),
+        }
+    }
+
+    /// 持久化变量的启动文件
+    pub fn rc_path(&self) -> Result<PathBuf> {
+        let home =
+            dirs::home_dir().ok_or_else(|| DomainError::Config("无法获取主目录".to_string()))?;
+
+        Ok(match self {
+            Shell::Bash => home.join(".bashrc"),
+            Shell::Zsh => std::env::var_os("ZDOTDIR")
+                .map(PathBuf::from)
+                .unwrap_or(home)
+                .join(".zshrc"),
+            Shell::Fish => std::env::var_os("XDG_CONFIG_HOME")
+                .map(PathBuf::from)
+                .filter(|dir| dir.is_absolute())
+                .unwrap_or_else(|| home.join(".config"))
+                .join("fish/conf.d/envcli.fish"),
+            Shell::Posix => home.join(".profile"),
+        })
+    }
+
+    /// 引用值，使其在该 shell 中按字面量解析
+    #[must_use]
+    pub fn quote(&self, value: &str) -> String {
+        match self {
+            Shell::Fish => format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'")),
+            _ => format!("'{}'", value.replace('\'', "'\\''")),
+        }
+    }
+
+    /// 还原 [`Shell::quote`] 引用的值，无法识别时返回 `None`
+    #[must_use]
+    pub fn unquote(&self, quoted: &str) -> Option<String> {
+        let mut value = String::new();
+        let mut chars = quoted.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\'' => loop {
+                    match chars.next()? {
+                        '\'' => break,
+                        '\\' if *self == Shell::Fish => match chars.next()? {
+                            c @ ('\\' | '\'') => value.push(c),
+                            c => {
+                                value.push('\\');
+                                value.push(c);
+                            }
+                        },
+                        c => value.push(c),
+                    }
+                },
+                '\\' => value.push(chars.next()?),
+                c if c.is_whitespace() => return None,
+                c => value.push(c),
+            }
+        }
+        Some(value)
+    }
+
+    /// 导出变量的语句
+    #[must_use]
+    pub fn export(&self, key: &str, value: &str) -> String {
+        match self {
+            Shell::Fish => format!("set -gx {} {}", key, self.quote(value)),
+            _ => format!("export {}={}", key, self.quote(value)),
+        }
+    }
+
+    /// 删除变量的语句
+    #[must_use]
+    pub fn unset(&self, key: &str) -> String {
+        match self {
+            Shell::Fish => format!("set -e {}", key),
+            _ => format!("unset {}", key),
+        }
+    }
+
+    /// 识别 [`Shell::export`] 生成的语句中的变量名
+    #[must_use]
+    pub fn exported_key(&self, line: &str) -> Option<String> {
+        let rest = match self {
+            Shell::Fish => line.trim_start().strip_prefix("set -gx ")?,
+            _ => line.trim_start().strip_prefix("export ")?,
+        };
+        let end = rest.find(|c: char| c == '=' || c.is_whitespace())?;
+        Some(rest[..end].to_string())
+    }
+
+    /// 解析 [`Shell::export`] 生成的语句，返回变量名与值
+    #[must_use]
+    pub fn parse_export(&self, line: &str) -> Option<(String, String)> {
+        let key = self.exported_key(line)?;
+        let rest = match self {
+            Shell::Fish => line.trim_start().strip_prefix("set -gx ")?,
+            _ => line.trim_start().strip_prefix("export ")?,
+        };
+        let quoted = rest[key.len()..].trim_start_matches(['=', ' ']);
+        Some((key, self.unquote(quoted.trim_end())?))
+    }
+}
+
+/// 变量名是否可用于 shell（`[A-Za-z_][A-Za-z0-9_]*`）
+#[must_use]
+pub fn is_valid_name(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
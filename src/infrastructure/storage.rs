@@ -2,18 +2,22 @@
 
 use crate::domain::error::{DomainError, Result};
 use crate::domain::models::{EnvSource, EnvVar};
-use crate::domain::repositories::{EnvRepository, StorageConfig};
+use crate::domain::repositories::{
+    Definition, EnvRepository, ImportChanges, SetOptions, StorageConfig, ValueRewriter,
+};
 use crate::infrastructure::cache::FileCache;
+use crate::infrastructure::dotenv::{self, DotenvDocument, in_file};
 use crate::infrastructure::paths;
 use async_trait::async_trait;
-use regex::Regex;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+/// profile 文件中声明继承的保留键
+const PROFILE_EXTENDS_KEY: &str = "extends";
+
 /// 文件环境变量存储
 pub struct FileEnvRepository {
-    #[allow(dead_code)]
     config: StorageConfig,
     cache: Arc<FileCache>,
 }
@@ -30,56 +34,54 @@ impl FileEnvRepository {
     }
 
     /// 解析 .env 文件内容
-    fn parse_dotenv(content: &str, source: &EnvSource) -> Vec<EnvVar> {
-        let mut vars = Vec::new();
-        let re = Regex::new(r"^\s*([A-Za-z_][A-Za-z0-9_]*)\s*=\s*(.*?)\s*$").unwrap();
-
-        for line in content.lines() {
-            let trimmed = line.trim();
-
-            // 跳过空行和注释
-            if trimmed.is_empty() || trimmed.starts_with('#') {
-                continue;
-            }
-
-            if let Some(caps) = re.captures(trimmed) {
-                let key = caps[1].to_string();
-                let value = caps[2].to_string();
-                vars.push(EnvVar::new(key, value, *source));
-            }
+    ///
+    /// 只读取的层级中一行写错不应让所有命令失败：无法解析的行被跳过并在 stderr 警告。
+    /// 要修改的文件仍按严格模式解析（见 [`Self::load_document`]）。
+    fn parse_dotenv(path: &Path, content: &str, source: &EnvSource) -> Vec<EnvVar> {
+        let (entries, errors) = dotenv::parse_lenient(content);
+        for e in errors {
+            eprintln!("警告: {}，已跳过该行", in_file(path, e));
         }
-
-        vars
-    }
-
-    /// 序列化为 .env 格式
-    fn serialize_dotenv(vars: &[EnvVar]) -> String {
-        let mut lines = vec![
-            "# EnvCLI 配置文件".to_string(),
-            "# 格式: KEY=VALUE".to_string(),
-            String::new(),
-        ];
-
-        for var in vars {
-            lines.push(format!("{}={}", var.key, var.value));
-        }
-
-        lines.join("\n")
+        entries
+            .into_iter()
+            .map(|entry| EnvVar {
+                secret: entry.secret,
+                literal: entry.quote.is_literal(),
+                ..EnvVar::new(entry.key, entry.value, *source)
+            })
+            .collect()
     }
 
     /// 获取文件路径
     fn get_path(&self, source: &EnvSource) -> Result<PathBuf> {
-        paths::get_layer_path(source)
+        match source {
+            EnvSource::Profile => {
+                let name = self.config.profile.as_deref().ok_or_else(|| {
+                    DomainError::InvalidArgument(
+                        "未选择 profile，使用 --profile <NAME> 或 --target profile:<NAME>"
+                            .to_string(),
+                    )
+                })?;
+                paths::get_profile_path(&self.config, name)
+            }
+            _ => paths::get_layer_path(&self.config, source),
+        }
     }
 
     /// 确保目录存在
     async fn ensure_dir(&self, source: &EnvSource) -> Result<()> {
         match source {
             EnvSource::User => {
-                paths::ensure_config_dir()?;
+                paths::ensure_config_dir(&self.config)?;
             }
             EnvSource::Project | EnvSource::Local => {
-                paths::ensure_project_dir()?;
+                paths::ensure_project_dir(&self.config)?;
+            }
+            EnvSource::Profile => {
+                let dir = paths::ensure_project_dir(&self.config)?.join("profiles");
+                tokio::fs::create_dir_all(&dir)
+                    .await
+                    .map_err(|e| DomainError::Io(e.to_string()))?;
             }
             _ => {}
         }
@@ -88,49 +90,140 @@ impl FileEnvRepository {
 
     /// 读取变量列表（带缓存）
     async fn read_vars(&self, source: &EnvSource) -> Result<Vec<EnvVar>> {
-        if *source == EnvSource::System {
-            let env = paths::get_system_env()?;
-            return Ok(env.into_iter().map(|(k, v)| EnvVar::system(k, v)).collect());
+        match source {
+            EnvSource::System => {
+                let env = paths::get_system_env()?;
+                Ok(env.into_iter().map(|(k, v)| EnvVar::system(k, v)).collect())
+            }
+            EnvSource::Profile => self.read_profile_vars().await,
+            EnvSource::Project if self.config.cascade => self.read_cascaded_project_vars().await,
+            _ => self.read_file_vars(&self.get_path(source)?, source).await,
         }
+    }
 
-        let path = self.get_path(source)?;
+    /// 读取 profile 层：沿继承链由远及近合并
+    async fn read_profile_vars(&self) -> Result<Vec<EnvVar>> {
+        let mut merged: Vec<EnvVar> = Vec::new();
+
+        for name in self.profile_chain().await?.iter().rev() {
+            let path = paths::get_profile_path(&self.config, name)?;
+            for var in self.read_file_vars(&path, &EnvSource::Profile).await? {
+                if var.key == PROFILE_EXTENDS_KEY {
+                    continue;
+                }
+                match merged.iter_mut().find(|v| v.key == var.key) {
+                    Some(existing) => *existing = var,
+                    None => merged.push(var),
+                }
+            }
+        }
+
+        Ok(merged)
+    }
 
+    /// 级联读取项目层：祖先目录中的 project.env 由远及近合并，近者优先
+    async fn read_cascaded_project_vars(&self) -> Result<Vec<EnvVar>> {
+        let mut files: Vec<PathBuf> = paths::get_ancestor_project_dirs(&self.config)
+            .into_iter()
+            .map(|dir| dir.join("project.env"))
+            .collect();
+        files.push(self.get_path(&EnvSource::Project)?);
+
+        let mut merged: Vec<EnvVar> = Vec::new();
+        for path in files {
+            for var in self.read_file_vars(&path, &EnvSource::Project).await? {
+                match merged.iter_mut().find(|v| v.key == var.key) {
+                    Some(existing) => *existing = var,
+                    None => merged.push(var),
+                }
+            }
+        }
+
+        Ok(merged)
+    }
+
+    /// 层级包含的文件，按优先级从低到高排列
+    async fn layer_files(&self, source: &EnvSource) -> Result<Vec<PathBuf>> {
+        match source {
+            EnvSource::System => Ok(Vec::new()),
+            EnvSource::Profile => {
+                let mut files = Vec::new();
+                for name in self.profile_chain().await?.iter().rev() {
+                    files.push(paths::get_profile_path(&self.config, name)?);
+                }
+                Ok(files)
+            }
+            EnvSource::Project if self.config.cascade => {
+                let mut files: Vec<PathBuf> = paths::get_ancestor_project_dirs(&self.config)
+                    .into_iter()
+                    .map(|dir| dir.join("project.env"))
+                    .collect();
+                files.push(self.get_path(source)?);
+                Ok(files)
+            }
+            _ => Ok(vec![self.get_path(source)?]),
+        }
+    }
+
+    /// 读取单个层级文件（带缓存）
+    async fn read_file_vars(&self, path: &Path, source: &EnvSource) -> Result<Vec<EnvVar>> {
         if !path.exists() {
             return Ok(Vec::new());
         }
 
         // 尝试从缓存获取
-        if let Some(cached) = self.cache.get(&path)? {
+        if let Some(cached) = self.cache.get(path)? {
             return Ok(cached);
         }
 
         // 读取文件
-        let content = tokio::fs::read_to_string(&path)
+        let content = tokio::fs::read_to_string(path)
             .await
             .map_err(|e| DomainError::Io(e.to_string()))?;
-        let vars = Self::parse_dotenv(&content, source);
+        let mut vars = Self::parse_dotenv(path, &content, source);
+
+        // 时间戳取文件的修改时间
+        if let Ok(modified) = std::fs::metadata(path).and_then(|m| m.modified()) {
+            let modified = chrono::DateTime::<chrono::Utc>::from(modified);
+            for var in &mut vars {
+                var.timestamp = modified;
+            }
+        }
 
         // 更新缓存
-        self.cache.set(&path, vars.clone())?;
+        self.cache.set(path, vars.clone())?;
 
         Ok(vars)
     }
 
-    /// 写入变量列表
-    async fn write_vars(&self, source: &EnvSource, vars: &[EnvVar]) -> Result<()> {
+    /// 读取层级文件为可编辑文档
+    async fn load_document(&self, source: &EnvSource) -> Result<DotenvDocument> {
+        let path = self.get_path(source)?;
+
+        if !path.exists() {
+            return Ok(DotenvDocument::default());
+        }
+
+        let content = tokio::fs::read_to_string(&path)
+            .await
+            .map_err(|e| DomainError::Io(e.to_string()))?;
+        DotenvDocument::parse(&content).map_err(|e| in_file(&path, e))
+    }
+
+    /// 写回层级文件
+    async fn save_document(&self, source: &EnvSource, document: &DotenvDocument) -> Result<()> {
         self.ensure_dir(source).await?;
         let path = self.get_path(source)?;
 
-        if vars.is_empty() {
-            // 如果没有变量，删除文件
+        if document.is_blank() {
+            // 文件中已无任何内容，删除文件
             if path.exists() {
                 tokio::fs::remove_file(&path)
                     .await
                     .map_err(|e| DomainError::Io(e.to_string()))?;
             }
         } else {
-            let content = Self::serialize_dotenv(vars);
-            tokio::fs::write(&path, content)
+            tokio::fs::write(&path, document.render())
                 .await
                 .map_err(|e| DomainError::Io(e.to_string()))?;
         }
@@ -146,13 +239,8 @@ impl FileEnvRepository {
 impl EnvRepository for FileEnvRepository {
     async fn get(&self, key: &str) -> Result<Option<String>> {
         // 按优先级查找
-        for source in [
-            EnvSource::Local,
-            EnvSource::Project,
-            EnvSource::User,
-            EnvSource::System,
-        ] {
-            if let Some(value) = self.get_from_source(key, &source).await? {
+        for source in self.config.layer_order.iter().rev() {
+            if let Some(value) = self.get_from_source(key, source).await? {
                 return Ok(Some(value));
             }
         }
@@ -161,10 +249,24 @@ impl EnvRepository for FileEnvRepository {
 
     async fn get_from_source(&self, key: &str, source: &EnvSource) -> Result<Option<String>> {
         let vars = self.read_vars(source).await?;
-        Ok(vars.into_iter().find(|v| v.key == key).map(|v| v.value))
+        Ok(vars
+            .into_iter()
+            .find(|v| v.key == key)
+            .map(|v| v.literal_value()))
     }
 
     async fn set(&self, key: &str, value: &str, source: EnvSource) -> Result<()> {
+        self.set_with_options(key, value, source, &SetOptions::default())
+            .await
+    }
+
+    async fn set_with_options(
+        &self,
+        key: &str,
+        value: &str,
+        source: EnvSource,
+        options: &SetOptions,
+    ) -> Result<()> {
         if !source.is_writable() {
             return Err(DomainError::PermissionDenied(format!(
                 "{} 层不可写",
@@ -172,17 +274,13 @@ impl EnvRepository for FileEnvRepository {
             )));
         }
 
-        let mut vars = self.read_vars(&source).await?;
-
-        // 更新或添加
-        if let Some(existing) = vars.iter_mut().find(|v| v.key == key) {
-            existing.value = value.to_string();
-            existing.timestamp = chrono::Utc::now();
-        } else {
-            vars.push(EnvVar::new(key.to_string(), value.to_string(), source));
+        // 更新或添加，其余内容保持不变
+        let mut document = self.load_document(&source).await?;
+        document.set_in_section(key, value, options.section.as_deref());
+        if options.mark_secret {
+            document.mark_secret(key);
         }
-
-        self.write_vars(&source, &vars).await
+        self.save_document(&source, &document).await
     }
 
     async fn delete(&self, key: &str, source: &EnvSource) -> Result<bool> {
@@ -193,15 +291,12 @@ impl EnvRepository for FileEnvRepository {
             )));
         }
 
-        let mut vars = self.read_vars(source).await?;
-        let original_len = vars.len();
-        vars.retain(|v| v.key != key);
-
-        if vars.len() == original_len {
+        let mut document = self.load_document(source).await?;
+        if !document.remove(key) {
             return Ok(false);
         }
 
-        self.write_vars(source, &vars).await?;
+        self.save_document(source, &document).await?;
         Ok(true)
     }
 
@@ -209,16 +304,11 @@ impl EnvRepository for FileEnvRepository {
         match source_filter {
             Some(source) => self.read_vars(&source).await,
             None => {
-                // 合并所有层级
-                let mut map = HashMap::new();
-
-                for source in [
-                    EnvSource::System,
-                    EnvSource::User,
-                    EnvSource::Project,
-                    EnvSource::Local,
-                ] {
-                    let vars = self.read_vars(&source).await?;
+                // 合并所有层级，按变量名排序
+                let mut map = BTreeMap::new();
+
+                for source in &self.config.layer_order {
+                    let vars = self.read_vars(source).await?;
                     for var in vars {
                         map.insert(var.key.clone(), var);
                     }
@@ -229,40 +319,169 @@ impl EnvRepository for FileEnvRepository {
         }
     }
 
-    async fn import(&self, file_path: &Path, target_source: EnvSource) -> Result<usize> {
+    async fn import(&self, changes: &ImportChanges, target_source: EnvSource) -> Result<()> {
         if !target_source.is_writable() {
             return Err(DomainError::PermissionDenied("目标层级不可写".to_string()));
         }
 
-        if !file_path.exists() {
-            return Err(DomainError::FileNotFound(file_path.to_path_buf()));
+        let mut document = self.load_document(&target_source).await?;
+        for (key, template) in &changes.set {
+            document.set_template(key, template);
+        }
+        for key in &changes.remove {
+            document.remove(key);
         }
 
-        let content = tokio::fs::read_to_string(file_path)
-            .await
-            .map_err(|e| DomainError::Io(e.to_string()))?;
+        self.save_document(&target_source, &document).await
+    }
 
-        let imported_vars = Self::parse_dotenv(&content, &EnvSource::System);
-        let mut existing_vars = self.read_vars(&target_source).await?;
+    async fn clear_cache(&self) {
+        self.cache.clear();
+    }
+
+    async fn rewrite_values(&self, source: &EnvSource, rewrite: &ValueRewriter) -> Result<usize> {
+        let paths = match source {
+            EnvSource::System => {
+                return Err(DomainError::PermissionDenied(format!(
+                    "{} 层不可写",
+                    source
+                )));
+            }
+            EnvSource::Profile => paths::list_profile_paths(&self.config)?,
+            _ => vec![self.get_path(source)?],
+        };
 
         let mut count = 0;
-        for var in imported_vars {
-            if !existing_vars.iter().any(|v| v.key == var.key) {
-                existing_vars.push(EnvVar::new(var.key, var.value, target_source));
-                count += 1;
+        for path in paths {
+            if !path.exists() {
+                continue;
+            }
+
+            let content = tokio::fs::read_to_string(&path)
+                .await
+                .map_err(|e| DomainError::Io(e.to_string()))?;
+            let mut document = DotenvDocument::parse(&content).map_err(|e| in_file(&path, e))?;
+
+            let mut changed = 0;
+            for entry in document.entries() {
+                if let Some(value) = rewrite(&entry.key, &entry.value)? {
+                    document.set(&entry.key, &value);
+                    changed += 1;
+                }
+            }
+
+            if changed > 0 {
+                tokio::fs::write(&path, document.render())
+                    .await
+                    .map_err(|e| DomainError::Io(e.to_string()))?;
+                self.cache.invalidate(&path);
+                count += changed;
             }
         }
 
-        self.write_vars(&target_source, &existing_vars).await?;
         Ok(count)
     }
 
-    async fn export(&self, source_filter: Option<EnvSource>) -> Result<String> {
-        let vars = self.list(source_filter).await?;
-        Ok(Self::serialize_dotenv(&vars))
+    async fn source_files(&self) -> Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        for source in self.layer_order() {
+            files.extend(self.layer_files(&source).await?);
+        }
+        Ok(files)
     }
 
-    async fn clear_cache(&self) {
-        self.cache.clear();
+    async fn definitions(&self, key: &str) -> Result<Vec<Definition>> {
+        let mut definitions = Vec::new();
+
+        for source in self.layer_order() {
+            if source == EnvSource::System {
+                if let Some(value) = paths::get_system_env()?.remove(key) {
+                    definitions.push(Definition {
+                        source,
+                        value,
+                        path: None,
+                        line: None,
+                        secret: false,
+                    });
+                }
+                continue;
+            }
+            if source == EnvSource::Profile && key == PROFILE_EXTENDS_KEY {
+                continue;
+            }
+
+            for path in self.layer_files(&source).await? {
+                if !path.exists() {
+                    continue;
+                }
+                let content = tokio::fs::read_to_string(&path)
+                    .await
+                    .map_err(|e| DomainError::Io(e.to_string()))?;
+                // 同一文件中重复定义时以最后一处为准
+                // 无法解析的行已在读取层级时警告过
+                if let Some(entry) = dotenv::parse_lenient(&content)
+                    .0
+                    .into_iter()
+                    .rev()
+                    .find(|e| e.key == key)
+                {
+                    definitions.push(Definition {
+                        source,
+                        value: entry.value,
+                        path: Some(path),
+                        line: Some(entry.line),
+                        secret: entry.secret,
+                    });
+                }
+            }
+        }
+
+        Ok(definitions)
+    }
+
+    fn layer_path(&self, source: &EnvSource) -> Result<PathBuf> {
+        self.get_path(source)
+    }
+
+    fn active_profile(&self) -> Option<String> {
+        self.config.profile.clone()
+    }
+
+    fn layer_order(&self) -> Vec<EnvSource> {
+        self.config.layer_order.clone()
+    }
+
+    async fn profile_chain(&self) -> Result<Vec<String>> {
+        let Some(mut name) = self.config.profile.clone() else {
+            return Ok(Vec::new());
+        };
+        let mut chain: Vec<String> = Vec::new();
+
+        loop {
+            if chain.contains(&name) {
+                chain.push(name);
+                return Err(DomainError::Config(format!(
+                    "profile 继承存在循环: {}",
+                    chain.join(" -> ")
+                )));
+            }
+
+            let path = paths::get_profile_path(&self.config, &name)?;
+            if !path.exists() && !chain.is_empty() {
+                return Err(DomainError::Config(format!(
+                    "profile {} 继承的 {} 不存在: {}",
+                    chain[chain.len() - 1],
+                    name,
+                    path.display()
+                )));
+            }
+            chain.push(name);
+
+            let vars = self.read_file_vars(&path, &EnvSource::Profile).await?;
+            match vars.into_iter().find(|v| v.key == PROFILE_EXTENDS_KEY) {
+                Some(parent) => name = parent.value,
+                None => return Ok(chain),
+            }
+        }
     }
 }
@@ -0,0 +1,333 @@
+//! envcli 写入的持久化系统环境变量文件
+//!
+//! Unix 上不同作用域对应不同文件，均以受管区块维护：
+//! - `global`: shell 启动文件（见 [`Shell::rc_path`]）
+//! - `machine`: `/etc/environment`（Linux，由 pam_env 读取）
+//! - `session`: `~/.config/environment.d/50-envcli.conf`（Linux，由 systemd 用户会话读取）
+//!
+//! Windows 上变量保存在注册表中（global: HKCU，machine: HKLM），envcli 写入的变量名
+//! 另记录在同一根键下的 `Software\envcli\Environment` 中，列出与删除只涉及这些变量。
+
+use crate::domain::error::{DomainError, Result};
+use crate::infrastructure::managed_block;
+use crate::infrastructure::shell::Shell;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// 系统变量作用域
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SystemScope {
+    /// 当前用户（Windows: HKCU；Unix: shell 启动文件）
+    Global,
+    /// 整台机器（Windows: HKLM；Linux: /etc/environment）
+    Machine,
+    /// 当前用户的图形/systemd 会话（Linux: environment.d）
+    Session,
+}
+
+impl fmt::Display for SystemScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SystemScope::Global => write!(f, "global"),
+            SystemScope::Machine => write!(f, "machine"),
+            SystemScope::Session => write!(f, "session"),
+        }
+    }
+}
+
+impl SystemScope {
+    /// 解析 `--scope` 参数
+    pub fn parse(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "global" => Ok(SystemScope::Global),
+            "machine" => Ok(SystemScope::Machine),
+            "session" => Ok(SystemScope::Session),
+            _ => Err(DomainError::InvalidArgument(
+                "scope 必须是 'global'、'machine' 或 'session'".to_string(),
+            )),
+        }
+    }
+}
+
+/// 已持久化的变量
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PersistedVar {
+    pub key: String,
+    pub value: String,
+    pub scope: SystemScope,
+    /// 所在文件（Windows 上为注册表项）
+    pub location: String,
+}
+
+/// envcli 维护的环境变量文件
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnvFile {
+    /// shell 启动文件
+    Shell(Shell),
+    /// `/etc/environment`
+    PamEnvironment,
+    /// `~/.config/environment.d/50-envcli.conf`
+    EnvironmentD,
+}
+
+impl EnvFile {
+    /// 作用域对应的文件，`shell` 仅用于 global 作用域
+    pub fn for_scope(scope: SystemScope, shell: Shell) -> Result<Self> {
+        match scope {
+            SystemScope::Global => Ok(EnvFile::Shell(shell)),
+            SystemScope::Machine if cfg!(target_os = "linux") => Ok(EnvFile::PamEnvironment),
+            SystemScope::Session if cfg!(target_os = "linux") => Ok(EnvFile::EnvironmentD),
+            _ => Err(DomainError::InvalidArgument(format!(
+                "{} 作用域仅支持 Linux",
+                scope
+            ))),
+        }
+    }
+
+    /// 文件所属作用域
+    #[must_use]
+    pub fn scope(&self) -> SystemScope {
+        match self {
+            EnvFile::Shell(_) => SystemScope::Global,
+            EnvFile::PamEnvironment => SystemScope::Machine,
+            EnvFile::EnvironmentD => SystemScope::Session,
+        }
+    }
+
+    /// 文件路径，`root` 为机器级文件所在的文件系统根目录
+    pub fn path(&self, root: &Path) -> Result<PathBuf> {
+        match self {
+            EnvFile::Shell(shell) => shell.rc_path(),
+            EnvFile::PamEnvironment => Ok(root.join("etc/environment")),
+            EnvFile::EnvironmentD => {
+                let config = std::env::var_os("XDG_CONFIG_HOME")
+                    .map(PathBuf::from)
+                    .filter(|dir| dir.is_absolute())
+                    .or_else(|| dirs::home_dir().map(|h| h.join(".config")))
+                    .ok_or_else(|| DomainError::Config("无法获取主目录".to_string()))?;
+                Ok(config.join("environment.d/50-envcli.conf"))
+            }
+        }
+    }
+
+    /// 赋值语句
+    ///
+    /// - pam_env 不支持转义，含 `"` 或换行的值无法表示
+    /// - environment.d 会展开 `$VAR`，值中的 `$` 被转义以按字面量生效
+    pub fn assignment(&self, key: &str, value: &str) -> Result<String> {
+        match self {
+            EnvFile::Shell(shell) => Ok(shell.export(key, value)),
+            EnvFile::PamEnvironment => {
+                if value.contains(['"', '\n', '\r']) {
+                    return Err(DomainError::InvalidArgument(
+                        "/etc/environment 的值不能包含双引号或换行".to_string(),
+                    ));
+                }
+                Ok(format!("{}=\"{}\"", key, value))
+            }
+            EnvFile::EnvironmentD => {
+                if value.contains(['\n', '\r']) {
+                    return Err(DomainError::InvalidArgument(
+                        "environment.d 的值不能包含换行".to_string(),
+                    ));
+                }
+                let mut escaped = String::with_capacity(value.len());
+                for c in value.chars() {
+                    if matches!(c, '\\' | '"' | '$' | '`') {
+                        escaped.push('\\');
+                    }
+                    escaped.push(c);
+                }
+                Ok(format!("{}=\"{}\"", key, escaped))
+            }
+        }
+    }
+
+    /// 识别赋值语句中的变量名
+    #[must_use]
+    pub fn key_of(&self, line: &str) -> Option<String> {
+        match self {
+            EnvFile::Shell(shell) => shell.exported_key(line),
+            EnvFile::PamEnvironment | EnvFile::EnvironmentD => {
+                let line = line.trim_start();
+                if line.starts_with('#') {
+                    return None;
+                }
+                line.split_once('=').map(|(key, _)| key.trim().to_string())
+            }
+        }
+    }
+
+    /// 解析 [`EnvFile::assignment`] 生成的语句，返回变量名与值
+    #[must_use]
+    pub fn parse_line(&self, line: &str) -> Option<(String, String)> {
+        match self {
+            EnvFile::Shell(shell) => shell.parse_export(line),
+            EnvFile::PamEnvironment | EnvFile::EnvironmentD => {
+                let key = self.key_of(line)?;
+                let (_, value) = line.split_once('=')?;
+                let value = value.trim();
+                let value = value
+                    .strip_prefix('"')
+                    .and_then(|v| v.strip_suffix('"'))
+                    .unwrap_or(value);
+                if *self == EnvFile::PamEnvironment {
+                    return Some((key, value.to_string()));
+                }
+
+                let mut unescaped = String::with_capacity(value.len());
+                let mut chars = value.chars();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => unescaped.push(chars.next().unwrap_or('\\')),
+                        c => unescaped.push(c),
+                    }
+                }
+                Some((key, unescaped))
+            }
+        }
+    }
+
+    /// 生效方式说明
+    #[must_use]
+    pub fn activation_hint(&self, path: &Path) -> String {
+        match self {
+            EnvFile::Shell(_) => format!("新开终端或执行 `source {}` 后生效", path.display()),
+            EnvFile::PamEnvironment => "重新登录后生效".to_string(),
+            EnvFile::EnvironmentD => "重新登录后生效".to_string(),
+        }
+    }
+}
+
+/// 本平台上 envcli 可能写入的全部文件
+#[must_use]
+pub fn managed_files() -> Vec<EnvFile> {
+    let mut files: Vec<EnvFile> = [Shell::Bash, Shell::Zsh, Shell::Fish, Shell::Posix]
+        .into_iter()
+        .map(EnvFile::Shell)
+        .collect();
+    if cfg!(target_os = "linux") {
+        files.push(EnvFile::PamEnvironment);
+        files.push(EnvFile::EnvironmentD);
+    }
+    files
+}
+
+/// 读取各文件受管区块中的变量，按文件顺序返回
+pub fn read_persisted(root: &Path) -> Result<Vec<PersistedVar>> {
+    let mut vars = Vec::new();
+    for file in managed_files() {
+        let path = file.path(root)?;
+        if !path.exists() {
+            continue;
+        }
+        let block = managed_block::read(&path)?;
+        for line in block.lines() {
+            if let Some((key, value)) = file.parse_line(line) {
+                vars.push(PersistedVar {
+                    key,
+                    value,
+                    scope: file.scope(),
+                    location: path.display().to_string(),
+                });
+            }
+        }
+    }
+    Ok(vars)
+}
+
+/// 注册表中的环境变量项
+#[cfg(windows)]
+const REGISTRY_KEYS: [(SystemScope, &str); 2] = [
+    (SystemScope::Global, "HKCU\\Environment"),
+    (
+        SystemScope::Machine,
+        "HKLM\\SYSTEM\\CurrentControlSet\\Control\\Session Manager\\Environment",
+    ),
+];
+
+/// 记录 envcli 写入的变量名的注册表子项，位于作用域对应的根键下
+#[cfg(windows)]
+const MANAGED_SUBKEY: &str = "Software\\envcli\\Environment";
+
+/// 作用域对应的根键与环境变量子项路径
+#[cfg(windows)]
+fn registry_root(scope: SystemScope) -> Result<(winreg::RegKey, &'static str)> {
+    use winreg::RegKey;
+    use winreg::enums::*;
+
+    match scope {
+        SystemScope::Global => Ok((RegKey::predef(HKEY_CURRENT_USER), "Environment")),
+        SystemScope::Machine => Ok((
+            RegKey::predef(HKEY_LOCAL_MACHINE),
+            "SYSTEM\\CurrentControlSet\\Control\\Session Manager\\Environment",
+        )),
+        SystemScope::Session => Err(DomainError::InvalidArgument(
+            "session 作用域仅支持 Linux".to_string(),
+        )),
+    }
+}
+
+/// 打开作用域对应的注册表项
+#[cfg(windows)]
+pub fn open_registry(scope: SystemScope, flags: u32) -> Result<winreg::RegKey> {
+    let (root, reg_path) = registry_root(scope)?;
+    root.open_subkey_with_flags(reg_path, flags)
+        .map_err(|e| DomainError::SystemEnvWriteFailed(e.to_string()))
+}
+
+/// envcli 在作用域中写入过的变量名
+#[cfg(windows)]
+pub fn managed_registry_keys(scope: SystemScope) -> Result<Vec<String>> {
+    let (root, _) = registry_root(scope)?;
+    let managed = match root.open_subkey_with_flags(MANAGED_SUBKEY, winreg::enums::KEY_READ) {
+        Ok(managed) => managed,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(DomainError::SystemEnvWriteFailed(e.to_string())),
+    };
+    Ok(managed
+        .enum_values()
+        .flatten()
+        .map(|(key, _)| key)
+        .collect())
+}
+
+/// 记录或移除 envcli 写入的变量名
+#[cfg(windows)]
+pub fn mark_managed(scope: SystemScope, key: &str, managed: bool) -> Result<()> {
+    let (root, _) = registry_root(scope)?;
+    let failed = |e: std::io::Error| DomainError::SystemEnvWriteFailed(e.to_string());
+
+    if managed {
+        let (list, _) = root.create_subkey(MANAGED_SUBKEY).map_err(failed)?;
+        return list.set_value(key, &String::new()).map_err(failed);
+    }
+    match root.open_subkey_with_flags(MANAGED_SUBKEY, winreg::enums::KEY_WRITE) {
+        Ok(list) => match list.delete_value(key) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(failed(e)),
+            _ => Ok(()),
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(failed(e)),
+    }
+}
+
+/// 读取注册表中由 envcli 写入的用户级与机器级环境变量
+#[cfg(windows)]
+pub fn read_registry() -> Result<Vec<PersistedVar>> {
+    let mut vars = Vec::new();
+    for (scope, location) in REGISTRY_KEYS {
+        let env = open_registry(scope, winreg::enums::KEY_READ)?;
+        for key in managed_registry_keys(scope)? {
+            if let Ok(value) = env.get_value::<String, _>(&key) {
+                vars.push(PersistedVar {
+                    key,
+                    value,
+                    scope,
+                    location: location.to_string(),
+                });
+            }
+        }
+    }
+    Ok(vars)
+}
@@ -0,0 +1,91 @@
+//! 受信任目录列表
+//!
+//! shell 钩子只在受信任的项目目录中自动加载变量，避免克隆的仓库注入环境变量。
+//! 列表保存在用户配置目录的 `allow.list` 中，每行一个项目根目录的绝对路径。
+
+use crate::domain::error::{DomainError, Result};
+use std::path::{Path, PathBuf};
+
+/// 列表文件名
+pub const ALLOW_FILE_NAME: &str = "allow.list";
+
+/// 受信任目录列表
+#[derive(Debug, Clone)]
+pub struct TrustStore {
+    path: PathBuf,
+}
+
+impl TrustStore {
+    /// 使用用户配置目录中的列表文件
+    pub fn new(user_dir: &Path) -> Self {
+        Self {
+            path: user_dir.join(ALLOW_FILE_NAME),
+        }
+    }
+
+    /// 目录是否受信任
+    pub fn is_allowed(&self, dir: &Path) -> Result<bool> {
+        let dir = normalize(dir);
+        Ok(self.read()?.contains(&dir))
+    }
+
+    /// 信任目录，返回是否新加入
+    pub fn allow(&self, dir: &Path) -> Result<bool> {
+        let mut dirs = self.read()?;
+        let dir = normalize(dir);
+        if dirs.contains(&dir) {
+            return Ok(false);
+        }
+        dirs.push(dir);
+        self.write(dirs)?;
+        Ok(true)
+    }
+
+    /// 取消信任，返回是否存在
+    pub fn revoke(&self, dir: &Path) -> Result<bool> {
+        let mut dirs = self.read()?;
+        let dir = normalize(dir);
+        let len = dirs.len();
+        dirs.retain(|d| *d != dir);
+        if dirs.len() == len {
+            return Ok(false);
+        }
+        self.write(dirs)?;
+        Ok(true)
+    }
+
+    fn read(&self) -> Result<Vec<PathBuf>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let content = std::fs::read_to_string(&self.path)
+            .map_err(|e| DomainError::Io(format!("{}: {}", self.path.display(), e)))?;
+        Ok(content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(PathBuf::from)
+            .collect())
+    }
+
+    fn write(&self, mut dirs: Vec<PathBuf>) -> Result<()> {
+        dirs.sort();
+        let mut content = String::new();
+        for dir in dirs {
+            content.push_str(&dir.to_string_lossy());
+            content.push('\n');
+        }
+
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| DomainError::Io(format!("{}: {}", parent.display(), e)))?;
+        }
+        std::fs::write(&self.path, content)
+            .map_err(|e| DomainError::Io(format!("{}: {}", self.path.display(), e)))
+    }
+}
+
+/// 解析符号链接，使同一目录的不同写法得到同一路径
+fn normalize(dir: &Path) -> PathBuf {
+    dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf())
+}
@@ -0,0 +1,64 @@
+//! 文件变化检测
+//!
+//! 按修改时间与大小轮询一组文件，不依赖平台的文件系统通知；
+//! 文件的创建与删除同样视为变化。
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// 轮询间隔
+pub const POLL_INTERVAL: Duration = Duration::from_millis(300);
+
+/// 文件状态：修改时间与大小，不存在时为 `None`
+type Stamp = Option<(SystemTime, u64)>;
+
+fn stamp(path: &Path) -> Stamp {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// 监视的文件集合
+#[derive(Debug, Default)]
+pub struct FileWatcher {
+    files: Vec<(PathBuf, Stamp)>,
+}
+
+impl FileWatcher {
+    pub fn new(paths: impl IntoIterator<Item = PathBuf>) -> Self {
+        let mut watcher = Self::default();
+        watcher.set_files(paths);
+        watcher
+    }
+
+    /// 替换监视的文件，已在监视中的文件保留上次记录的状态
+    pub fn set_files(&mut self, paths: impl IntoIterator<Item = PathBuf>) {
+        let mut files = Vec::new();
+        for path in paths {
+            if files.iter().any(|(p, _)| *p == path) {
+                continue;
+            }
+            let previous = self.files.iter().find(|(p, _)| *p == path).map(|(_, s)| *s);
+            let stamp = previous.unwrap_or_else(|| stamp(&path));
+            files.push((path, stamp));
+        }
+        self.files = files;
+    }
+
+    /// 监视中的文件
+    pub fn files(&self) -> impl Iterator<Item = &Path> {
+        self.files.iter().map(|(p, _)| p.as_path())
+    }
+
+    /// 检查变化并更新记录，返回自上次检查以来变化的文件
+    pub fn poll(&mut self) -> Vec<PathBuf> {
+        let mut changed = Vec::new();
+        for (path, last) in &mut self.files {
+            let current = stamp(path);
+            if current != *last {
+                *last = current;
+                changed.push(path.clone());
+            }
+        }
+        changed
+    }
+}
@@ -21,4 +21,4 @@ pub mod cli;
 pub mod app;
 
 // 重新导出常用类型
-pub use domain::{DomainError, EnvSource, EnvVar, OutputFormat, Result};
+pub use domain::{DomainError, EnvSource, EnvVar, Format, Result};
@@ -4,8 +4,16 @@
 
 use clap::Parser;
 use envcli::app::{AppConfig, Application};
-use envcli::cli::{self, CacheCommands, Cli, Commands, ConfigCommands};
+use envcli::application::services::{ExportOptions, ImportStrategy};
+use envcli::cli::{
+    self, CacheCommands, Cli, Commands, ConfigCommands, KeyCommands, SystemCommands,
+};
 use envcli::commands::*;
+use envcli::domain::SetOptions;
+use envcli::domain::secrets::SecretClassifier;
+use envcli::infrastructure::{paths, process};
+use std::io::IsTerminal;
+use std::time::Duration;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -16,93 +24,303 @@ async fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
 
     // 创建应用配置
-    let app_config = AppConfig {
+    let mut app_config = AppConfig {
         verbose: cli.verbose,
         ..Default::default()
     };
+    app_config.storage.profile = cli.effective_profile();
+    app_config.storage.cascade = cli.cascade;
 
-    // 初始化应用程序
-    let app = Application::new(app_config.clone()).await?;
+    // 解析用户配置目录与项目目录
+    if let Err(e) = resolve_storage_dirs(&cli, &mut app_config) {
+        eprintln!("错误: {}", e);
+        std::process::exit(1);
+    }
 
     // 创建命令上下文
     let ctx = CommandContext {
         verbose: cli.verbose,
+        reveal: cli.reveal,
+    };
+
+    // 配置命令只依赖目录，配置文件有误时也能运行
+    let command = match cli.command {
+        Commands::Config(config_cmd) => {
+            let result = run_config_command(config_cmd, &app_config, &ctx).await;
+            return finish(result);
+        }
+        command => command,
     };
 
+    // 初始化应用程序（合并 config.toml）
+    let app = match Application::new(app_config).await {
+        Ok(app) => app,
+        Err(e) => {
+            eprintln!("错误: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let config = &app.config;
+
     // 执行命令
-    let result = match cli.command {
+    let result = match command {
         // 核心命令
-        Commands::Get { key } => {
-            let cmd = get::GetCommand::new(app.env_service.clone(), key);
+        Commands::Get { key, explain, .. } if explain => {
+            let cmd = explain::ExplainCommand::new(
+                app.env_service.clone(),
+                key,
+                "text".to_string(),
+                true,
+            );
+            cmd.execute(&ctx).await
+        }
+
+        Commands::Get { key, raw, .. } => {
+            let cmd = get::GetCommand::new(app.env_service.clone(), key, raw);
+            cmd.execute(&ctx).await
+        }
+
+        Commands::Explain { key, format } => {
+            let cmd = explain::ExplainCommand::new(app.env_service.clone(), key, format, false);
             cmd.execute(&ctx).await
         }
 
-        Commands::Set { key, value, target } => {
-            let source = cli::parse_writable_source(&target)?;
-            let cmd = set::SetCommand::new(app.env_service.clone(), key, value, source);
+        Commands::Set {
+            key,
+            value,
+            target,
+            section,
+            mark_secret,
+            secret,
+        } => {
+            let source = cli::parse_target(target.as_deref(), config.default_target)?;
+            let options = SetOptions {
+                section,
+                mark_secret,
+                encrypt: secret,
+            };
+            let cmd = set::SetCommand::new(app.env_service.clone(), key, value, source, options);
             cmd.execute(&ctx).await
         }
 
         Commands::Unset { key, target } => {
-            let source = cli::parse_writable_source(&target)?;
+            let source = cli::parse_target(target.as_deref(), config.default_target)?;
             let cmd = unset::UnsetCommand::new(app.env_service.clone(), key, source);
             cmd.execute(&ctx).await
         }
 
-        Commands::List { source, format } => {
+        Commands::List {
+            source,
+            format,
+            raw,
+            sort,
+            prefix,
+            grep,
+            only_shadowed,
+            show_source,
+        } => {
             let source = cli::parse_source(source.as_deref());
-            let format = format.as_str().into();
-            let cmd = list::ListCommand::new(app.env_service.clone(), source, format);
+            let view = cli::parse_list_view(format.as_deref(), &config.default_format)?;
+            let options = list::ListOptions {
+                raw,
+                sort: list::ListSort::parse(&sort)?,
+                prefix,
+                grep,
+                only_shadowed,
+                show_source,
+            };
+            let cmd = list::ListCommand::new(app.env_service.clone(), source, view, options);
             cmd.execute(&ctx).await
         }
 
-        Commands::Export { source, format } => {
+        Commands::Export {
+            source,
+            format,
+            raw,
+            decrypt,
+            unset,
+        } => {
             let source = cli::parse_source(source.as_deref());
-            let format = format.as_str().into();
-            let cmd = export::ExportCommand::new(app.env_service.clone(), source, format);
+            let format = cli::parse_format(format.as_deref(), &config.default_format)?;
+            // 输出到终端时掩码；重定向或经管道读取（如 eval）时输出真实值
+            let options = ExportOptions {
+                raw,
+                reveal: ctx.reveal || !std::io::stdout().is_terminal(),
+                decrypt,
+            };
+            let cmd =
+                export::ExportCommand::new(app.env_service.clone(), source, format, options, unset);
             cmd.execute(&ctx).await
         }
 
-        Commands::Import { file, target } => {
-            let source = cli::parse_writable_source(&target)?;
+        Commands::Diff { a, b, format } => {
+            let classifier = SecretClassifier::new(config.secret_patterns.clone());
+            let cmd = diff::DiffCommand::new(
+                config.storage.clone(),
+                app.env_service.cipher(),
+                classifier,
+                a,
+                b,
+                format,
+            );
+            cmd.execute(&ctx).await
+        }
+
+        Commands::Import {
+            file,
+            target,
+            format,
+            strategy,
+        } => {
+            let source = cli::parse_target(target.as_deref(), config.default_target)?;
+            let format = cli::parse_import_format(format.as_deref())?;
+            let strategy = ImportStrategy::parse(&strategy)?;
             let cmd = import::ImportCommand::new(
                 app.env_service.clone(),
                 std::path::PathBuf::from(file),
                 source,
+                format,
+                strategy,
             );
             cmd.execute(&ctx).await
         }
 
         Commands::Run {
-            env,
-            from_file,
+            child_env,
+            print_env,
+            watch,
+            reload_signal,
+            stop_timeout,
+            secrets_as_files,
             command,
         } => {
-            let cmd = run::RunCommand::new(app.env_service.clone(), env, from_file, command);
+            let reload_signal = reload_signal
+                .as_deref()
+                .map(process::parse_signal)
+                .transpose()?;
+            let options = run::RunOptions {
+                print_env,
+                watch,
+                reload_signal,
+                stop_timeout: Some(Duration::from_secs(stop_timeout)),
+                secrets_as_files,
+                ..child_env.into_options()
+            };
+            let cmd = run::RunCommand::new(app.env_service.clone(), command, options);
+            cmd.execute(&ctx).await
+        }
+
+        Commands::Up {
+            names,
+            procfile,
+            stop_timeout,
+            child_env,
+        } => {
+            let cmd = up::UpCommand::new(
+                app.env_service.clone(),
+                config.storage.clone(),
+                config.processes.clone(),
+                names,
+                procfile,
+                Duration::from_secs(stop_timeout),
+                child_env.into_options(),
+            );
+            cmd.execute(&ctx).await
+        }
+
+        Commands::Shell { force, raw } => {
+            let cmd = shell::ShellCommand::new(
+                app.env_service.clone(),
+                config.storage.clone(),
+                force,
+                raw,
+            );
             cmd.execute(&ctx).await
         }
 
         Commands::Status => {
-            let cmd = status::StatusCommand::new(app.env_service.clone());
+            let cmd = status::StatusCommand::new(app.env_service.clone(), config.storage.clone());
             cmd.execute(&ctx).await
         }
 
         Commands::Doctor => {
-            let cmd = doctor::DoctorCommand::new(app.env_service.clone());
+            let cmd = doctor::DoctorCommand::new(app.env_service.clone(), config.storage.clone());
+            cmd.execute(&ctx).await
+        }
+
+        // shell 钩子
+        Commands::Hook { shell } => {
+            let cmd = hook::HookCommand::new(shell);
+            cmd.execute(&ctx).await
+        }
+
+        Commands::HookEnv { shell } => {
+            let cmd =
+                hook::HookEnvCommand::new(app.env_service.clone(), config.storage.clone(), shell);
+            cmd.execute(&ctx).await
+        }
+
+        Commands::Allow => {
+            let cmd = hook::AllowCommand::new(config.storage.clone(), true);
+            cmd.execute(&ctx).await
+        }
+
+        Commands::Deny => {
+            let cmd = hook::AllowCommand::new(config.storage.clone(), false);
             cmd.execute(&ctx).await
         }
 
         // 系统命令
-        Commands::SystemSet { key, value, scope } => {
-            let cmd = system::SystemSetCommand::new(key, value, scope);
+        Commands::SystemSet {
+            key,
+            value,
+            scope,
+            shell,
+            root,
+            dry_run,
+        } => {
+            let options = system::SystemOptions {
+                scope,
+                shell,
+                root,
+                dry_run,
+            };
+            let cmd = system::SystemSetCommand::new(key, value, options);
             cmd.execute(&ctx).await
         }
 
-        Commands::SystemUnset { key, scope } => {
-            let cmd = system::SystemUnsetCommand::new(key, scope);
+        Commands::SystemUnset {
+            key,
+            scope,
+            shell,
+            root,
+            dry_run,
+        } => {
+            let options = system::SystemOptions {
+                scope,
+                shell,
+                root,
+                dry_run,
+            };
+            let cmd = system::SystemUnsetCommand::new(key, options);
             cmd.execute(&ctx).await
         }
 
+        Commands::System(system_cmd) => {
+            let classifier = SecretClassifier::new(config.secret_patterns.clone());
+            match system_cmd {
+                SystemCommands::List { scope, root } => {
+                    let cmd = system::SystemListCommand::new(scope, root, classifier);
+                    cmd.execute(&ctx).await
+                }
+
+                SystemCommands::Diff { scope, root } => {
+                    let cmd = system::SystemDiffCommand::new(scope, root, classifier);
+                    cmd.execute(&ctx).await
+                }
+            }
+        }
+
         // 缓存命令
         Commands::Cache(cache_cmd) => match cache_cmd {
             CacheCommands::Stats => {
@@ -116,30 +334,97 @@ async fn main() -> anyhow::Result<()> {
             }
         },
 
-        // 配置命令
-        Commands::Config(config_cmd) => match config_cmd {
-            ConfigCommands::Validate { verbose } => {
-                let cmd = config::ConfigValidateCommand::new(verbose);
-                cmd.execute(&ctx).await
-            }
-
-            ConfigCommands::Init { force } => {
-                let cmd = config::ConfigInitCommand::new(force);
-                cmd.execute(&ctx).await
-            }
+        // 配置命令已在初始化前处理
+        Commands::Config(_) => unreachable!(),
 
-            ConfigCommands::Info => {
-                let cmd = config::ConfigInfoCommand::new();
+        // 密钥命令
+        Commands::Key(key_cmd) => match key_cmd {
+            KeyCommands::Rotate => {
+                let cmd = key::KeyRotateCommand::new(app.env_service.clone());
                 cmd.execute(&ctx).await
             }
         },
     };
 
-    // 处理错误
+    finish(result)
+}
+
+/// 处理命令结果，出错时打印错误并以非零状态退出
+fn finish(result: envcli::Result<()>) -> anyhow::Result<()> {
     if let Err(e) = result {
-        eprintln!("错误: {}", e);
-        std::process::exit(1);
+        if !matches!(e, envcli::domain::DomainError::ExitStatus(_)) {
+            eprintln!("错误: {}", e);
+        }
+        std::process::exit(e.exit_code());
+    }
+
+    Ok(())
+}
+
+/// 执行配置命令
+async fn run_config_command(
+    config_cmd: ConfigCommands,
+    app_config: &AppConfig,
+    ctx: &CommandContext,
+) -> envcli::Result<()> {
+    let storage = app_config.storage.clone();
+    match config_cmd {
+        ConfigCommands::Validate { verbose } => {
+            config::ConfigValidateCommand::new(storage, verbose)
+                .execute(ctx)
+                .await
+        }
+        ConfigCommands::Init { force } => {
+            config::ConfigInitCommand::new(storage, force)
+                .execute(ctx)
+                .await
+        }
+        ConfigCommands::Info => config::ConfigInfoCommand::new(storage).execute(ctx).await,
+        ConfigCommands::Get { key } => {
+            config::ConfigGetCommand::new(storage, key)
+                .execute(ctx)
+                .await
+        }
+        ConfigCommands::Set {
+            key,
+            value,
+            project,
+        } => {
+            config::ConfigSetCommand::new(storage, key, value, project)
+                .execute(ctx)
+                .await
+        }
+        ConfigCommands::List => config::ConfigListCommand::new(storage).execute(ctx).await,
+    }
+}
+
+/// 解析用户配置目录（含旧目录迁移）与项目目录
+fn resolve_storage_dirs(cli: &Cli, app_config: &mut AppConfig) -> envcli::Result<()> {
+    let user_dir = paths::resolve_user_dir()?;
+    if let Some(legacy) = paths::migrate_legacy_user_dir(&user_dir)? {
+        eprintln!(
+            "已将配置目录从 {} 迁移到 {}",
+            legacy.display(),
+            user_dir.display()
+        );
     }
+    app_config.storage.user_dir = user_dir;
+
+    app_config.storage.project_dir = match &cli.project_dir {
+        Some(dir) => {
+            let root = std::path::absolute(dir)
+                .ok()
+                .filter(|root| root.is_dir())
+                .ok_or_else(|| {
+                    envcli::DomainError::InvalidArgument(format!(
+                        "项目目录不存在: {}",
+                        dir.display()
+                    ))
+                })?;
+            root.join(paths::PROJECT_DIR_NAME)
+        }
+        None => paths::discover_project_dir()?,
+    };
 
     Ok(())
 }
@@ -45,6 +45,42 @@ fn get_env_command() -> std::path::PathBuf {
     path
 }
 
+/// 在 `temp_dir` 中运行 envcli
+///
+/// `HOME` 与 `ENVCLI_HOME` 都指向 `temp_dir/home`，测试不会读写开发者真实的主目录。
+fn envcli(temp_dir: &TempDir) -> Command {
+    Command::from_std(envcli_process(temp_dir))
+}
+
+/// 同 [`envcli`]，返回标准库的 `Command`，用于需要 spawn 并读取输出流的测试
+fn envcli_process(temp_dir: &TempDir) -> std::process::Command {
+    let home = temp_dir.path().join("home");
+    let mut command = std::process::Command::new(get_env_command());
+    command
+        .current_dir(temp_dir.path())
+        .env("ENVCLI_HOME", &home)
+        .env("HOME", &home);
+    command
+}
+
+/// 创建临时项目，`files` 为相对 `.envcli` 的路径与内容
+fn create_project(files: &[(&str, &str)]) -> TempDir {
+    let temp_dir = create_test_env();
+    let project = temp_dir.path().join(".envcli");
+    fs::create_dir_all(&project).unwrap();
+    for (path, content) in files {
+        let path = project.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+    temp_dir
+}
+
+/// 含普通变量与敏感变量的项目，run、up 等测试共用
+fn create_app_project() -> TempDir {
+    create_project(&[("project.env", "APP_ENV=test\nAPI_TOKEN=abcdefghijkl\n")])
+}
+
 mod basic_commands {
     use super::*;
 
@@ -267,7 +303,7 @@ mod import_export_commands {
             .success()
             .stdout(predicate::str::contains("import_value"));
 
-        // 导出
+        // 导出（IMPORT_KEY 匹配敏感模式，导出时仍输出原值）
         let mut export_cmd = Command::new(&cmd);
         export_cmd.arg("export").current_dir(&temp_dir);
 
@@ -374,3 +410,3098 @@ mod error_handling {
         set_cmd.assert().failure();
     }
 }
+
+mod dotenv_parsing {
+    use super::*;
+
+    #[test]
+    fn test_import_quoted_values_and_export_prefix() {
+        let temp_dir = create_test_env();
+
+        let env_file = temp_dir.path().join("quoted.env");
+        fs::write(
+            &env_file,
+            "export EXPORTED=yes\n\
+             DOUBLE=\"a b # c\"\n\
+             SINGLE='raw \\n value'\n\
+             ESCAPED=\"line1\\nline2\"\n\
+             INLINE=plain # comment\n\
+             CERT=\"-----BEGIN-----\nabc\n-----END-----\"\n",
+        )
+        .unwrap();
+
+        envcli(&temp_dir)
+            .arg("import")
+            .arg(env_file.to_str().unwrap())
+            .assert()
+            .success();
+
+        for (key, expected) in [
+            ("EXPORTED", "yes\n"),
+            ("DOUBLE", "a b # c\n"),
+            ("SINGLE", "raw \\n value\n"),
+            ("ESCAPED", "line1\nline2\n"),
+            ("INLINE", "plain\n"),
+            ("CERT", "-----BEGIN-----\nabc\n-----END-----\n"),
+        ] {
+            envcli(&temp_dir)
+                .arg("get")
+                .arg(key)
+                .assert()
+                .success()
+                .stdout(predicate::eq(expected));
+        }
+    }
+
+    #[test]
+    fn test_set_get_roundtrip_special_characters() {
+        let temp_dir = create_test_env();
+        let value = "he said \"hi\" # not a comment \\ end\nsecond line";
+
+        envcli(&temp_dir)
+            .arg("set")
+            .arg("SPECIAL")
+            .arg(value)
+            .assert()
+            .success();
+
+        envcli(&temp_dir)
+            .arg("get")
+            .arg("SPECIAL")
+            .assert()
+            .success()
+            .stdout(predicate::eq(format!("{}\n", value)));
+    }
+
+    #[test]
+    fn test_unterminated_quote_reports_line() {
+        let temp_dir = create_test_env();
+
+        let env_file = temp_dir.path().join("broken.env");
+        fs::write(&env_file, "OK=1\nBROKEN=\"never closed\n").unwrap();
+
+        envcli(&temp_dir)
+            .arg("import")
+            .arg(env_file.to_str().unwrap())
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("第 2 行"));
+    }
+
+    #[test]
+    fn test_malformed_line_in_read_layer_is_skipped_with_warning() {
+        let temp_dir = create_project(&[("project.env", "A=1\nNOT A VALID LINE\nB=2\n")]);
+
+        envcli(&temp_dir)
+            .args(["get", "B"])
+            .assert()
+            .success()
+            .stdout(predicate::eq("2\n"))
+            .stderr(predicate::str::contains("project.env: 第 2 行"))
+            .stderr(predicate::str::contains("已跳过该行"));
+
+        envcli(&temp_dir)
+            .args(["list", "--format", "env"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("A=1"))
+            .stdout(predicate::str::contains("B=2"));
+
+        // 修改其他层级不受影响，文件中的错误行原样保留
+        envcli(&temp_dir).args(["set", "C", "3"]).assert().success();
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join(".envcli/project.env")).unwrap(),
+            "A=1\nNOT A VALID LINE\nB=2\n"
+        );
+    }
+
+    #[test]
+    fn test_editing_malformed_layer_reports_path_and_line() {
+        let temp_dir = create_project(&[("local.env", "OK=1\nBROKEN=\"never closed\n")]);
+
+        envcli(&temp_dir)
+            .args(["set", "C", "3"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("local.env: 第 2 行: 引号未闭合"));
+    }
+
+    #[test]
+    fn test_run_from_file_uses_dotenv_parser() {
+        if cfg!(windows) {
+            return;
+        }
+
+        let temp_dir = create_test_env();
+
+        let env_file = temp_dir.path().join("run.env");
+        fs::write(&env_file, "export GREETING=\"hello world\" # comment\n").unwrap();
+
+        envcli(&temp_dir)
+            .arg("run")
+            .arg("--from-file")
+            .arg(env_file.to_str().unwrap())
+            .arg("--")
+            .arg("sh")
+            .arg("-c")
+            .arg("printf '%s' \"$GREETING\"")
+            .assert()
+            .success()
+            .stdout(predicate::eq("hello world"));
+    }
+}
+
+mod lossless_editing {
+    use super::*;
+
+    const ORIGINAL: &str = "# Team settings\n\
+                            \n\
+                            # [database]\n\
+                            DB_HOST=localhost   # primary\n\
+                            DB_PORT=5432\n\
+                            \n\
+                            # [cache]\n\
+                            export REDIS_URL=redis://localhost\n";
+
+    fn write_project_file(temp_dir: &TempDir) -> std::path::PathBuf {
+        let dir = temp_dir.path().join(".envcli");
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("project.env");
+        fs::write(&file, ORIGINAL).unwrap();
+        file
+    }
+
+    #[test]
+    fn test_set_existing_key_changes_only_that_line() {
+        let temp_dir = create_test_env();
+        let file = write_project_file(&temp_dir);
+
+        envcli(&temp_dir)
+            .args(["set", "DB_HOST", "db.internal", "--target", "project"])
+            .assert()
+            .success();
+
+        let expected = ORIGINAL.replace("DB_HOST=localhost", "DB_HOST=db.internal");
+        assert_eq!(fs::read_to_string(&file).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_set_new_key_appends_and_unset_restores() {
+        let temp_dir = create_test_env();
+        let file = write_project_file(&temp_dir);
+
+        envcli(&temp_dir)
+            .args(["set", "NEW_KEY", "new value", "--target", "project"])
+            .assert()
+            .success();
+
+        assert_eq!(
+            fs::read_to_string(&file).unwrap(),
+            format!("{}NEW_KEY=\"new value\"\n", ORIGINAL)
+        );
+
+        envcli(&temp_dir)
+            .args(["unset", "NEW_KEY", "--target", "project"])
+            .assert()
+            .success();
+
+        assert_eq!(fs::read_to_string(&file).unwrap(), ORIGINAL);
+    }
+
+    #[test]
+    fn test_set_new_key_into_section() {
+        let temp_dir = create_test_env();
+        let file = write_project_file(&temp_dir);
+
+        envcli(&temp_dir)
+            .args(["set", "DB_NAME", "app", "--target", "project"])
+            .args(["--section", "database"])
+            .assert()
+            .success();
+
+        let expected = ORIGINAL.replace("DB_PORT=5432\n", "DB_PORT=5432\nDB_NAME=app\n");
+        assert_eq!(fs::read_to_string(&file).unwrap(), expected);
+
+        envcli(&temp_dir)
+            .args(["set", "LOG_LEVEL", "debug", "--target", "project"])
+            .args(["--section", "logging"])
+            .assert()
+            .success();
+
+        assert!(
+            fs::read_to_string(&file)
+                .unwrap()
+                .ends_with("\n# [logging]\nLOG_LEVEL=debug\n")
+        );
+    }
+}
+
+mod interpolation {
+    use super::*;
+
+    fn write_layers(temp_dir: &TempDir, project: &str, local: &str) {
+        let dir = temp_dir.path().join(".envcli");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("project.env"), project).unwrap();
+        fs::write(dir.join("local.env"), local).unwrap();
+    }
+
+    fn get(temp_dir: &TempDir, args: &[&str]) -> assert_cmd::assert::Assert {
+        envcli(temp_dir).arg("get").args(args).assert()
+    }
+
+    #[test]
+    fn test_references_resolve_across_layers() {
+        let temp_dir = create_test_env();
+        write_layers(
+            &temp_dir,
+            "DB_USER=app\nDB_HOST=db.internal\nDB_PORT=5432\n",
+            "DB_HOST=localhost\nDATABASE_URL=postgres://${DB_USER}@${DB_HOST}:$DB_PORT/app\n",
+        );
+
+        get(&temp_dir, &["DATABASE_URL"])
+            .success()
+            .stdout(predicate::eq("postgres://app@localhost:5432/app\n"));
+
+        get(&temp_dir, &["DATABASE_URL", "--raw"])
+            .success()
+            .stdout(predicate::eq(
+                "postgres://${DB_USER}@${DB_HOST}:$DB_PORT/app\n",
+            ));
+    }
+
+    #[test]
+    fn test_default_error_and_escape_operators() {
+        let temp_dir = create_test_env();
+        write_layers(
+            &temp_dir,
+            "EMPTY=\n",
+            "WITH_DEFAULT=${MISSING_VAR_XYZ:-fallback}\n\
+             FROM_EMPTY=${EMPTY:-${WITH_DEFAULT}}\n\
+             LITERAL=\"cost \\$5\"\n\
+             REQUIRED=${MISSING_VAR_XYZ:?must be set}\n",
+        );
+
+        get(&temp_dir, &["WITH_DEFAULT"])
+            .success()
+            .stdout(predicate::eq("fallback\n"));
+        get(&temp_dir, &["FROM_EMPTY"])
+            .success()
+            .stdout(predicate::eq("fallback\n"));
+        get(&temp_dir, &["LITERAL"])
+            .success()
+            .stdout(predicate::eq("cost $5\n"));
+        get(&temp_dir, &["REQUIRED"])
+            .failure()
+            .stderr(predicate::str::contains("MISSING_VAR_XYZ: must be set"));
+    }
+
+    #[test]
+    fn test_set_value_with_dollar_round_trips() {
+        let temp_dir = create_test_env();
+        write_layers(&temp_dir, "HOME_DIR=/home/app\n", "");
+
+        for value in ["pa$word", "${HOME_DIR}", "a\\$b", "\\\\$"] {
+            envcli(&temp_dir)
+                .args(["set", "PW", value])
+                .assert()
+                .success();
+            get(&temp_dir, &["PW"])
+                .success()
+                .stdout(predicate::eq(format!("{}\n", value)));
+            get(&temp_dir, &["PW", "--raw"])
+                .success()
+                .stdout(predicate::eq(format!("{}\n", value)));
+        }
+
+        envcli(&temp_dir)
+            .args(["set", "PW", "pa$word"])
+            .assert()
+            .success();
+        assert!(
+            fs::read_to_string(temp_dir.path().join(".envcli/local.env"))
+                .unwrap()
+                .contains("PW=\"pa\\$word\"")
+        );
+        envcli(&temp_dir)
+            .args(["run", "--", "sh", "-c", "printf %s \"$PW\""])
+            .assert()
+            .success()
+            .stdout(predicate::eq("pa$word"));
+    }
+
+    #[test]
+    fn test_single_quoted_values_are_not_interpolated() {
+        let temp_dir = create_test_env();
+        write_layers(
+            &temp_dir,
+            "NAME=app\n",
+            "RAW='no $NAME here'\nTICK=`${NAME}`\nEXPANDED=\"${NAME} here\"\n",
+        );
+
+        get(&temp_dir, &["RAW"])
+            .success()
+            .stdout(predicate::eq("no $NAME here\n"));
+        get(&temp_dir, &["TICK"])
+            .success()
+            .stdout(predicate::eq("${NAME}\n"));
+        get(&temp_dir, &["EXPANDED"])
+            .success()
+            .stdout(predicate::eq("app here\n"));
+    }
+
+    #[test]
+    fn test_cycle_is_reported_with_chain() {
+        let temp_dir = create_test_env();
+        write_layers(&temp_dir, "A=${B}\n", "B=${C}\nC=${A}\n");
+
+        get(&temp_dir, &["A"])
+            .failure()
+            .stderr(predicate::str::contains("循环引用: A -> B -> C -> A"));
+    }
+
+    #[test]
+    fn test_self_reference_uses_lower_layer() {
+        let temp_dir = create_test_env();
+        write_layers(&temp_dir, "SEARCH=base\n", "SEARCH=${SEARCH}:extra\n");
+
+        get(&temp_dir, &["SEARCH"])
+            .success()
+            .stdout(predicate::eq("base:extra\n"));
+
+        envcli(&temp_dir)
+            .args(["export", "--source", "local"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("SEARCH=base:extra"));
+    }
+}
+
+mod secret_masking {
+    use super::*;
+
+    fn write_local(temp_dir: &TempDir, content: &str) {
+        let dir = temp_dir.path().join(".envcli");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("local.env"), content).unwrap();
+    }
+
+    #[test]
+    fn test_list_masks_secret_patterns_and_marked_keys() {
+        let temp_dir = create_test_env();
+        write_local(
+            &temp_dir,
+            "GITHUB_TOKEN=ghp_abcdefghijklmnop\n\
+             DB_PASSWORD=short\n\
+             INTERNAL_ID=abcdefghijkl # @secret\n\
+             APP_ENV=development\n",
+        );
+
+        envcli(&temp_dir)
+            .args(["list", "--source", "local"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("GITHUB_TOKEN=gh****op"))
+            .stdout(predicate::str::contains("DB_PASSWORD=****"))
+            .stdout(predicate::str::contains("INTERNAL_ID=ab****kl"))
+            .stdout(predicate::str::contains("APP_ENV=development"))
+            .stdout(predicate::str::contains("ghp_abcdefghijklmnop").not());
+
+        envcli(&temp_dir)
+            .args(["list", "--source", "local", "--reveal"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "GITHUB_TOKEN=ghp_abcdefghijklmnop",
+            ));
+    }
+
+    #[test]
+    fn test_json_output_marks_masked_entries() {
+        let temp_dir = create_test_env();
+        write_local(&temp_dir, "API_KEY=0123456789abcdef\nAPP_ENV=dev\n");
+
+        let output = envcli(&temp_dir)
+            .args(["list", "--source", "local", "--format", "json"])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+
+        let vars: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        let vars = vars.as_array().unwrap();
+        let api_key = vars.iter().find(|v| v["key"] == "API_KEY").unwrap();
+        let app_env = vars.iter().find(|v| v["key"] == "APP_ENV").unwrap();
+
+        assert_eq!(api_key["value"], "01****ef");
+        assert_eq!(api_key["masked"], true);
+        assert!(app_env.get("masked").is_none());
+    }
+
+    #[test]
+    fn test_export_outputs_real_secret_values_when_piped() {
+        let temp_dir = create_test_env();
+        write_local(&temp_dir, "API_KEY=0123456789abcdef\n");
+
+        let output = envcli(&temp_dir)
+            .args(["export", "--source", "local", "--format", "json"])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let vars: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(vars[0]["value"], "0123456789abcdef");
+        assert!(vars[0].get("masked").is_none());
+
+        envcli(&temp_dir)
+            .args(["export", "--source", "local", "--format", "bash"])
+            .assert()
+            .success()
+            .stdout("export API_KEY='0123456789abcdef'\n");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_export_masks_secrets_on_terminal() {
+        use std::io::Read;
+        use std::os::fd::{FromRawFd, OwnedFd};
+
+        let temp_dir = create_test_env();
+        write_local(&temp_dir, "API_KEY=0123456789abcdef\n");
+
+        let export_to_tty = |reveal: bool| {
+            let (mut master, mut slave) = (0, 0);
+            // SAFETY: openpty 只写入两个新打开的文件描述符
+            let rc = unsafe {
+                libc::openpty(
+                    &mut master,
+                    &mut slave,
+                    std::ptr::null_mut(),
+                    std::ptr::null_mut(),
+                    std::ptr::null_mut(),
+                )
+            };
+            assert_eq!(rc, 0);
+            // SAFETY: 两个描述符由 openpty 刚刚打开，此处取得所有权
+            let (master, slave) =
+                unsafe { (OwnedFd::from_raw_fd(master), OwnedFd::from_raw_fd(slave)) };
+
+            let mut cmd = envcli_process(&temp_dir);
+            cmd.args(["export", "--source", "local"]);
+            if reveal {
+                cmd.arg("--reveal");
+            }
+            assert!(cmd.stdout(slave).status().unwrap().success());
+            drop(cmd);
+
+            // 子进程退出且从端全部关闭后，读取主端以 EIO 结束
+            let mut output = Vec::new();
+            let _ = fs::File::from(master).read_to_end(&mut output);
+            String::from_utf8(output).unwrap()
+        };
+
+        assert!(export_to_tty(false).contains("API_KEY=\"01****ef\""));
+        assert!(export_to_tty(true).contains("API_KEY=0123456789abcdef"));
+    }
+
+    #[test]
+    fn test_verbose_set_masks_and_mark_secret_annotates() {
+        let temp_dir = create_test_env();
+
+        envcli(&temp_dir)
+            .args([
+                "set",
+                "SESSION_ID",
+                "s3cr3t-session-value",
+                "--mark-secret",
+                "-v",
+            ])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("SESSION_ID = s3****ue"));
+
+        let content = fs::read_to_string(temp_dir.path().join(".envcli/local.env")).unwrap();
+        assert_eq!(content, "SESSION_ID=s3cr3t-session-value # @secret\n");
+
+        envcli(&temp_dir)
+            .args(["get", "SESSION_ID"])
+            .assert()
+            .success()
+            .stdout(predicate::eq("s3cr3t-session-value\n"));
+    }
+}
+
+mod encryption {
+    use super::*;
+
+    /// 使用临时用户目录运行命令，避免写入真实的 ~/.envcli/keys
+    #[test]
+    fn test_set_secret_stores_envelope_and_get_decrypts() {
+        let temp_dir = create_test_env();
+
+        envcli(&temp_dir)
+            .args(["set", "DB_PASSWORD", "hunter2 with spaces", "--secret"])
+            .assert()
+            .success();
+
+        let content = fs::read_to_string(temp_dir.path().join(".envcli/local.env")).unwrap();
+        assert!(content.starts_with("DB_PASSWORD=\"ENC[v1,"));
+        assert!(!content.contains("hunter2"));
+        assert!(temp_dir.path().join("home/keys/current").exists());
+
+        envcli(&temp_dir)
+            .args(["get", "DB_PASSWORD"])
+            .assert()
+            .success()
+            .stdout(predicate::eq("hunter2 with spaces\n"));
+
+        envcli(&temp_dir)
+            .args(["get", "DB_PASSWORD", "--raw"])
+            .assert()
+            .success()
+            .stdout(predicate::str::starts_with("ENC[v1,"));
+    }
+
+    #[test]
+    fn test_export_keeps_envelope_unless_decrypt() {
+        let temp_dir = create_test_env();
+
+        envcli(&temp_dir)
+            .args(["set", "SIGNING_SEED", "plain-seed-value", "--secret"])
+            .assert()
+            .success();
+
+        envcli(&temp_dir)
+            .args(["export", "--source", "local"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("SIGNING_SEED=\"ENC[v1,"));
+
+        envcli(&temp_dir)
+            .args(["export", "--source", "local", "--decrypt"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("SIGNING_SEED=plain-seed-value"));
+    }
+
+    #[test]
+    fn test_key_rotate_reencrypts_envelopes() {
+        let temp_dir = create_test_env();
+
+        envcli(&temp_dir)
+            .args(["set", "API_TOKEN", "token-value", "--secret"])
+            .assert()
+            .success();
+        let local = temp_dir.path().join(".envcli/local.env");
+        let before = fs::read_to_string(&local).unwrap();
+
+        envcli(&temp_dir)
+            .args(["key", "rotate"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("已重新加密 1 个变量"));
+
+        let after = fs::read_to_string(&local).unwrap();
+        assert_ne!(before, after);
+        let keys = fs::read_dir(temp_dir.path().join("home/keys"))
+            .unwrap()
+            .filter(|e| e.as_ref().unwrap().path().extension() == Some("key".as_ref()))
+            .count();
+        assert_eq!(keys, 2);
+
+        envcli(&temp_dir)
+            .args(["get", "API_TOKEN"])
+            .assert()
+            .success()
+            .stdout(predicate::eq("token-value\n"));
+    }
+
+    #[test]
+    fn test_key_rotate_covers_every_profile() {
+        let temp_dir = create_test_env();
+
+        for profile in ["staging", "prod"] {
+            envcli(&temp_dir)
+                .args(["set", "API_TOKEN", profile, "--secret"])
+                .args(["--target", &format!("profile:{}", profile)])
+                .assert()
+                .success();
+        }
+        let profiles = temp_dir.path().join(".envcli/profiles");
+        let before = fs::read_to_string(profiles.join("prod.env")).unwrap();
+
+        envcli(&temp_dir)
+            .args(["key", "rotate", "--profile", "staging"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("已重新加密 2 个变量"));
+
+        assert_ne!(
+            fs::read_to_string(profiles.join("prod.env")).unwrap(),
+            before
+        );
+        envcli(&temp_dir)
+            .args(["get", "API_TOKEN", "--profile", "prod"])
+            .assert()
+            .success()
+            .stdout(predicate::eq("prod\n"));
+    }
+
+    #[test]
+    fn test_tampered_envelope_fails_to_decrypt() {
+        let temp_dir = create_test_env();
+
+        envcli(&temp_dir)
+            .args(["set", "API_TOKEN", "token-value", "--secret"])
+            .assert()
+            .success();
+
+        // 把密文挪到另一个变量名下，认证数据不匹配
+        let local = temp_dir.path().join(".envcli/local.env");
+        let content = fs::read_to_string(&local).unwrap();
+        fs::write(&local, content.replace("API_TOKEN=", "OTHER_TOKEN=")).unwrap();
+
+        envcli(&temp_dir)
+            .args(["get", "OTHER_TOKEN"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("解密失败"))
+            .stderr(predicate::str::contains(format!(
+                "密钥目录 {}",
+                temp_dir.path().join("home/keys").display()
+            )));
+    }
+
+    #[test]
+    fn test_envelope_key_id_must_be_hex() {
+        let temp_dir = create_project(&[(
+            "local.env",
+            "API_TOKEN=\"ENC[v1,../../outside,AAAAAAAAAAAAAAAA,AAAA]\"\n",
+        )]);
+        fs::write(temp_dir.path().join("outside.key"), "not a key").unwrap();
+
+        envcli(&temp_dir)
+            .args(["get", "API_TOKEN"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("无效的密钥 ID: ../../outside"));
+    }
+
+    #[test]
+    fn test_missing_key_only_affects_encrypted_values() {
+        let temp_dir = create_test_env();
+
+        envcli(&temp_dir)
+            .args(["set", "API_TOKEN", "token-value", "--secret"])
+            .assert()
+            .success();
+        envcli(&temp_dir)
+            .args(["set", "PLAIN", "hello"])
+            .assert()
+            .success();
+        for entry in fs::read_dir(temp_dir.path().join("home/keys")).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension() == Some("key".as_ref()) {
+                fs::remove_file(path).unwrap();
+            }
+        }
+
+        envcli(&temp_dir)
+            .args(["get", "PLAIN"])
+            .assert()
+            .success()
+            .stdout(predicate::eq("hello\n"));
+
+        envcli(&temp_dir)
+            .args(["list", "--format", "env"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("PLAIN=hello"))
+            .stdout(predicate::str::contains("API_TOKEN=ENC[v1,"))
+            .stderr(predicate::str::contains("找不到密钥"));
+
+        envcli(&temp_dir)
+            .args(["get", "API_TOKEN"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("找不到密钥"))
+            .stderr(predicate::str::contains(
+                temp_dir.path().join("home/keys").display().to_string(),
+            ));
+    }
+}
+
+mod profiles {
+    use super::*;
+
+    fn write_profiles(temp_dir: &TempDir) {
+        let dir = temp_dir.path().join(".envcli");
+        fs::create_dir_all(dir.join("profiles")).unwrap();
+        fs::write(dir.join("project.env"), "APP_ENV=dev\nLOG_LEVEL=debug\n").unwrap();
+        fs::write(
+            dir.join("profiles/base.env"),
+            "APP_ENV=deployed\nREPLICAS=2\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("profiles/staging.env"),
+            "extends = \"base\"\nLOG_LEVEL=info\n",
+        )
+        .unwrap();
+    }
+
+    fn envcli(temp_dir: &TempDir) -> Command {
+        let mut command = super::envcli(temp_dir);
+        command.env_remove("ENVCLI_PROFILE");
+        command
+    }
+
+    #[test]
+    fn test_profile_overrides_project_with_inheritance() {
+        let temp_dir = create_test_env();
+        write_profiles(&temp_dir);
+
+        for (key, expected) in [
+            ("APP_ENV", "deployed\n"),
+            ("REPLICAS", "2\n"),
+            ("LOG_LEVEL", "info\n"),
+        ] {
+            envcli(&temp_dir)
+                .args(["get", key, "--profile", "staging"])
+                .assert()
+                .success()
+                .stdout(predicate::eq(expected));
+        }
+
+        envcli(&temp_dir)
+            .args(["get", "APP_ENV"])
+            .env("ENVCLI_PROFILE", "staging")
+            .assert()
+            .success()
+            .stdout(predicate::eq("deployed\n"));
+
+        envcli(&temp_dir)
+            .args(["get", "APP_ENV"])
+            .assert()
+            .success()
+            .stdout(predicate::eq("dev\n"));
+
+        envcli(&temp_dir)
+            .args(["list", "--source", "profile:staging"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("REPLICAS=2"))
+            .stdout(predicate::str::contains("extends").not());
+    }
+
+    #[test]
+    fn test_local_still_wins_over_profile() {
+        let temp_dir = create_test_env();
+        write_profiles(&temp_dir);
+
+        envcli(&temp_dir)
+            .args(["set", "LOG_LEVEL", "trace"])
+            .assert()
+            .success();
+
+        envcli(&temp_dir)
+            .args(["get", "LOG_LEVEL", "--profile", "staging"])
+            .assert()
+            .success()
+            .stdout(predicate::eq("trace\n"));
+    }
+
+    #[test]
+    fn test_set_target_profile_writes_profile_file() {
+        let temp_dir = create_test_env();
+        write_profiles(&temp_dir);
+
+        envcli(&temp_dir)
+            .args(["set", "REPLICAS", "5", "--target", "profile:staging"])
+            .assert()
+            .success();
+
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join(".envcli/profiles/staging.env")).unwrap(),
+            "extends = \"base\"\nLOG_LEVEL=info\nREPLICAS=5\n"
+        );
+
+        envcli(&temp_dir)
+            .args(["set", "X", "1", "--target", "profile"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("未选择 profile"));
+    }
+
+    #[test]
+    fn test_status_and_doctor_report_profile() {
+        let temp_dir = create_test_env();
+        write_profiles(&temp_dir);
+
+        envcli(&temp_dir)
+            .args(["status", "--profile", "staging"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("当前 profile: staging -> base"));
+
+        fs::write(
+            temp_dir.path().join(".envcli/profiles/base.env"),
+            "extends=staging\n",
+        )
+        .unwrap();
+
+        envcli(&temp_dir)
+            .args(["doctor", "--profile", "staging"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "profile 继承存在循环: staging -> base -> staging",
+            ));
+    }
+}
+
+mod project_discovery {
+    use super::*;
+
+    fn envcli(temp_dir: &TempDir, cwd: &std::path::Path) -> Command {
+        let mut command = super::envcli(temp_dir);
+        command.current_dir(cwd).env_remove("ENVCLI_CASCADE");
+        command
+    }
+
+    #[test]
+    fn test_subdirectory_uses_nearest_project() {
+        let temp_dir = create_test_env();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join(".envcli")).unwrap();
+        fs::write(root.join(".envcli/project.env"), "APP_NAME=demo\n").unwrap();
+        let src = root.join("src/nested");
+        fs::create_dir_all(&src).unwrap();
+
+        envcli(&temp_dir, &src)
+            .args(["get", "APP_NAME"])
+            .assert()
+            .success()
+            .stdout(predicate::eq("demo\n"));
+
+        envcli(&temp_dir, &src)
+            .args(["set", "FROM_SUBDIR", "1"])
+            .assert()
+            .success();
+
+        assert!(!root.join("src/nested/.envcli").exists());
+        assert_eq!(
+            fs::read_to_string(root.join(".envcli/local.env")).unwrap(),
+            "FROM_SUBDIR=1\n"
+        );
+    }
+
+    #[test]
+    fn test_discovery_stops_at_git_boundary() {
+        let temp_dir = create_test_env();
+        let outer = temp_dir.path();
+        fs::create_dir_all(outer.join(".envcli")).unwrap();
+        fs::write(outer.join(".envcli/project.env"), "OUTER_ONLY=1\n").unwrap();
+        let repo = outer.join("repo");
+        fs::create_dir_all(repo.join(".git")).unwrap();
+        fs::create_dir_all(repo.join("src")).unwrap();
+
+        envcli(&temp_dir, &repo.join("src"))
+            .args(["get", "OUTER_ONLY"])
+            .assert()
+            .failure();
+
+        envcli(&temp_dir, &repo.join("src"))
+            .args(["set", "IN_REPO", "1", "--target", "project"])
+            .assert()
+            .success();
+
+        assert!(repo.join(".envcli/project.env").exists());
+        assert!(!repo.join("src/.envcli").exists());
+    }
+
+    #[test]
+    fn test_project_dir_flag_overrides_discovery() {
+        let temp_dir = create_test_env();
+        let app = temp_dir.path().join("app");
+        fs::create_dir_all(app.join(".envcli")).unwrap();
+        fs::write(app.join(".envcli/project.env"), "APP_NAME=app\n").unwrap();
+
+        envcli(&temp_dir, temp_dir.path())
+            .args(["-C", "app", "get", "APP_NAME"])
+            .assert()
+            .success()
+            .stdout(predicate::eq("app\n"));
+
+        envcli(&temp_dir, temp_dir.path())
+            .args(["--project-dir", "missing", "get", "APP_NAME"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("项目目录不存在"));
+    }
+
+    #[test]
+    fn test_cascade_merges_ancestor_projects() {
+        let temp_dir = create_test_env();
+        let outer = temp_dir.path();
+        fs::create_dir_all(outer.join(".git")).unwrap();
+        fs::create_dir_all(outer.join(".envcli")).unwrap();
+        fs::write(
+            outer.join(".envcli/project.env"),
+            "ORG=acme\nREGION=us-east-1\n",
+        )
+        .unwrap();
+        let package = outer.join("packages/api");
+        fs::create_dir_all(package.join(".envcli")).unwrap();
+        fs::write(package.join(".envcli/project.env"), "REGION=eu-west-1\n").unwrap();
+
+        envcli(&temp_dir, &package)
+            .args(["get", "ORG"])
+            .assert()
+            .failure();
+
+        envcli(&temp_dir, &package)
+            .args(["--cascade", "get", "ORG"])
+            .assert()
+            .success()
+            .stdout(predicate::eq("acme\n"));
+
+        envcli(&temp_dir, &package)
+            .args(["get", "REGION"])
+            .env("ENVCLI_CASCADE", "true")
+            .assert()
+            .success()
+            .stdout(predicate::eq("eu-west-1\n"));
+    }
+}
+
+mod user_dir {
+    use super::*;
+
+    /// 以临时目录作为主目录运行命令
+    fn envcli(temp_dir: &TempDir) -> Command {
+        let home = temp_dir.path().join("home");
+        let project = temp_dir.path().join("project");
+        fs::create_dir_all(&home).unwrap();
+        fs::create_dir_all(&project).unwrap();
+
+        let mut command = Command::new(get_env_command());
+        command
+            .current_dir(project)
+            .env("HOME", &home)
+            .env("USERPROFILE", &home)
+            .env_remove("ENVCLI_HOME")
+            .env_remove("ENVCLI_XDG")
+            .env_remove("XDG_CONFIG_HOME");
+        command
+    }
+
+    #[test]
+    fn test_envcli_home_overrides_user_dir() {
+        let temp_dir = create_test_env();
+        let custom = temp_dir.path().join("custom");
+
+        envcli(&temp_dir)
+            .env("ENVCLI_HOME", &custom)
+            .args(["set", "EDITOR", "vim", "--target", "user"])
+            .assert()
+            .success();
+
+        assert_eq!(
+            fs::read_to_string(custom.join("user.env")).unwrap(),
+            "EDITOR=vim\n"
+        );
+        assert!(!temp_dir.path().join("home/.envcli").exists());
+
+        envcli(&temp_dir)
+            .env("ENVCLI_HOME", &custom)
+            .args(["status"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(custom.display().to_string()));
+    }
+
+    #[test]
+    fn test_xdg_migrates_legacy_dir() {
+        let temp_dir = create_test_env();
+        let legacy = temp_dir.path().join("home/.envcli");
+        fs::create_dir_all(legacy.join("keys")).unwrap();
+        fs::write(legacy.join("user.env"), "EDITOR=vim\n").unwrap();
+        let xdg = temp_dir.path().join("xdg");
+
+        envcli(&temp_dir)
+            .env("ENVCLI_XDG", "1")
+            .env("XDG_CONFIG_HOME", &xdg)
+            .args(["get", "EDITOR"])
+            .assert()
+            .success()
+            .stdout(predicate::eq("vim\n"))
+            .stderr(predicate::str::contains("已将配置目录从"));
+
+        assert!(!legacy.exists());
+        assert!(xdg.join("envcli/user.env").exists());
+        assert!(xdg.join("envcli/keys").is_dir());
+
+        // 迁移后无需 ENVCLI_XDG 也会使用已存在的 XDG 目录
+        envcli(&temp_dir)
+            .env("XDG_CONFIG_HOME", &xdg)
+            .args(["get", "EDITOR"])
+            .assert()
+            .success()
+            .stdout(predicate::eq("vim\n"))
+            .stderr(predicate::str::is_empty());
+    }
+
+    #[test]
+    fn test_envcli_home_never_migrates_legacy_dir() {
+        let temp_dir = create_test_env();
+        let legacy = temp_dir.path().join("home/.envcli");
+        fs::create_dir_all(&legacy).unwrap();
+        fs::write(legacy.join("user.env"), "EDITOR=vim\n").unwrap();
+        let custom = temp_dir.path().join("custom");
+
+        envcli(&temp_dir)
+            .env("ENVCLI_HOME", &custom)
+            .env("ENVCLI_XDG", "1")
+            .args(["get", "EDITOR"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("已将配置目录从").not());
+
+        assert_eq!(
+            fs::read_to_string(legacy.join("user.env")).unwrap(),
+            "EDITOR=vim\n"
+        );
+        assert!(!custom.join("user.env").exists());
+    }
+
+    #[test]
+    fn test_default_user_dir_is_legacy_home() {
+        let temp_dir = create_test_env();
+
+        envcli(&temp_dir)
+            .args(["set", "EDITOR", "nano", "--target", "user"])
+            .assert()
+            .success();
+
+        assert!(temp_dir.path().join("home/.envcli/user.env").exists());
+    }
+}
+
+mod config_file {
+    use super::*;
+
+    /// 使用临时用户目录运行命令
+    fn write_user_config(temp_dir: &TempDir, content: &str) {
+        let home = temp_dir.path().join("home");
+        fs::create_dir_all(&home).unwrap();
+        fs::write(home.join("config.toml"), content).unwrap();
+    }
+
+    #[test]
+    fn test_validate_reports_unknown_keys_and_type_errors() {
+        let temp_dir = create_test_env();
+        write_user_config(
+            &temp_dir,
+            "default_target = \"local\"\ncolour = \"auto\"\n\n[cache]\nenabled = \"yes\"\nttl_seconds = 30\n",
+        );
+
+        envcli(&temp_dir)
+            .args(["config", "validate"])
+            .assert()
+            .failure()
+            .stdout(predicate::str::contains(
+                "config.toml:2: 未知配置项: colour",
+            ))
+            .stdout(predicate::str::contains(
+                "config.toml:5: cache.enabled: 应为布尔值",
+            ))
+            .stderr(predicate::str::contains("2 个问题"));
+
+        // 配置有误时其他命令报错并指向问题所在行
+        envcli(&temp_dir)
+            .args(["list"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("config.toml:2"));
+    }
+
+    #[test]
+    fn test_config_set_get_list_with_project_override() {
+        let temp_dir = create_test_env();
+
+        envcli(&temp_dir)
+            .args(["config", "set", "default_format", "json"])
+            .assert()
+            .success();
+        envcli(&temp_dir)
+            .args(["config", "set", "cache.ttl_seconds", "30"])
+            .assert()
+            .success();
+        envcli(&temp_dir)
+            .args(["config", "set", "default_format", "env", "--project"])
+            .assert()
+            .success();
+
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("home/config.toml")).unwrap(),
+            "default_format = \"json\"\n\n[cache]\nttl_seconds = 30\n"
+        );
+
+        envcli(&temp_dir)
+            .args(["config", "get", "default_format"])
+            .assert()
+            .success()
+            .stdout(predicate::eq("env\n"));
+
+        envcli(&temp_dir)
+            .args(["config", "list"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("default_format = env (project)"))
+            .stdout(predicate::str::contains("cache.ttl_seconds = 30 (user)"))
+            .stdout(predicate::str::contains("cache.enabled = true (默认)"));
+
+        envcli(&temp_dir)
+            .args(["config", "set", "cache.enabled", "maybe"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("应为布尔值"));
+    }
+
+    #[test]
+    fn test_settings_drive_defaults() {
+        let temp_dir = create_test_env();
+        write_user_config(
+            &temp_dir,
+            "default_target = \"project\"\ndefault_format = \"json\"\nsecret_patterns = [\"*_DSN\"]\n",
+        );
+
+        envcli(&temp_dir)
+            .args(["set", "SENTRY_DSN", "https://key@sentry.example/1"])
+            .assert()
+            .success();
+        assert!(temp_dir.path().join(".envcli/project.env").exists());
+        assert!(!temp_dir.path().join(".envcli/local.env").exists());
+
+        envcli(&temp_dir)
+            .args(["list", "--source", "project"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("\"key\": \"SENTRY_DSN\""))
+            .stdout(predicate::str::contains("\"masked\": true"));
+    }
+
+    #[test]
+    fn test_layer_order_changes_precedence() {
+        let temp_dir = create_test_env();
+        let dir = temp_dir.path().join(".envcli");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("project.env"), "MODE=project\n").unwrap();
+        fs::write(dir.join("local.env"), "MODE=local\n").unwrap();
+
+        envcli(&temp_dir)
+            .args(["get", "MODE"])
+            .assert()
+            .success()
+            .stdout(predicate::eq("local\n"));
+
+        fs::write(
+            dir.join("config.toml"),
+            "layer_order = [\"system\", \"user\", \"local\", \"profile\", \"project\"]\n",
+        )
+        .unwrap();
+
+        envcli(&temp_dir)
+            .args(["get", "MODE"])
+            .assert()
+            .success()
+            .stdout(predicate::eq("project\n"));
+    }
+}
+
+#[cfg(unix)]
+mod shell_profile {
+    use super::*;
+
+    /// 以临时目录作为主目录运行命令
+    fn envcli(temp_dir: &TempDir) -> Command {
+        let mut command = super::envcli(temp_dir);
+        command
+            .env("HOME", temp_dir.path())
+            .env_remove("ZDOTDIR")
+            .env_remove("XDG_CONFIG_HOME");
+        command
+    }
+
+    #[test]
+    fn test_system_set_keeps_existing_rc_content() {
+        let temp_dir = create_test_env();
+        let bashrc = temp_dir.path().join(".bashrc");
+        fs::write(&bashrc, "alias ll='ls -l'\n").unwrap();
+
+        envcli(&temp_dir)
+            .args(["system-set", "GREETING", "it's $HOME", "--shell", "bash"])
+            .assert()
+            .success();
+        envcli(&temp_dir)
+            .args(["system-set", "EDITOR", "vim", "--shell", "bash"])
+            .assert()
+            .success();
+        envcli(&temp_dir)
+            .args(["system-set", "EDITOR", "nvim", "--shell", "bash"])
+            .assert()
+            .success();
+
+        assert_eq!(
+            fs::read_to_string(&bashrc).unwrap(),
+            "alias ll='ls -l'\n\n# >>> envcli >>>\nexport GREETING='it'\\''s $HOME'\nexport EDITOR='nvim'\n# <<< envcli <<<\n"
+        );
+        assert!(temp_dir.path().join(".bashrc.envcli.bak").exists());
+    }
+
+    #[test]
+    fn test_system_unset_removes_key_and_empty_block() {
+        let temp_dir = create_test_env();
+        let bashrc = temp_dir.path().join(".bashrc");
+        fs::write(&bashrc, "alias ll='ls -l'\n").unwrap();
+
+        envcli(&temp_dir)
+            .args(["system-set", "EDITOR", "vim", "--shell", "bash"])
+            .assert()
+            .success();
+        envcli(&temp_dir)
+            .args(["system-unset", "EDITOR", "--shell", "bash"])
+            .assert()
+            .success();
+
+        assert_eq!(fs::read_to_string(&bashrc).unwrap(), "alias ll='ls -l'\n");
+
+        envcli(&temp_dir)
+            .args(["system-unset", "EDITOR", "--shell", "bash"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("变量未找到"));
+    }
+
+    #[test]
+    fn test_shell_specific_files_and_quoting() {
+        let temp_dir = create_test_env();
+
+        envcli(&temp_dir)
+            .args(["system-set", "MOTD", "it's \\ here", "--shell", "fish"])
+            .assert()
+            .success();
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join(".config/fish/conf.d/envcli.fish")).unwrap(),
+            "# >>> envcli >>>\nset -gx MOTD 'it\\'s \\\\ here'\n# <<< envcli <<<\n"
+        );
+
+        envcli(&temp_dir)
+            .env("SHELL", "/usr/bin/zsh")
+            .args(["system-set", "EDITOR", "vim"])
+            .assert()
+            .success();
+        assert!(temp_dir.path().join(".zshrc").exists());
+
+        envcli(&temp_dir)
+            .args(["system-set", "EDITOR", "vim", "--shell", "sh"])
+            .assert()
+            .success();
+        assert!(temp_dir.path().join(".profile").exists());
+
+        envcli(&temp_dir)
+            .args(["system-set", "BAD-NAME", "x", "--shell", "bash"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("无效的变量名"));
+    }
+}
+
+// ==================== 机器级与会话级变量测试 ====================
+
+#[cfg(target_os = "linux")]
+mod system_scopes {
+    use super::*;
+
+    /// 以临时目录作为主目录与文件系统根目录运行命令
+    fn envcli(temp_dir: &TempDir) -> Command {
+        let mut command = super::envcli(temp_dir);
+        command
+            .env("HOME", temp_dir.path())
+            .env_remove("XDG_CONFIG_HOME");
+        command
+    }
+
+    #[test]
+    fn test_machine_scope_writes_etc_environment() {
+        let temp_dir = create_test_env();
+        let root = temp_dir.path().to_str().unwrap();
+        let file = temp_dir.path().join("etc/environment");
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(&file, "PATH=\"/usr/bin:/bin\"\n").unwrap();
+
+        envcli(&temp_dir)
+            .args(["system-set", "LANG", "C.UTF-8", "--scope", "machine"])
+            .args(["--root", root])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("重新登录后生效"));
+        assert_eq!(
+            fs::read_to_string(&file).unwrap(),
+            "PATH=\"/usr/bin:/bin\"\n\n# >>> envcli >>>\nLANG=\"C.UTF-8\"\n# <<< envcli <<<\n"
+        );
+
+        envcli(&temp_dir)
+            .args(["system-set", "QUOTED", "say \"hi\"", "--scope", "machine"])
+            .args(["--root", root])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("不能包含双引号"));
+
+        envcli(&temp_dir)
+            .args(["system-unset", "LANG", "--scope", "machine"])
+            .args(["--root", root])
+            .assert()
+            .success();
+        assert_eq!(
+            fs::read_to_string(&file).unwrap(),
+            "PATH=\"/usr/bin:/bin\"\n"
+        );
+    }
+
+    #[test]
+    fn test_session_scope_writes_environment_d() {
+        let temp_dir = create_test_env();
+
+        envcli(&temp_dir)
+            .args(["system-set", "PRICE", "$5 \"net\"", "--scope", "session"])
+            .assert()
+            .success();
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join(".config/environment.d/50-envcli.conf"))
+                .unwrap(),
+            "# >>> envcli >>>\nPRICE=\"\\$5 \\\"net\\\"\"\n# <<< envcli <<<\n"
+        );
+    }
+
+    #[test]
+    fn test_dry_run_prints_diff_without_writing() {
+        let temp_dir = create_test_env();
+        let root = temp_dir.path().to_str().unwrap();
+        let file = temp_dir.path().join("etc/environment");
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(&file, "PATH=\"/usr/bin\"\n").unwrap();
+
+        envcli(&temp_dir)
+            .args(["system-set", "LANG", "C.UTF-8", "--scope", "machine"])
+            .args(["--root", root, "--dry-run"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(format!("--- {}", file.display())))
+            .stdout(predicate::str::contains("+LANG=\"C.UTF-8\""))
+            .stdout(predicate::str::contains(" PATH").not())
+            .stdout(predicate::str::contains("✓").not());
+        assert_eq!(fs::read_to_string(&file).unwrap(), "PATH=\"/usr/bin\"\n");
+        assert!(!temp_dir.path().join("etc/environment.envcli.bak").exists());
+    }
+
+    #[test]
+    fn test_machine_scope_permission_denied_suggests_sudo() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = create_test_env();
+        let file = temp_dir.path().join("etc/environment");
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(&file, "").unwrap();
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o444)).unwrap();
+
+        // root 不受文件权限限制
+        if fs::OpenOptions::new().append(true).open(&file).is_ok() {
+            return;
+        }
+
+        envcli(&temp_dir)
+            .args(["system-set", "LANG", "C.UTF-8", "--scope", "machine"])
+            .args(["--root", temp_dir.path().to_str().unwrap()])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("sudo"))
+            .stderr(predicate::str::contains("--dry-run"));
+    }
+
+    #[test]
+    fn test_system_list_shows_scope_and_file() {
+        let temp_dir = create_test_env();
+        let root = temp_dir.path().to_str().unwrap();
+
+        envcli(&temp_dir)
+            .args(["system-set", "EDITOR", "vim", "--shell", "bash"])
+            .assert()
+            .success();
+        envcli(&temp_dir)
+            .args([
+                "system-set",
+                "GITHUB_TOKEN",
+                "ghp_1234567890",
+                "--shell",
+                "fish",
+            ])
+            .assert()
+            .success();
+        envcli(&temp_dir)
+            .args(["system-set", "LANG", "C.UTF-8", "--scope", "machine"])
+            .args(["--root", root])
+            .assert()
+            .success();
+        envcli(&temp_dir)
+            .args(["system-set", "PRICE", "$5", "--scope", "session"])
+            .assert()
+            .success();
+
+        let bashrc = temp_dir.path().join(".bashrc");
+        let etc = temp_dir.path().join("etc/environment");
+        envcli(&temp_dir)
+            .args(["system", "list", "--root", root])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(format!(
+                "global   EDITOR=vim  ({})",
+                bashrc.display()
+            )))
+            .stdout(predicate::str::contains("GITHUB_TOKEN=gh****90"))
+            .stdout(predicate::str::contains(format!(
+                "machine  LANG=C.UTF-8  ({})",
+                etc.display()
+            )))
+            .stdout(predicate::str::contains("session  PRICE=$5"));
+
+        envcli(&temp_dir)
+            .args(["system", "list", "--scope", "machine", "--root", root])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("LANG=C.UTF-8"))
+            .stdout(predicate::str::contains("EDITOR").not());
+    }
+
+    #[test]
+    fn test_system_diff_reports_keys_not_yet_live() {
+        let temp_dir = create_test_env();
+
+        envcli(&temp_dir)
+            .args(["system-set", "EDITOR", "vim", "--shell", "bash"])
+            .assert()
+            .success();
+        envcli(&temp_dir)
+            .args(["system-set", "PAGER", "less", "--shell", "bash"])
+            .assert()
+            .success();
+        envcli(&temp_dir)
+            .args(["system-set", "VISUAL", "code", "--shell", "bash"])
+            .assert()
+            .success();
+
+        envcli(&temp_dir)
+            .env("EDITOR", "vim")
+            .env("PAGER", "more")
+            .env_remove("VISUAL")
+            .args(["system", "diff", "--scope", "global"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("EDITOR").not())
+            .stdout(predicate::str::contains(
+                "~ PAGER [global]: 当前 'more'，已持久化 'less'",
+            ))
+            .stdout(predicate::str::contains(
+                "+ VISUAL [global]: 已持久化 'code'，当前进程不可见",
+            ))
+            .stdout(predicate::str::contains("2 个变量尚未生效"));
+
+        envcli(&temp_dir)
+            .env("EDITOR", "vim")
+            .env("PAGER", "less")
+            .env("VISUAL", "code")
+            .args(["system", "diff", "--scope", "global"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("3 个持久化变量均已生效"));
+    }
+}
+
+// ==================== shell 钩子测试 ====================
+
+#[cfg(unix)]
+mod shell_hook {
+    use super::*;
+
+    /// 创建含 project/local 变量的项目目录与项目外目录
+    fn setup() -> TempDir {
+        let temp_dir = create_test_env();
+        let project = temp_dir.path().join("proj/.envcli");
+        fs::create_dir_all(&project).unwrap();
+        fs::create_dir_all(temp_dir.path().join("other")).unwrap();
+        fs::create_dir_all(temp_dir.path().join("home")).unwrap();
+        fs::write(project.join("project.env"), "API_URL=http://localhost\n").unwrap();
+        fs::write(project.join("local.env"), "EDITOR=nano\n").unwrap();
+        temp_dir
+    }
+
+    /// 在 bash 中加载钩子并运行脚本
+    fn run_bash(temp_dir: &TempDir, script: &str) -> std::process::Output {
+        let home = temp_dir.path().join("home");
+        let exe = get_env_command();
+        std::process::Command::new("bash")
+            .current_dir(temp_dir.path())
+            .env("ENVCLI_HOME", &home)
+            .env("HOME", &home)
+            .env("EDITOR", "vim")
+            .env_remove("API_URL")
+            .env_remove("ENVCLI_HOOK_STATE")
+            .arg("-c")
+            .arg(format!(
+                "eval \"$('{}' hook bash)\"\n{}",
+                exe.display(),
+                script
+            ))
+            .output()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_hook_requires_allow() {
+        let temp_dir = setup();
+        let output = run_bash(
+            &temp_dir,
+            "cd proj && _envcli_hook && echo \"API=${API_URL-unset}\"",
+        );
+
+        assert!(String::from_utf8_lossy(&output.stdout).contains("API=unset"));
+        assert!(String::from_utf8_lossy(&output.stderr).contains("envcli allow"));
+    }
+
+    #[test]
+    fn test_hook_loads_and_restores_on_cd() {
+        let temp_dir = setup();
+        envcli(&temp_dir)
+            .current_dir(temp_dir.path().join("proj"))
+            .arg("allow")
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("已信任"));
+
+        let output = run_bash(
+            &temp_dir,
+            r#"cd proj && _envcli_hook
+echo "in: API=$API_URL EDITOR=$EDITOR"
+echo 'API_URL=http://changed' > .envcli/project.env
+_envcli_hook
+echo "reload: API=$API_URL"
+cd ../other && _envcli_hook
+echo "out: API=${API_URL-unset} EDITOR=$EDITOR STATE=${ENVCLI_HOOK_STATE-unset}""#,
+        );
+        let stdout = String::from_utf8_lossy(&output.stdout);
+
+        assert!(
+            stdout.contains("in: API=http://localhost EDITOR=nano"),
+            "{}",
+            stdout
+        );
+        assert!(stdout.contains("reload: API=http://changed"), "{}", stdout);
+        assert!(
+            stdout.contains("out: API=unset EDITOR=vim STATE=unset"),
+            "{}",
+            stdout
+        );
+    }
+
+    #[test]
+    fn test_hook_reload_does_not_repeat_self_reference() {
+        let temp_dir = setup();
+        fs::write(
+            temp_dir.path().join("proj/.envcli/project.env"),
+            "GREETING=${GREETING}-proj\n",
+        )
+        .unwrap();
+        envcli(&temp_dir)
+            .current_dir(temp_dir.path().join("proj"))
+            .arg("allow")
+            .assert()
+            .success();
+
+        let output = run_bash(
+            &temp_dir,
+            r#"export GREETING=hi
+cd proj && _envcli_hook
+echo "in: $GREETING"
+echo 'OTHER=1' >> .envcli/project.env
+_envcli_hook
+echo "reload: $GREETING"
+cd ../other && _envcli_hook
+echo "out: $GREETING""#,
+        );
+        let stdout = String::from_utf8_lossy(&output.stdout);
+
+        assert!(stdout.contains("in: hi-proj\n"), "{}", stdout);
+        assert!(stdout.contains("reload: hi-proj\n"), "{}", stdout);
+        assert!(stdout.contains("out: hi\n"), "{}", stdout);
+    }
+
+    #[test]
+    fn test_deny_stops_loading() {
+        let temp_dir = setup();
+        let envcli = |arg: &str| {
+            envcli(&temp_dir)
+                .current_dir(temp_dir.path().join("proj"))
+                .arg(arg)
+                .assert()
+                .success();
+        };
+        envcli("allow");
+        envcli("deny");
+
+        let output = run_bash(
+            &temp_dir,
+            "cd proj && _envcli_hook && echo \"API=${API_URL-unset}\"",
+        );
+        assert!(String::from_utf8_lossy(&output.stdout).contains("API=unset"));
+    }
+
+    #[test]
+    fn test_hook_rejects_unsupported_shell() {
+        envcli(&create_test_env())
+            .args(["hook", "tcsh"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("不支持的 shell"));
+    }
+}
+
+// ==================== 子 shell 测试 ====================
+
+#[cfg(unix)]
+mod subshell {
+    use super::*;
+
+    fn envcli(temp_dir: &TempDir) -> Command {
+        let mut command = super::envcli(temp_dir);
+        command.env("SHELL", "bash").env_remove("ENVCLI_ACTIVE");
+        command
+    }
+
+    #[test]
+    fn test_shell_loads_variables_and_prompt() {
+        let temp_dir = create_project(&[("project.env", "API_URL=http://localhost\n")]);
+        envcli(&temp_dir)
+            .arg("shell")
+            .write_stdin("echo \"API=$API_URL ACTIVE=$ENVCLI_ACTIVE\"\nexit 3\n")
+            .assert()
+            .code(3)
+            .stdout(predicate::str::contains("API=http://localhost ACTIVE="))
+            .stderr(predicate::str::contains("(envcli:"));
+    }
+
+    #[test]
+    fn test_shell_uses_profile_in_prompt() {
+        let temp_dir = create_project(&[("project.env", "API_URL=http://localhost\n")]);
+        let profiles = temp_dir.path().join(".envcli/profiles");
+        fs::create_dir_all(&profiles).unwrap();
+        fs::write(profiles.join("dev.env"), "API_URL=http://dev\n").unwrap();
+
+        envcli(&temp_dir)
+            .args(["shell", "--profile", "dev"])
+            .write_stdin("echo \"API=$API_URL\"\n")
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("API=http://dev"))
+            .stderr(predicate::str::contains("/dev) "));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_shell_init_files_are_private_and_removed() {
+        let temp_dir = create_project(&[("project.env", "API_URL=http://localhost\n")]);
+        let home = temp_dir.path().join("home");
+        fs::create_dir_all(&home).unwrap();
+        // 由 envcli 的 rcfile 加载，BASH_SOURCE[1] 即 rcfile 路径
+        fs::write(
+            home.join(".bashrc"),
+            "rc=\"${BASH_SOURCE[1]}\"\necho \"RC=$rc\"\nstat -c 'MODE=%a' \"${rc%/*}\" \"$rc\"\n",
+        )
+        .unwrap();
+
+        let output = envcli(&temp_dir)
+            .arg("shell")
+            .write_stdin("exit\n")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains("MODE=700\nMODE=600\n"), "{}", stdout);
+
+        let rc = stdout
+            .lines()
+            .find_map(|line| line.strip_prefix("RC="))
+            .unwrap();
+        assert!(!std::path::Path::new(rc).parent().unwrap().exists());
+    }
+
+    #[test]
+    fn test_shell_refuses_to_nest_without_force() {
+        let temp_dir = create_project(&[("project.env", "API_URL=http://localhost\n")]);
+        envcli(&temp_dir)
+            .env("ENVCLI_ACTIVE", "/somewhere")
+            .arg("shell")
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("--force"));
+
+        envcli(&temp_dir)
+            .env("ENVCLI_ACTIVE", "/somewhere")
+            .args(["shell", "--force"])
+            .write_stdin("echo \"ACTIVE=$ENVCLI_ACTIVE\"\n")
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("ACTIVE=/somewhere").not());
+    }
+}
+
+// ==================== shell 导出格式测试 ====================
+
+mod shell_export {
+    use super::*;
+
+    #[test]
+    fn test_export_shell_formats_quote_values() {
+        let temp_dir = create_project(&[("project.env", "GREETING=\"it's\"\nPLAIN=a b\n")]);
+        fs::write(
+            temp_dir.path().join(".envcli/project.env"),
+            "GREETING=\"it's $5 \\\"ok\\\"\"\n",
+        )
+        .unwrap();
+
+        let cases = [
+            ("bash", "export GREETING='it'\\''s $5 \"ok\"'"),
+            ("zsh", "export GREETING='it'\\''s $5 \"ok\"'"),
+            ("fish", "set -gx GREETING 'it\\'s $5 \"ok\"'"),
+            ("powershell", "$env:GREETING = 'it''s $5 \"ok\"'"),
+            ("nu", "$env.GREETING = \"it's $5 \\\"ok\\\"\""),
+        ];
+        for (format, expected) in cases {
+            envcli(&temp_dir)
+                .args(["export", "--source", "project", "--format", format])
+                .assert()
+                .success()
+                .stdout(predicate::str::contains(expected));
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_export_bash_round_trips_through_eval() {
+        let temp_dir = create_test_env();
+        fs::create_dir_all(temp_dir.path().join(".envcli")).unwrap();
+        fs::write(
+            temp_dir.path().join(".envcli/project.env"),
+            "TRICKY=\"a 'b' \\\"c\\\" $d `e` \\\\f\"\n",
+        )
+        .unwrap();
+
+        let output = std::process::Command::new("bash")
+            .current_dir(temp_dir.path())
+            .env("ENVCLI_HOME", temp_dir.path().join("home"))
+            .env("HOME", temp_dir.path().join("home"))
+            .arg("-c")
+            .arg(format!(
+                "eval \"$('{}' export --format bash --raw)\"; printf '%s' \"$TRICKY\"",
+                get_env_command().display()
+            ))
+            .output()
+            .unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "a 'b' \"c\" $d `e` \\f"
+        );
+    }
+
+    #[test]
+    fn test_export_unset_prints_unset_statements() {
+        let temp_dir = create_project(&[("project.env", "GREETING=\"it's\"\nPLAIN=a b\n")]);
+
+        envcli(&temp_dir)
+            .args([
+                "export", "--source", "project", "--format", "bash", "--unset",
+            ])
+            .assert()
+            .success()
+            .stdout("unset GREETING\nunset PLAIN\n");
+
+        envcli(&temp_dir)
+            .args(["export", "--format", "fish", "--unset"])
+            .assert()
+            .success()
+            .stdout("set -e GREETING\nset -e PLAIN\n");
+
+        envcli(&temp_dir)
+            .args(["export", "--format", "powershell", "--unset"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Remove-Item Env:GREETING -ErrorAction SilentlyContinue",
+            ));
+
+        envcli(&temp_dir)
+            .args(["export", "--unset"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("--unset 需要 shell 格式"));
+    }
+
+    #[test]
+    fn test_export_rejects_unknown_format() {
+        let temp_dir = create_project(&[("project.env", "GREETING=\"it's\"\nPLAIN=a b\n")]);
+        envcli(&temp_dir)
+            .args(["export", "--format", "tcsh"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("无效的输出格式"));
+    }
+}
+
+mod formats {
+    use super::*;
+
+    const FILES: &[(&str, &str)] = &[(
+        "project.env",
+        "GREETING=\"say \\\"hi\\\" = ok: #1\"\nNAME=ünï\nPLAIN=a b\nPRICE=\"\\$5\"\nRAW='a$b'\n",
+    )];
+
+    fn local_vars(temp_dir: &TempDir) -> String {
+        let output = envcli(temp_dir)
+            .args(["list", "--source", "local"])
+            .output()
+            .unwrap();
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    #[test]
+    fn test_export_import_round_trip_for_each_format() {
+        let temp_dir = create_project(FILES);
+
+        for (format, file) in [
+            ("json", "vars.json"),
+            ("json-flat", "flat.json"),
+            ("yaml", "vars.yaml"),
+            ("toml", "vars.toml"),
+            ("properties", "vars.properties"),
+            ("env", "vars.env"),
+        ] {
+            let output = envcli(&temp_dir)
+                .args(["export", "--source", "project", "--format", format])
+                .output()
+                .unwrap();
+            assert!(output.status.success());
+            fs::write(temp_dir.path().join(file), &output.stdout).unwrap();
+
+            let _ = fs::remove_file(temp_dir.path().join(".envcli/local.env"));
+            envcli(&temp_dir).args(["import", file]).assert().success();
+
+            assert_eq!(
+                local_vars(&temp_dir),
+                "GREETING=say \"hi\" = ok: #1\nNAME=ünï\nPLAIN=a b\nPRICE=$5\nRAW=a$b\n",
+                "format {}",
+                format
+            );
+        }
+    }
+
+    #[test]
+    fn test_import_detects_format_from_content() {
+        let temp_dir = create_project(FILES);
+
+        let cases = [
+            ("{\"DEBUG\": true, \"PORT\": 8080}\n", "json"),
+            ("---\nDEBUG: 'true'\nPORT: 8080 # 注释\n", "yaml"),
+            ("DEBUG = true\nPORT = 8080\n", "toml"),
+            ("DEBUG=true\nPORT=8080\n", "env"),
+        ];
+        for (content, format) in cases {
+            fs::write(temp_dir.path().join("vars.txt"), content).unwrap();
+            let _ = fs::remove_file(temp_dir.path().join(".envcli/local.env"));
+
+            envcli(&temp_dir)
+                .args(["-v", "import", "vars.txt"])
+                .assert()
+                .success()
+                .stdout(predicate::str::contains(format!("（{}）", format)));
+            assert_eq!(local_vars(&temp_dir), "DEBUG=true\nPORT=8080\n");
+        }
+    }
+
+    #[test]
+    fn test_import_format_flag_overrides_detection() {
+        let temp_dir = create_project(FILES);
+        fs::write(
+            temp_dir.path().join("app.conf"),
+            "# Java\napp_name : demo\npath=C\\:\\\\tmp\\u0021\n",
+        )
+        .unwrap();
+
+        envcli(&temp_dir)
+            .args(["import", "app.conf", "--format", "properties"])
+            .assert()
+            .success();
+        assert_eq!(local_vars(&temp_dir), "app_name=demo\npath=C:\\tmp!\n");
+    }
+
+    #[test]
+    fn test_import_rejects_unsupported_content() {
+        let temp_dir = create_project(FILES);
+
+        fs::write(temp_dir.path().join("nested.yaml"), "DB:\n  HOST: x\n").unwrap();
+        envcli(&temp_dir)
+            .args(["import", "nested.yaml"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("不支持嵌套"));
+
+        fs::write(temp_dir.path().join("table.toml"), "[db]\nhost = \"x\"\n").unwrap();
+        envcli(&temp_dir)
+            .args(["import", "table.toml"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("仅支持扁平的键值对"));
+
+        fs::write(temp_dir.path().join("app.properties"), "app.name=demo\n").unwrap();
+        envcli(&temp_dir)
+            .args(["import", "app.properties"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("无效的变量名 'app.name'"));
+
+        fs::write(temp_dir.path().join("vars.sh"), "export A=1\n").unwrap();
+        envcli(&temp_dir)
+            .args(["import", "vars.sh", "--format", "bash"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("bash 格式仅支持导出"));
+
+        assert!(!temp_dir.path().join(".envcli/local.env").exists());
+    }
+}
+
+mod import_strategies {
+    use super::*;
+
+    /// local.env 为 A=1、B=2、C=3，待导入的 update.env 为 A=1、B=20、D=4
+    fn create_with_update() -> TempDir {
+        let temp_dir = create_project(&[("local.env", "A=1\nB=2\nC=3\n")]);
+        fs::write(temp_dir.path().join("update.env"), "A=1\nB=20\nD=4\n").unwrap();
+        temp_dir
+    }
+
+    fn local_file(temp_dir: &TempDir) -> String {
+        fs::read_to_string(temp_dir.path().join(".envcli/local.env")).unwrap()
+    }
+
+    #[test]
+    fn test_keep_adds_new_keys_and_reports_kept() {
+        let temp_dir = create_with_update();
+
+        envcli(&temp_dir)
+            .args(["import", "update.env"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("新增    1  D"))
+            .stdout(predicate::str::contains("更新    0"))
+            .stdout(predicate::str::contains("未变    1\n"))
+            .stdout(predicate::str::contains("保留    1  B"));
+        assert_eq!(local_file(&temp_dir), "A=1\nB=2\nC=3\nD=4\n");
+    }
+
+    #[test]
+    fn test_overwrite_updates_changed_keys() {
+        let temp_dir = create_with_update();
+
+        envcli(&temp_dir)
+            .args(["-v", "import", "update.env", "--strategy", "overwrite"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("更新    1  B"))
+            .stdout(predicate::str::contains("未变    1  A"))
+            .stdout(predicate::str::contains("删除    0"));
+        assert_eq!(local_file(&temp_dir), "A=1\nB=20\nC=3\nD=4\n");
+    }
+
+    #[test]
+    fn test_prune_removes_missing_keys() {
+        let temp_dir = create_with_update();
+
+        envcli(&temp_dir)
+            .args(["import", "update.env", "--strategy", "prune"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("删除    1  C"));
+        assert_eq!(local_file(&temp_dir), "A=1\nB=20\nD=4\n");
+    }
+
+    #[test]
+    fn test_interactive_asks_per_conflict() {
+        let temp_dir = create_with_update();
+        fs::write(temp_dir.path().join("update.env"), "A=10\nB=20\n").unwrap();
+
+        envcli(&temp_dir)
+            .args(["import", "update.env", "--strategy", "interactive"])
+            .write_stdin("y\nn\n")
+            .assert()
+            .success()
+            .stderr(predicate::str::contains("A 已存在\n  当前: 1\n  导入: 10"))
+            .stdout(predicate::str::contains("更新    1  A"))
+            .stdout(predicate::str::contains("保留    1  B"));
+        assert_eq!(local_file(&temp_dir), "A=10\nB=2\nC=3\n");
+    }
+
+    #[test]
+    fn test_import_reads_stdin() {
+        let temp_dir = create_with_update();
+
+        envcli(&temp_dir)
+            .args(["import", "-", "--strategy", "overwrite"])
+            .write_stdin("{\"B\": \"json\"}")
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("从 标准输入（json）导入到 local"));
+        assert_eq!(local_file(&temp_dir), "A=1\nB=json\nC=3\n");
+
+        envcli(&temp_dir)
+            .args(["import", "-", "--strategy", "interactive"])
+            .write_stdin("B=x\n")
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("无法逐个询问"));
+    }
+
+    #[test]
+    fn test_import_keeps_references_interpolatable() {
+        let temp_dir = create_project(&[("local.env", "DB_HOST=db\n")]);
+        fs::write(
+            temp_dir.path().join("refs.env"),
+            "URL=postgres://${DB_HOST}/app\nQUOTED=\"${DB_HOST}:5432\"\nRAW='${DB_HOST}'\nPRICE=\"\\$5\"\n",
+        )
+        .unwrap();
+
+        envcli(&temp_dir)
+            .args(["import", "refs.env"])
+            .assert()
+            .success();
+
+        for (key, expected) in [
+            ("URL", "postgres://db/app"),
+            ("QUOTED", "db:5432"),
+            ("RAW", "${DB_HOST}"),
+            ("PRICE", "$5"),
+        ] {
+            envcli(&temp_dir)
+                .args(["get", key])
+                .assert()
+                .success()
+                .stdout(predicate::eq(format!("{}\n", expected)));
+        }
+    }
+
+    #[test]
+    fn test_reimport_reports_dollar_values_unchanged() {
+        let content = "HOST=db\nPW=\"pa\\$word\"\nRAW='a$b'\nTICK=`${HOST}`\nURL=${HOST}/x\n";
+        let temp_dir = create_project(&[("local.env", content)]);
+        fs::write(temp_dir.path().join("update.env"), content).unwrap();
+
+        envcli(&temp_dir)
+            .args(["-v", "import", "update.env", "--strategy", "overwrite"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("更新    0"))
+            .stdout(predicate::str::contains(
+                "未变    5  HOST, PW, RAW, TICK, URL",
+            ));
+        assert_eq!(local_file(&temp_dir), content);
+    }
+
+    #[test]
+    fn test_rejects_unknown_strategy() {
+        let temp_dir = create_with_update();
+        envcli(&temp_dir)
+            .args(["import", "update.env", "--strategy", "merge"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("无效的导入策略"));
+    }
+}
+
+mod diff_command {
+    use super::*;
+
+    const FILES: &[(&str, &str)] = &[
+        ("project.env", "A=1\nB=2\nAPI_TOKEN=abcdefghijkl\n"),
+        ("profiles/dev.env", "A=9\n"),
+    ];
+
+    #[test]
+    fn test_diff_layer_against_file() {
+        let temp_dir = create_project(FILES);
+        fs::write(
+            temp_dir.path().join("reference.env"),
+            "B=3\nC=4\nAPI_TOKEN=abcdefghijkz\n",
+        )
+        .unwrap();
+
+        envcli(&temp_dir)
+            .args(["diff", "project", "reference.env"])
+            .assert()
+            .code(1)
+            .stdout(
+                "--- project\n+++ reference.env\n- A=1\n~ API_TOKEN: ab****kl → ab****kz\n\
+                 ~ B: 2 → 3\n+ C=4\n4 处差异：新增 1，删除 1，修改 2\n",
+            );
+
+        envcli(&temp_dir)
+            .args(["--reveal", "diff", "project", "reference.env"])
+            .assert()
+            .code(1)
+            .stdout(predicate::str::contains(
+                "~ API_TOKEN: abcdefghijkl → abcdefghijkz",
+            ));
+    }
+
+    #[test]
+    fn test_diff_identical_sides_exit_zero() {
+        let temp_dir = create_project(FILES);
+        fs::copy(
+            temp_dir.path().join(".envcli/project.env"),
+            temp_dir.path().join("reference.env"),
+        )
+        .unwrap();
+
+        envcli(&temp_dir)
+            .args(["diff", "project", "reference.env"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("✓ 两侧相同（3 个变量）"));
+    }
+
+    #[test]
+    fn test_diff_layer_against_own_file_with_dollar_values() {
+        let temp_dir = create_project(&[("project.env", "P=\"a\\$b\"\nQ=${P}x\nR='c$d'\n")]);
+        envcli(&temp_dir)
+            .args(["set", "S", "e$f", "--target", "project"])
+            .assert()
+            .success();
+
+        envcli(&temp_dir)
+            .args(["diff", "project", ".envcli/project.env"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("✓ 两侧相同（4 个变量）"));
+
+        fs::write(
+            temp_dir.path().join("changed.env"),
+            "P='a$b'\nQ=$Px\nR='c$d'\n",
+        )
+        .unwrap();
+        envcli(&temp_dir)
+            .args(["diff", "project", "changed.env"])
+            .assert()
+            .code(1)
+            .stdout(
+                "--- project\n+++ changed.env\n~ Q: ${P}x → $Px\n- S=e$f\n2 处差异：新增 0，删除 1，修改 1\n",
+            );
+    }
+
+    #[test]
+    fn test_diff_decrypts_envelopes() {
+        let temp_dir = create_project(FILES);
+        for (target, value) in [("project", "same-secret"), ("profile:dev", "same-secret")] {
+            envcli(&temp_dir)
+                .args(["set", "DB_SEED", value, "--secret", "--target", target])
+                .assert()
+                .success();
+        }
+
+        // 密文的 nonce 不同，明文相同则不算差异
+        envcli(&temp_dir)
+            .args(["diff", "project", "profile:dev"])
+            .assert()
+            .code(1)
+            .stdout(predicate::str::contains("DB_SEED").not())
+            .stderr("");
+
+        envcli(&temp_dir)
+            .args(["set", "DB_SEED", "other-secret", "--secret"])
+            .args(["--target", "profile:dev"])
+            .assert()
+            .success();
+        envcli(&temp_dir)
+            .args(["diff", "project", "profile:dev"])
+            .assert()
+            .code(1)
+            .stdout(predicate::str::contains("~ DB_SEED: sa****et → ot****et"));
+    }
+
+    #[test]
+    fn test_diff_profiles_and_merged_view() {
+        let temp_dir = create_project(FILES);
+        fs::write(temp_dir.path().join(".envcli/local.env"), "B=5\n").unwrap();
+
+        envcli(&temp_dir)
+            .args(["diff", "profile:dev", "project"])
+            .assert()
+            .code(1)
+            .stdout(predicate::str::contains("~ A: 9 → 1"));
+
+        envcli(&temp_dir)
+            .args(["diff", "project", "merged"])
+            .assert()
+            .code(1)
+            .stdout(predicate::str::contains("~ B: 2 → 5"))
+            .stdout(predicate::str::contains("1 处差异"));
+    }
+
+    #[test]
+    fn test_diff_json_output() {
+        let temp_dir = create_project(FILES);
+        fs::write(
+            temp_dir.path().join("reference.json"),
+            "{\"A\": \"1\", \"B\": \"2\", \"API_TOKEN\": \"abcdefghijkz\"}",
+        )
+        .unwrap();
+
+        let output = envcli(&temp_dir)
+            .args(["diff", "project", "reference.json", "--format", "json"])
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(1));
+
+        let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(report["equal"], false);
+        assert_eq!(report["unchanged"], 2);
+        assert_eq!(report["changed"][0]["key"], "API_TOKEN");
+        assert_eq!(report["changed"][0]["new"], "ab****kz");
+        assert_eq!(report["changed"][0]["masked"], true);
+        assert_eq!(report["added"].as_array().unwrap().len(), 0);
+    }
+
+    #[test]
+    fn test_diff_missing_file_fails() {
+        let temp_dir = create_project(FILES);
+        envcli(&temp_dir)
+            .args(["diff", "project", "missing.env"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("文件不存在"));
+    }
+}
+
+mod explain_command {
+    use super::*;
+
+    const FILES: &[(&str, &str)] = &[
+        (
+            "project.env",
+            "DB_HOST=localhost\nDATABASE_URL=postgres://${DB_HOST}/app\n",
+        ),
+        (
+            "local.env",
+            "# 本地覆盖\nDATABASE_URL=postgres://${DB_HOST}:${DB_PORT:-5432}/app\n",
+        ),
+    ];
+
+    #[test]
+    fn test_explain_lists_definitions_and_interpolation() {
+        let temp_dir = create_project(FILES);
+
+        envcli(&temp_dir)
+            .args(["explain", "DATABASE_URL"])
+            .assert()
+            .success()
+            .stdout(predicate::str::starts_with(
+                "DATABASE_URL = postgres://localhost:5432/app\n",
+            ))
+            .stdout(predicate::str::contains(
+                "✓ local    .envcli/local.env:2    postgres://${DB_HOST}:${DB_PORT:-5432}/app\n",
+            ))
+            .stdout(predicate::str::contains(
+                "  project  .envcli/project.env:2  postgres://${DB_HOST}/app（被覆盖）",
+            ))
+            .stdout(predicate::str::contains(
+                "${DB_HOST} → localhost（project）",
+            ))
+            .stdout(predicate::str::contains("${DB_PORT} → 未定义"));
+    }
+
+    #[test]
+    fn test_get_explain_is_compact() {
+        let temp_dir = create_project(FILES);
+
+        envcli(&temp_dir)
+            .args(["get", "DATABASE_URL", "--explain"])
+            .assert()
+            .success()
+            .stdout(
+                "postgres://localhost:5432/app\n  ← local（.envcli/local.env:2），覆盖 project，插值 DB_HOST、DB_PORT\n",
+            );
+    }
+
+    #[test]
+    fn test_explain_masks_and_reports_decryption() {
+        let temp_dir = create_project(FILES);
+        envcli(&temp_dir)
+            .args(["set", "SIGNING_SEED", "plain-seed-value", "--secret"])
+            .assert()
+            .success();
+
+        envcli(&temp_dir)
+            .args(["explain", "SIGNING_SEED"])
+            .assert()
+            .success()
+            .stdout(predicate::str::starts_with("SIGNING_SEED = pl****ue\n"))
+            .stdout(predicate::str::contains("ENC[…]"))
+            .stdout(predicate::str::contains("解密: 生效值为加密信封"));
+
+        envcli(&temp_dir)
+            .args(["--reveal", "get", "SIGNING_SEED", "--explain"])
+            .assert()
+            .success()
+            .stdout(predicate::str::starts_with("plain-seed-value\n"))
+            .stdout(predicate::str::contains("，已解密"));
+    }
+
+    #[test]
+    fn test_explain_masks_secret_references() {
+        let temp_dir = create_project(&[(
+            "local.env",
+            "DB_PASS=hunter2secret # @secret\nDSN=postgres://app:${DB_PASS}@db/${DB_SEED}\n",
+        )]);
+        envcli(&temp_dir)
+            .args(["set", "DB_SEED", "plain-seed-value", "--secret"])
+            .assert()
+            .success();
+
+        envcli(&temp_dir)
+            .args(["explain", "DSN"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("${DB_PASS} → hu****et（local）"))
+            .stdout(predicate::str::contains("${DB_SEED} → pl****ue（local）"));
+
+        envcli(&temp_dir)
+            .args(["--reveal", "explain", "DSN"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "${DB_PASS} → hunter2secret（local）",
+            ));
+    }
+
+    #[test]
+    fn test_explain_json_output() {
+        let temp_dir = create_project(FILES);
+
+        let output = envcli(&temp_dir)
+            .args(["explain", "DATABASE_URL", "--format", "json"])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+
+        let explanation: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(explanation["value"], "postgres://localhost:5432/app");
+        let definitions = explanation["definitions"].as_array().unwrap();
+        assert_eq!(definitions.len(), 2);
+        assert_eq!(definitions[0]["source"], "Project");
+        assert_eq!(definitions[1]["source"], "Local");
+        assert_eq!(definitions[1]["line"], 2);
+        assert_eq!(explanation["references"][0]["name"], "DB_HOST");
+        assert_eq!(explanation["references"][0]["source"], "Project");
+    }
+
+    #[test]
+    fn test_explain_unknown_key_fails() {
+        let temp_dir = create_project(FILES);
+        envcli(&temp_dir)
+            .args(["explain", "ENVCLI_TEST_MISSING_KEY"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("变量未找到"));
+    }
+}
+
+mod list_options {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+
+    /// project.env 与 local.env 两层，project.env 比 local.env 早修改一小时
+    fn create_layers() -> TempDir {
+        let temp_dir = create_project(&[
+            (
+                "project.env",
+                "APP_ZETA=z\nAPP_HOST=example.com\nAPP_MODE=dev\n",
+            ),
+            (
+                "local.env",
+                "APP_MODE=debug\nAPP_ALPHA=a\nAPP_SECRET=hunter2222\n",
+            ),
+        ]);
+        let project = temp_dir.path().join(".envcli");
+
+        // project.env 比 local.env 早修改一小时
+        let hour_ago = SystemTime::now() - Duration::from_secs(3600);
+        fs::File::options()
+            .write(true)
+            .open(project.join("project.env"))
+            .unwrap()
+            .set_modified(hour_ago)
+            .unwrap();
+        temp_dir
+    }
+
+    #[test]
+    fn test_list_sorted_by_key_by_default() {
+        let temp_dir = create_layers();
+
+        envcli(&temp_dir)
+            .args(["list", "--prefix", "APP_"])
+            .assert()
+            .success()
+            .stdout(
+                "APP_ALPHA=a\nAPP_HOST=example.com\nAPP_MODE=debug\nAPP_SECRET=hu****22\nAPP_ZETA=z\n",
+            );
+    }
+
+    #[test]
+    fn test_list_sort_by_source_and_modified() {
+        let temp_dir = create_layers();
+
+        envcli(&temp_dir)
+            .args([
+                "list",
+                "--prefix",
+                "APP_",
+                "--sort",
+                "source",
+                "--show-source",
+            ])
+            .assert()
+            .success()
+            .stdout(
+                "APP_HOST=example.com  # project\nAPP_ZETA=z  # project\n\
+                 APP_ALPHA=a  # local\nAPP_MODE=debug  # local\nAPP_SECRET=hu****22  # local\n",
+            );
+
+        // local.env 最近修改，排在前面
+        envcli(&temp_dir)
+            .args(["list", "--prefix", "APP_", "--sort", "modified"])
+            .assert()
+            .success()
+            .stdout(
+                "APP_ALPHA=a\nAPP_MODE=debug\nAPP_SECRET=hu****22\nAPP_HOST=example.com\nAPP_ZETA=z\n",
+            );
+
+        envcli(&temp_dir)
+            .args(["list", "--sort", "size"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("无效的排序方式: size"));
+    }
+
+    #[test]
+    fn test_list_grep_matches_key_or_displayed_value() {
+        let temp_dir = create_layers();
+
+        envcli(&temp_dir)
+            .args(["list", "--grep", "^APP_(A|Z)|example"])
+            .assert()
+            .success()
+            .stdout("APP_ALPHA=a\nAPP_HOST=example.com\nAPP_ZETA=z\n");
+
+        // 敏感变量按掩码后的值匹配
+        envcli(&temp_dir)
+            .args(["list", "--grep", "hunter"])
+            .assert()
+            .success()
+            .stdout("");
+        envcli(&temp_dir)
+            .args(["--reveal", "list", "--grep", "hunter"])
+            .assert()
+            .success()
+            .stdout("APP_SECRET=hunter2222\n");
+
+        envcli(&temp_dir)
+            .args(["list", "--grep", "("])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("无效的正则表达式"));
+    }
+
+    #[test]
+    fn test_list_only_shadowed_table() {
+        let temp_dir = create_layers();
+
+        envcli(&temp_dir)
+            .args(["list", "--only-shadowed", "--prefix", "APP_", "-f", "table"])
+            .assert()
+            .success()
+            .stdout("KEY       VALUE  SOURCE\nAPP_MODE  debug  local（覆盖 project）\n");
+
+        envcli(&temp_dir)
+            .args(["list", "-s", "project", "--prefix", "APP_", "-f", "table"])
+            .assert()
+            .success()
+            .stdout(
+                "KEY       VALUE        SOURCE\n\
+                 APP_HOST  example.com  project\n\
+                 APP_MODE  dev          project（被 local 覆盖）\n\
+                 APP_ZETA  z            project\n",
+            );
+    }
+
+    #[test]
+    fn test_list_show_source_requires_readable_format() {
+        let temp_dir = create_layers();
+
+        envcli(&temp_dir)
+            .args(["list", "--show-source", "-f", "yaml"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("--show-source 仅适用于"));
+    }
+}
+
+#[cfg(unix)]
+mod run_process {
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+
+    #[test]
+    fn test_run_passes_exit_code_through() {
+        let temp_dir = create_test_env();
+
+        envcli(&temp_dir)
+            .args(["run", "--", "sh", "-c", "exit 7"])
+            .assert()
+            .code(7);
+    }
+
+    #[test]
+    fn test_run_replaces_envcli_process() {
+        let temp_dir = create_test_env();
+
+        // exec 之后目标命令的父进程就是测试进程本身
+        envcli(&temp_dir)
+            .args(["run", "--", "sh", "-c", "echo $PPID"])
+            .assert()
+            .success()
+            .stdout(format!("{}\n", std::process::id()));
+
+        let output = envcli(&temp_dir)
+            .args(["run", "--", "sh", "-c", "kill -TERM $$"])
+            .output()
+            .unwrap();
+        assert_eq!(output.status.signal(), Some(15));
+    }
+
+    #[test]
+    fn test_run_command_not_found_exits_127() {
+        let temp_dir = create_test_env();
+
+        envcli(&temp_dir)
+            .args(["run", "--", "envcli-no-such-command"])
+            .assert()
+            .code(127)
+            .stderr(predicate::str::contains(
+                "命令未找到: envcli-no-such-command",
+            ));
+    }
+}
+
+#[cfg(unix)]
+mod run_clean {
+    use super::*;
+
+    fn envcli(temp_dir: &TempDir) -> Command {
+        let mut command = super::envcli(temp_dir);
+        command
+            .env("HOST_ONLY", "1")
+            .env("LC_ENVCLI_TEST", "zh_CN.UTF-8")
+            .env("AWS_REGION", "us-east-1");
+        command
+    }
+
+    #[test]
+    fn test_run_clean_starts_from_empty_environment() {
+        let temp_dir = create_app_project();
+
+        envcli(&temp_dir)
+            .args(["run", "--clean", "--", "/usr/bin/env"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("APP_ENV=test"))
+            .stdout(predicate::str::contains("HOST_ONLY").not())
+            .stdout(predicate::str::contains("ENVCLI_HOME").not());
+    }
+
+    #[test]
+    fn test_run_clean_keep_and_print_env() {
+        let temp_dir = create_app_project();
+
+        envcli(&temp_dir)
+            .args([
+                "run",
+                "--clean",
+                "--keep",
+                "HOST_ONLY,NOT_SET_ANYWHERE",
+                "--keep-pattern",
+                "LC_ENVCLI_*",
+                "--print-env",
+            ])
+            .assert()
+            .success()
+            .stdout(
+                "API_TOKEN=ab****kl  # project\n\
+                 APP_ENV=test  # project\n\
+                 HOST_ONLY=1  # inherited\n\
+                 LC_ENVCLI_TEST=zh_CN.UTF-8  # inherited\n",
+            );
+
+        envcli(&temp_dir)
+            .args(["run", "--clean", "--print-env", "--env", "APP_ENV=override"])
+            .assert()
+            .success()
+            .stdout("API_TOKEN=ab****kl  # project\nAPP_ENV=override  # --env\n");
+    }
+
+    #[test]
+    fn test_run_drop_removes_inherited_variables() {
+        let temp_dir = create_app_project();
+
+        envcli(&temp_dir)
+            .args([
+                "run",
+                "--drop",
+                "AWS_*,HOST_ONLY",
+                "--",
+                "sh",
+                "-c",
+                "echo \"${AWS_REGION-unset} ${HOST_ONLY-unset} ${LC_ENVCLI_TEST} ${APP_ENV}\"",
+            ])
+            .assert()
+            .success()
+            .stdout("unset unset zh_CN.UTF-8 test\n");
+    }
+
+    #[test]
+    fn test_run_keep_requires_clean() {
+        let temp_dir = create_app_project();
+
+        envcli(&temp_dir)
+            .args(["run", "--keep", "PATH", "--", "true"])
+            .assert()
+            .failure();
+        envcli(&temp_dir)
+            .args(["run", "--clean", "--drop", "PATH", "--", "true"])
+            .assert()
+            .failure();
+    }
+}
+
+#[cfg(unix)]
+mod run_watch {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read};
+    use std::process::{Child, Stdio};
+    use std::sync::mpsc::{self, Receiver};
+    use std::time::Duration;
+
+    /// 启动 `envcli run --watch`，逐行读取其标准输出
+    fn spawn_watch(temp_dir: &TempDir, args: &[&str]) -> (Child, Receiver<String>) {
+        let mut child = envcli_process(temp_dir)
+            .arg("run")
+            .arg("--watch")
+            .args(args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        let stdout = child.stdout.take().unwrap();
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(|l| l.ok()) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        (child, receiver)
+    }
+
+    fn next_line(lines: &Receiver<String>) -> String {
+        lines
+            .recv_timeout(Duration::from_secs(10))
+            .expect("等待子进程输出超时")
+    }
+
+    /// 向 envcli 发送 SIGTERM，返回退出码与标准错误
+    fn terminate(mut child: Child) -> (Option<i32>, String) {
+        std::process::Command::new("kill")
+            .args(["-TERM", &child.id().to_string()])
+            .status()
+            .unwrap();
+        let status = child.wait().unwrap();
+        let mut stderr = String::new();
+        child
+            .stderr
+            .take()
+            .unwrap()
+            .read_to_string(&mut stderr)
+            .unwrap();
+        (status.code(), stderr)
+    }
+
+    #[test]
+    fn test_watch_restarts_when_environment_changes() {
+        let temp_dir = create_project(&[("local.env", "APP_ENV=one\nAPI_TOKEN=abcdefghijkl\n")]);
+        let (child, lines) = spawn_watch(
+            &temp_dir,
+            &["--", "sh", "-c", "echo \"start $APP_ENV\"; exec sleep 30"],
+        );
+        assert_eq!(next_line(&lines), "start one");
+
+        fs::write(
+            temp_dir.path().join(".envcli/local.env"),
+            "APP_ENV=second\nAPI_TOKEN=abcdefghijkz\n",
+        )
+        .unwrap();
+        assert_eq!(next_line(&lines), "start second");
+
+        // 转发的 SIGTERM 结束子进程后，以 128 + 15 退出
+        let (code, stderr) = terminate(child);
+        assert_eq!(code, Some(143));
+        assert!(stderr.contains(".envcli/local.env 已修改"), "{}", stderr);
+        assert!(
+            stderr.contains("~ API_TOKEN: ab****kl → ab****kz"),
+            "{}",
+            stderr
+        );
+        assert!(stderr.contains("~ APP_ENV: one → second"), "{}", stderr);
+        assert!(stderr.contains("重新启动 sh"), "{}", stderr);
+    }
+
+    #[test]
+    fn test_watch_reload_signal_keeps_process() {
+        let temp_dir = create_project(&[("local.env", "APP_ENV=one\nAPI_TOKEN=abcdefghijkl\n")]);
+        let (child, lines) = spawn_watch(
+            &temp_dir,
+            &[
+                "--reload-signal",
+                "HUP",
+                "--",
+                "sh",
+                "-c",
+                "trap 'echo reload' HUP; echo \"start $APP_ENV\"; while :; do sleep 0.1; done",
+            ],
+        );
+        assert_eq!(next_line(&lines), "start one");
+
+        fs::write(temp_dir.path().join(".envcli/project.env"), "NEW_FLAG=1\n").unwrap();
+        assert_eq!(next_line(&lines), "reload");
+
+        let (code, stderr) = terminate(child);
+        assert_eq!(code, Some(143));
+        assert!(stderr.contains("+ NEW_FLAG=1"), "{}", stderr);
+        assert!(stderr.contains("向 sh 发送 SIGHUP"), "{}", stderr);
+        assert!(!stderr.contains("重新启动"), "{}", stderr);
+    }
+
+    #[test]
+    fn test_watch_rejects_unknown_signal() {
+        let temp_dir = create_project(&[("local.env", "APP_ENV=one\nAPI_TOKEN=abcdefghijkl\n")]);
+
+        envcli(&temp_dir)
+            .args(["run", "--watch", "--reload-signal", "BOGUS", "--", "true"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("无效的信号: BOGUS"));
+    }
+}
+
+#[cfg(unix)]
+mod up_command {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::process::Stdio;
+
+    #[test]
+    fn test_up_overlays_env_and_stops_on_first_exit() {
+        let temp_dir = create_app_project();
+        fs::write(
+            temp_dir.path().join("Procfile"),
+            "web: echo web $APP_ENV $MODE; sleep 30\nworker: sleep 0.3; echo worker $MODE; exit 3\n",
+        )
+        .unwrap();
+        fs::write(
+            temp_dir.path().join(".envcli/config.toml"),
+            "[processes.web]\nenv = { MODE = \"frontend\" }\n",
+        )
+        .unwrap();
+
+        envcli(&temp_dir)
+            .arg("up")
+            .timeout(std::time::Duration::from_secs(20))
+            .assert()
+            .code(3)
+            .stdout(predicate::str::contains("web    | web test frontend"))
+            .stdout(predicate::str::contains("worker | worker\n"))
+            .stdout(predicate::str::contains("envcli | worker 已退出（状态 3）"))
+            .stdout(predicate::str::contains("envcli | 停止其余进程"));
+    }
+
+    #[test]
+    fn test_up_exits_with_first_failure_after_clean_exit() {
+        let temp_dir = create_app_project();
+        // web 先成功退出；worker 收到 SIGTERM 后以状态 4 退出，api 被信号结束
+        fs::write(
+            temp_dir.path().join("Procfile"),
+            "web: sleep 0.3\nworker: trap 'kill $!; exit 4' TERM; sleep 30 & wait\napi: exec sleep 30\n",
+        )
+        .unwrap();
+
+        envcli(&temp_dir)
+            .arg("up")
+            .timeout(std::time::Duration::from_secs(20))
+            .assert()
+            .code(4)
+            .stdout(predicate::str::contains("envcli | web 已退出（状态 0）"))
+            .stdout(predicate::str::contains("envcli | worker 已退出（状态 4）"))
+            .stdout(predicate::str::contains("envcli | api 已退出（状态 143）"));
+
+        fs::write(
+            temp_dir.path().join("Procfile"),
+            "web: sleep 0.3\napi: exec sleep 30\n",
+        )
+        .unwrap();
+        envcli(&temp_dir)
+            .arg("up")
+            .timeout(std::time::Duration::from_secs(20))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("envcli | api 已退出（状态 143）"));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_up_stops_processes_started_by_the_shell() {
+        let temp_dir = create_app_project();
+        fs::write(
+            temp_dir.path().join("Procfile"),
+            "web: sleep 77.25; echo done\nquick: sleep 1; exit 3\n",
+        )
+        .unwrap();
+
+        envcli(&temp_dir)
+            .arg("up")
+            .timeout(std::time::Duration::from_secs(20))
+            .assert()
+            .code(3)
+            .stdout(predicate::str::contains("web    | done").not());
+
+        // 进程收到信号后异步退出，稍等片刻再检查
+        let survivor = || {
+            fs::read_dir("/proc").unwrap().flatten().any(|entry| {
+                fs::read(entry.path().join("cmdline"))
+                    .is_ok_and(|cmdline| cmdline == b"sleep\x0077.25\x00")
+            })
+        };
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(2);
+        while survivor() && std::time::Instant::now() < deadline {
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        }
+        assert!(!survivor(), "sleep 77.25 在 up 退出后仍在运行");
+    }
+
+    #[test]
+    fn test_up_starts_selected_processes_from_config() {
+        let temp_dir = create_app_project();
+        fs::write(
+            temp_dir.path().join(".envcli/config.toml"),
+            "[processes]\na = \"echo a $APP_ENV\"\nb = { command = \"exit 9\" }\n",
+        )
+        .unwrap();
+
+        envcli(&temp_dir)
+            .args(["up", "a"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("a      | a test"))
+            .stdout(predicate::str::contains("b 已启动").not());
+    }
+
+    #[test]
+    fn test_up_forwards_sigterm_to_all_processes() {
+        let temp_dir = create_app_project();
+        fs::write(
+            temp_dir.path().join("Procfile"),
+            "one: sleep 30\ntwo: sleep 30\n",
+        )
+        .unwrap();
+
+        let mut child = envcli_process(&temp_dir)
+            .arg("up")
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut lines = BufReader::new(child.stdout.take().unwrap()).lines();
+        let mut started = 0;
+        while started < 2 {
+            let line = lines.next().unwrap().unwrap();
+            if line.contains("已启动") {
+                started += 1;
+            }
+        }
+
+        std::process::Command::new("kill")
+            .args(["-TERM", &child.id().to_string()])
+            .status()
+            .unwrap();
+        let rest: Vec<String> = lines.map_while(|l| l.ok()).collect();
+        assert_eq!(child.wait().unwrap().code(), Some(143));
+        assert!(
+            rest.iter()
+                .any(|l| l.contains("收到 SIGTERM，停止所有进程"))
+        );
+        assert!(rest.iter().any(|l| l.contains("one 已退出（状态 143）")));
+        assert!(rest.iter().any(|l| l.contains("two 已退出（状态 143）")));
+    }
+
+    #[test]
+    fn test_up_reports_missing_and_unknown_processes() {
+        let temp_dir = create_app_project();
+
+        envcli(&temp_dir)
+            .arg("up")
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("未找到要启动的进程"));
+
+        fs::write(temp_dir.path().join("Procfile"), "web: true\n").unwrap();
+        envcli(&temp_dir)
+            .args(["up", "api"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("未知进程: api（可选 web）"));
+
+        envcli(&temp_dir)
+            .args(["up", "--procfile", "Procfile.dev"])
+            .assert()
+            .failure();
+    }
+
+    #[test]
+    fn test_up_rejects_invalid_process_config() {
+        let temp_dir = create_app_project();
+        fs::write(temp_dir.path().join("Procfile"), "web: true\n").unwrap();
+        fs::write(
+            temp_dir.path().join(".envcli/config.toml"),
+            "[processes.web]\nenv = { \"BAD KEY\" = \"x\" }\n",
+        )
+        .unwrap();
+
+        envcli(&temp_dir)
+            .arg("up")
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("processes"));
+
+        fs::write(temp_dir.path().join(".envcli/config.toml"), "").unwrap();
+        fs::write(temp_dir.path().join("Procfile"), "web true\n").unwrap();
+        envcli(&temp_dir)
+            .arg("up")
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Procfile 第 1 行"));
+    }
+}
+
+#[cfg(unix)]
+mod run_secrets_as_files {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::path::PathBuf;
+    use std::process::Stdio;
+
+    #[test]
+    fn test_secrets_are_passed_as_private_files() {
+        let temp_dir = create_app_project();
+        let script = "echo \"token=$API_TOKEN app=$APP_ENV\"; \
+                      cat \"$API_TOKEN_FILE\"; echo; \
+                      stat -c %a \"$API_TOKEN_FILE\" \"$(dirname \"$API_TOKEN_FILE\")\"; \
+                      echo \"$API_TOKEN_FILE\"; exit 4";
+
+        let output = envcli(&temp_dir)
+            .args(["run", "--secrets-as-files", "--", "sh", "-c", script])
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(4));
+
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let lines: Vec<&str> = stdout.lines().collect();
+        assert_eq!(lines[0], "token= app=test");
+        assert_eq!(lines[1], "abcdefghijkl");
+        assert_eq!(lines[2..4], ["600", "700"]);
+
+        // 子进程退出后临时目录被删除
+        let file = PathBuf::from(lines[4]);
+        assert!(file.ends_with("API_TOKEN"));
+        assert!(!file.parent().unwrap().exists());
+    }
+
+    #[test]
+    fn test_secret_dir_removed_when_envcli_is_signalled() {
+        let temp_dir = create_app_project();
+
+        let mut child = envcli_process(&temp_dir)
+            .args(["run", "--secrets-as-files", "--"])
+            .args(["sh", "-c", "echo $API_TOKEN_FILE; exec sleep 30"])
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut lines = BufReader::new(child.stdout.take().unwrap()).lines();
+        let file = PathBuf::from(lines.next().unwrap().unwrap());
+        assert!(file.exists());
+
+        std::process::Command::new("kill")
+            .args(["-TERM", &child.id().to_string()])
+            .status()
+            .unwrap();
+        assert_eq!(child.wait().unwrap().code(), Some(143));
+        assert!(!file.parent().unwrap().exists());
+    }
+
+    #[test]
+    fn test_secrets_as_files_print_env_and_conflicts() {
+        let temp_dir = create_app_project();
+
+        envcli(&temp_dir)
+            .args(["run", "--secrets-as-files", "--print-env"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "API_TOKEN_FILE=<临时目录>/API_TOKEN  # --secrets-as-files",
+            ))
+            .stdout(predicate::str::contains("API_TOKEN=").not())
+            .stdout(predicate::str::contains("APP_ENV=test  # project"));
+
+        envcli(&temp_dir)
+            .args(["run", "--secrets-as-files", "--env", "API_TOKEN_FILE=/x"])
+            .args(["--", "true"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(
+                "API_TOKEN_FILE 已定义，无法以文件传递 API_TOKEN",
+            ));
+    }
+}